@@ -1,199 +1,13245 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
-#[ink::contract]
+/// Implemented by contracts that want to be notified when `Foobar` flips.
+#[ink::trait_definition]
+pub trait OnFlip {
+    /// Called by `Foobar` with the new value after every flip.
+    #[ink(message)]
+    fn on_flip(&mut self, new_value: bool);
+}
+
+/// Signature topics of the events emitted by `Foobar`, for indexers that
+/// filter on them.
+///
+/// Each event is identified by its path, e.g. `Foobar::Flipped`, from which
+/// `signature_topic` derives the topic the same way ink! does. The anonymous
+/// `FlippedLite` has no signature topic and is therefore missing here.
+pub mod event_topics {
+    use ink::env::hash::{Blake2x256, CryptoHash};
+    use ink::prelude::vec::Vec;
+
+    pub const CREATED: &str = "Foobar::Created";
+    pub const FLIPPED: &str = "Foobar::Flipped";
+    pub const OPERATION_REJECTED: &str = "Foobar::OperationRejected";
+    pub const MESSAGE_CHANGED: &str = "Foobar::MessageChanged";
+    pub const DOCUMENT_HASH_SET: &str = "Foobar::DocumentHashSet";
+    pub const NOTE_CHANGED: &str = "Foobar::NoteChanged";
+    pub const CONFIG_CHANGED: &str = "Foobar::ConfigChanged";
+    pub const ALLOWLIST_UPDATED: &str = "Foobar::AllowlistUpdated";
+    pub const ACTION_QUEUED: &str = "Foobar::ActionQueued";
+    pub const ACTION_EXECUTED: &str = "Foobar::ActionExecuted";
+    pub const ACTION_CANCELLED: &str = "Foobar::ActionCancelled";
+    pub const ACCOUNT_BANNED: &str = "Foobar::AccountBanned";
+    pub const ACCOUNT_UNBANNED: &str = "Foobar::AccountUnbanned";
+    pub const ROLE_GRANTED: &str = "Foobar::RoleGranted";
+    pub const ROLE_REVOKED: &str = "Foobar::RoleRevoked";
+    pub const OWNERSHIP_RENOUNCED: &str = "Foobar::OwnershipRenounced";
+    pub const OWNER_PROPOSED: &str = "Foobar::OwnerProposed";
+    pub const OWNERSHIP_TRANSFERRED: &str = "Foobar::OwnershipTransferred";
+    pub const VOTE_CAST: &str = "Foobar::VoteCast";
+    pub const PAUSED: &str = "Foobar::Paused";
+    pub const UNPAUSED: &str = "Foobar::Unpaused";
+    pub const PERSONAL_FLIPPED: &str = "Foobar::PersonalFlipped";
+    pub const OPERATOR_APPROVAL: &str = "Foobar::OperatorApproval";
+    pub const FLIPPED_WITH_REASON: &str = "Foobar::FlippedWithReason";
+    pub const STATUS_CHANGED: &str = "Foobar::StatusChanged";
+    pub const AUCTION_CREATED: &str = "Foobar::AuctionCreated";
+    pub const BID_PLACED: &str = "Foobar::BidPlaced";
+    pub const AUCTION_ENDED: &str = "Foobar::AuctionEnded";
+    pub const AUCTION_FINALIZED: &str = "Foobar::AuctionFinalized";
+    pub const AUCTION_CANCELLED: &str = "Foobar::AuctionCancelled";
+    pub const RESERVE_NOT_MET: &str = "Foobar::ReserveNotMet";
+    pub const DEADLINE_EXTENDED: &str = "Foobar::DeadlineExtended";
+    pub const AUCTION_TRANSFERRED: &str = "Foobar::AuctionTransferred";
+    pub const BOUGHT_NOW: &str = "Foobar::BoughtNow";
+    pub const EXPIRED_FINALIZED: &str = "Foobar::ExpiredFinalized";
+    pub const BENEFICIARY_CHANGED: &str = "Foobar::BeneficiaryChanged";
+    pub const FEES_WITHDRAWN: &str = "Foobar::FeesWithdrawn";
+    pub const DEPOSITED: &str = "Foobar::Deposited";
+    pub const TERMINATED: &str = "Foobar::Terminated";
+    pub const SWEPT_OUT: &str = "Foobar::SweptOut";
+    pub const DONATION_RECEIVED: &str = "Foobar::DonationReceived";
+    pub const DONATIONS_WITHDRAWN: &str = "Foobar::DonationsWithdrawn";
+
+    /// Paths of all events that carry a signature topic.
+    pub const ALL: &[&str] = &[
+        CREATED,
+        FLIPPED,
+        OPERATION_REJECTED,
+        MESSAGE_CHANGED,
+        DOCUMENT_HASH_SET,
+        NOTE_CHANGED,
+        CONFIG_CHANGED,
+        ALLOWLIST_UPDATED,
+        ACTION_QUEUED,
+        ACTION_EXECUTED,
+        ACTION_CANCELLED,
+        ACCOUNT_BANNED,
+        ACCOUNT_UNBANNED,
+        ROLE_GRANTED,
+        ROLE_REVOKED,
+        OWNERSHIP_RENOUNCED,
+        OWNER_PROPOSED,
+        OWNERSHIP_TRANSFERRED,
+        VOTE_CAST,
+        PAUSED,
+        UNPAUSED,
+        PERSONAL_FLIPPED,
+        OPERATOR_APPROVAL,
+        FLIPPED_WITH_REASON,
+        STATUS_CHANGED,
+        AUCTION_CREATED,
+        BID_PLACED,
+        AUCTION_ENDED,
+        AUCTION_FINALIZED,
+        AUCTION_CANCELLED,
+        RESERVE_NOT_MET,
+        DEADLINE_EXTENDED,
+        AUCTION_TRANSFERRED,
+        BOUGHT_NOW,
+        EXPIRED_FINALIZED,
+        BENEFICIARY_CHANGED,
+        FEES_WITHDRAWN,
+        DEPOSITED,
+        TERMINATED,
+        SWEPT_OUT,
+        DONATION_RECEIVED,
+        DONATIONS_WITHDRAWN,
+    ];
+
+    /// Returns the signature topic of the event at `path`.
+    pub fn signature_topic(path: &str) -> [u8; 32] {
+        // ink! encodes an empty byte slice prefix, i.e. a single zero length
+        // byte, followed by the raw bytes of the path.
+        let mut encoded = Vec::with_capacity(path.len() + 1);
+        encoded.push(0);
+        encoded.extend_from_slice(path.as_bytes());
+        let mut topic = [0u8; 32];
+        if encoded.len() <= topic.len() {
+            topic[..encoded.len()].copy_from_slice(&encoded);
+        } else {
+            Blake2x256::hash(&encoded, &mut topic);
+        }
+        topic
+    }
+}
+
+/// sr25519 signature verification, only built with the `sr25519` feature.
+///
+/// On chain this calls the `sr25519_verify` host function of pallet-contracts.
+/// It is part of the pallet's unstable interface, so the runtime has to set
+/// `Config::UnsafeUnstableInterface` to `ConstBool<true>`, as
+/// substrate-contracts-node does. Uploading a contract built with this feature
+/// fails on runtimes that don't. Off chain, signatures are checked with
+/// schnorrkel so unit tests see the same results as the node.
+#[cfg(feature = "sr25519")]
+pub mod sr25519 {
+    /// Signing context used by Substrate for sr25519 signatures.
+    pub const SIGNING_CONTEXT: &[u8] = b"substrate";
+
+    #[cfg(target_arch = "wasm32")]
+    mod host {
+        #[link(wasm_import_module = "seal0")]
+        extern "C" {
+            pub fn sr25519_verify(
+                signature_ptr: *const u8,
+                pub_key_ptr: *const u8,
+                message_len: u32,
+                message_ptr: *const u8,
+            ) -> u32;
+        }
+    }
+
+    /// Returns whether `signature` is a valid signature of `message` by
+    /// `pubkey` in the `SIGNING_CONTEXT` context.
+    #[cfg(target_arch = "wasm32")]
+    pub fn verify(signature: &[u8; 64], message: &[u8], pubkey: &[u8; 32]) -> bool {
+        // The host returns `ReturnCode::Success`, i.e. 0, for valid signatures.
+        // SAFETY: all pointers are valid for the lengths the host reads.
+        unsafe {
+            host::sr25519_verify(
+                signature.as_ptr(),
+                pubkey.as_ptr(),
+                message.len() as u32,
+                message.as_ptr(),
+            ) == 0
+        }
+    }
+
+    /// Returns whether `signature` is a valid signature of `message` by
+    /// `pubkey` in the `SIGNING_CONTEXT` context.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn verify(signature: &[u8; 64], message: &[u8], pubkey: &[u8; 32]) -> bool {
+        let Ok(pubkey) = schnorrkel::PublicKey::from_bytes(pubkey) else {
+            return false;
+        };
+        let Ok(signature) = schnorrkel::Signature::from_bytes(signature) else {
+            return false;
+        };
+        pubkey.verify_simple(SIGNING_CONTEXT, message, &signature).is_ok()
+    }
+}
+
+/// Function id of `RandomnessExtension::fetch_random`.
+///
+/// pallet-contracts splits the id into the extension id in the upper and the
+/// function id in the lower 16 bits, so the runtime's chain extension has to
+/// answer extension id 0, function id 1101. It gets the SCALE encoded
+/// `[u8; 32]` subject as input and has to write 32 random bytes to the
+/// output, returning status 0 on success and 1 if it has no randomness.
+pub const FETCH_RANDOM_FUNC_ID: u32 = 1101;
+
+/// Randomness provided by the runtime, used by `random_flip` with the
+/// `chain-ext` feature.
+#[cfg(feature = "chain-ext")]
+#[ink::chain_extension]
+pub trait RandomnessExtension {
+    type ErrorCode = RandomReadErr;
+
+    /// Returns randomness derived by the runtime for `subject`.
+    #[ink(extension = 1101)]
+    fn fetch_random(subject: [u8; 32]) -> [u8; 32];
+}
+
+/// Status codes of `RandomnessExtension`, see `FETCH_RANDOM_FUNC_ID`.
+#[cfg(feature = "chain-ext")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum RandomReadErr {
+    /// The runtime has no randomness source available.
+    FailGetRandomSource,
+}
+
+#[cfg(feature = "chain-ext")]
+impl ink::env::chain_extension::FromStatusCode for RandomReadErr {
+    fn from_status_code(status_code: u32) -> Result<(), Self> {
+        match status_code {
+            0 => Ok(()),
+            _ => Err(Self::FailGetRandomSource),
+        }
+    }
+}
+
+/// The default environment with `RandomnessExtension` plugged in, used as the
+/// contract's environment with the `chain-ext` feature.
+#[cfg(feature = "chain-ext")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum FoobarEnvironment {}
+
+#[cfg(feature = "chain-ext")]
+impl ink::env::Environment for FoobarEnvironment {
+    const MAX_EVENT_TOPICS: usize = <ink::env::DefaultEnvironment as ink::env::Environment>::MAX_EVENT_TOPICS;
+
+    type AccountId = <ink::env::DefaultEnvironment as ink::env::Environment>::AccountId;
+    type Balance = <ink::env::DefaultEnvironment as ink::env::Environment>::Balance;
+    type Hash = <ink::env::DefaultEnvironment as ink::env::Environment>::Hash;
+    type BlockNumber = <ink::env::DefaultEnvironment as ink::env::Environment>::BlockNumber;
+    type Timestamp = <ink::env::DefaultEnvironment as ink::env::Environment>::Timestamp;
+
+    type ChainExtension = RandomnessExtension;
+}
+
+/// Logs a line with `ink::env::debug_println!` when the `debug` feature is
+/// enabled and expands to nothing otherwise.
+macro_rules! debug_log {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "debug")]
+        ink::env::debug_println!($($arg)*);
+    };
+}
+
+#[cfg_attr(not(feature = "chain-ext"), ink::contract)]
+#[cfg_attr(feature = "chain-ext", ink::contract(env = crate::FoobarEnvironment))]
 mod foobar {
+    use ink::env::call::{build_call, ExecutionInput, Selector};
+    use ink::env::hash::{Blake2x256, Keccak256};
     use ink::prelude::string::String;
     use ink::prelude::vec::Vec;
-    
+    use ink::storage::{Lazy, Mapping};
+    use scale::Encode;
+
+    /// Example of a composite type passed through the message ABI, see
+    /// `get_my_struct` and `set_my_struct`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
     pub struct MyStruct {
         /// Stores a single `bool` value on the storage.
+        pub value: bool,
+        /// At most `MAX_MESSAGE_LEN` bytes long.
+        pub my_string: String,
+        /// At most `MAX_NUMBERS` entries long.
+        pub my_vector: Vec<u32>,
+        pub my_account: AccountId,
+        pub my_balance: Balance,
+        pub my_hash: Hash,
+    }
+
+    impl MyStruct {
+        /// Returns a struct that is empty apart from `my_account`.
+        fn empty(my_account: AccountId) -> Self {
+            Self {
+                value: false,
+                my_string: String::new(),
+                my_vector: Vec::new(),
+                my_account,
+                my_balance: 0,
+                my_hash: Hash::default(),
+            }
+        }
+    }
+
+    /// Lifecycle state of an auction, see `status`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum Status {
+        NotStarted,
+        OpeningPeriod,
+        EndingPeriod,
+        Ended,
+        Cancelled,
+    }
+
+    /// How bids are placed on an auction.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum AuctionKind {
+        /// Open ascending bids through `place_bid`.
+        English,
+        /// Commit-reveal bids through `commit_bid` and `reveal_bid`.
+        Sealed,
+        /// A price falling from `start_price` to `floor_price`, paid with `buy`.
+        Dutch,
+    }
+
+    /// Permission that can be granted to accounts, see `grant_role`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum Role {
+        /// May grant and revoke roles and toggle `restricted_mode`.
+        Admin,
+        /// May flip while `restricted_mode` is on.
+        Flipper,
+    }
+
+    /// Configuration change that goes through the timelock, see
+    /// `queue_action`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum AdminAction {
+        /// Same as `set_fee_bps`.
+        SetFeeBps(u16),
+        /// Same as `pause`.
+        Pause,
+        /// Same as `unpause`.
+        Unpause,
+        /// Same as `transfer_ownership`.
+        TransferOwnership(AccountId),
+    }
+
+    /// An admin action waiting in the timelock.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct QueuedAction {
+        pub action: AdminAction,
+        pub queued_at: BlockNumber,
+    }
+
+    /// What the winner of an auction pays.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum SettlementKind {
+        /// The highest bid.
+        FirstPrice,
+        /// The second-highest bid, but at least the reserve price. Without a
+        /// second bid, the reserve price or, if there is none, the bid itself.
+        SecondPrice,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Auction {
+        name: String,
+        seller: AccountId,
+        subject: Hash,
+        status: Status,
+        finalized: bool,
+        vector: Vec<u8>,
+        highest_bid: Balance,
+        highest_bidder: Option<AccountId>,
+        min_increment: Balance,
+        reserve_price: Balance,
+        end_timestamp: Timestamp,
+        extension_window_ms: u64,
+        extension_ms: u64,
+        max_extensions: u32,
+        extensions: u32,
+        kind: AuctionKind,
+        reveal_end: Timestamp,
+        start_timestamp: Timestamp,
+        start_price: Balance,
+        floor_price: Balance,
+        restricted: bool,
+        fee_bps: u16,
+        buy_now_price: Option<Balance>,
+        settlement: SettlementKind,
+        second_bid: Balance,
+    }
+
+    /// A sealed bid committed with `commit_bid`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct SealedBid {
+        commitment: Hash,
+        deposit: Balance,
+        revealed: bool,
+    }
+
+    /// Overview of an auction returned by `list_auctions` and `list_by_status`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct AuctionSummary {
+        pub id: u32,
+        pub name: String,
+        pub status: Status,
+        pub highest_bid: Balance,
+        pub end_timestamp: Timestamp,
+    }
+
+    /// Aggregates over the number vector returned by `stats`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct NumberStats {
+        pub min: u32,
+        pub max: u32,
+        pub sum: u64,
+        pub count: u32,
+    }
+
+    /// Accepted bids as `(amount, timestamp)` pairs, returned by `bids_of`.
+    pub type BidHistory = Vec<(Balance, Timestamp)>;
+
+    /// Maximum number of bids remembered per bidder and auction by `bids_of`.
+    pub const MAX_BIDS_PER_BIDDER: usize = 20;
+
+    /// Maximum number of entries returned by one auction listing call.
+    pub const MAX_PAGE_SIZE: u32 = 50;
+
+    /// Highest protocol fee in basis points, i.e. 10%.
+    pub const MAX_FEE_BPS: u16 = 1_000;
+
+    /// Maximum number of flips remembered in the on-chain history.
+    pub const HISTORY_CAPACITY: usize = 32;
+
+    /// A single entry of the on-chain flip history.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct FlipRecord {
+        caller: AccountId,
+        new_value: bool,
+        block: BlockNumber,
+    }
+
+    /// Maximum size of the multisig signer set.
+    pub const MAX_SIGNERS: usize = 16;
+
+    /// Action tag included in the payload signed for `flip_with_signature`.
+    pub const FLIP_ACTION: [u8; 4] = *b"flip";
+
+    /// Default maximum number of value change points kept for `value_at`.
+    pub const DEFAULT_MAX_CHANGE_POINTS: u32 = 64;
+
+    /// Maximum length in bytes of a reason passed to `flip_with_reason`.
+    pub const MAX_REASON_LEN: usize = 128;
+
+    /// Maximum length in bytes of the message set with `set_message`.
+    pub const MAX_MESSAGE_LEN: usize = 256;
+
+    /// Maximum length in bytes of a note set with `set_my_note`.
+    pub const MAX_NOTE_LEN: usize = 160;
+
+    /// Maximum number of entries in the number vector, see `push_number`.
+    pub const MAX_NUMBERS: usize = 1024;
+
+    /// Maximum length in bytes of the input to `hash_blake2` and `hash_keccak`.
+    pub const MAX_HASH_INPUT_LEN: usize = 16 * 1024;
+
+    /// Proof-of-work difficulty of `pow_flip` until the owner changes it.
+    pub const DEFAULT_POW_DIFFICULTY: u8 = 8;
+
+    /// Maximum difficulty accepted by `set_pow_difficulty`.
+    pub const MAX_POW_DIFFICULTY: u8 = 24;
+
+    /// Maximum cooldown accepted by `try_new`, about a week of 6 second blocks.
+    pub const MAX_COOLDOWN_BLOCKS: u32 = 100_800;
+
+    /// Maximum number of accounts passed to one `add_many_to_allowlist` call.
+    pub const MAX_ALLOWLIST_BATCH: usize = 32;
+
+    /// Values of `Created::constructor`, telling which constructor ran.
+    pub const CONSTRUCTOR_NEW: u8 = 0;
+    pub const CONSTRUCTOR_DEFAULT: u8 = 1;
+    pub const CONSTRUCTOR_WITH_COOLDOWN: u8 = 2;
+    pub const CONSTRUCTOR_WITH_EXPIRY: u8 = 3;
+    pub const CONSTRUCTOR_WITH_RATE_LIMIT: u8 = 4;
+    pub const CONSTRUCTOR_WITH_TIMELOCK: u8 = 5;
+    pub const CONSTRUCTOR_WITH_FEE: u8 = 6;
+    pub const CONSTRUCTOR_MULTISIG: u8 = 7;
+    pub const CONSTRUCTOR_TRY_NEW: u8 = 8;
+    pub const CONSTRUCTOR_WITH_FLIP_FEE: u8 = 9;
+    pub const CONSTRUCTOR_FUNDED: u8 = 10;
+    pub const CONSTRUCTOR_WITH_TREASURY: u8 = 11;
+    pub const CONSTRUCTOR_WITH_STRICT_VALUE_CHECKS: u8 = 12;
+
+    /// Returned by `expected_value_of` for messages whose value depends on
+    /// the call, like a bid.
+    pub const VALUE_DEPENDS_ON_CALL: Balance = Balance::MAX;
+
+    /// Number of entries kept on the flip leaderboard.
+    pub const LEADERBOARD_SIZE: usize = 10;
+
+    /// Number of entries kept in `top_donors`.
+    pub const TOP_DONORS_SIZE: usize = 5;
+
+    /// Snapshot of the contract state returned by `info`.
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct FoobarInfo {
+        pub value: bool,
+        pub flip_count: u64,
+        pub last_flipper: Option<AccountId>,
+        pub last_flip_block: Option<BlockNumber>,
+        pub paused: bool,
+    }
+
+    /// How the contract sees the caller of `caller_context`.
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct CallerContext {
+        pub caller: AccountId,
+        pub is_owner: bool,
+        pub is_allowed: bool,
+        pub is_banned: bool,
+        pub cooldown_remaining: BlockNumber,
+        pub deposit: Balance,
+    }
+
+    /// Resource usage reported by `debug_meter`, which only exists with the
+    /// `debug` feature.
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct MeterInfo {
+        pub gas_left: u64,
+        pub balance: Balance,
+        pub minimum_balance: Balance,
+        /// Encoded size in bytes of the root storage struct, without the
+        /// entries of mappings and lazy fields.
+        pub storage_size: u32,
+    }
+
+    /// The part of the runtime's call enum `runtime_transfer` dispatches.
+    ///
+    /// The indices are those of substrate-contracts-node: `Balances` is the
+    /// fifth pallet in its `construct_runtime!`, and `transfer_keep_alive` is
+    /// call 3 of pallet-balances. Other runtimes may need other indices.
+    #[cfg(feature = "call-runtime")]
+    #[derive(scale::Encode)]
+    enum RuntimeCall {
+        #[codec(index = 4)]
+        Balances(BalancesCall),
+    }
+
+    #[cfg(feature = "call-runtime")]
+    #[derive(scale::Encode)]
+    enum BalancesCall {
+        #[codec(index = 3)]
+        TransferKeepAlive {
+            dest: MultiAddress,
+            #[codec(compact)]
+            value: Balance,
+        },
+    }
+
+    /// The `Id` variant of `sp_runtime::MultiAddress`, the only one we send.
+    #[cfg(feature = "call-runtime")]
+    #[derive(scale::Encode)]
+    enum MultiAddress {
+        #[codec(index = 0)]
+        Id(AccountId),
+    }
+
+    /// Maximum number of registered flip observers.
+    pub const MAX_OBSERVERS: usize = 8;
+
+    /// Gas made available to each observer notification.
+    pub const OBSERVER_GAS_LIMIT: u64 = 5_000_000_000;
+
+    #[ink(event)]
+    pub struct Created {
+        #[ink(topic)]   // Indexed element
+        creator: AccountId,
+        message: String,
+        constructor: u8,
+        init_value: bool,
+        endowment: Balance,
+        code_hash: Hash,
+    }
+
+    #[ink(event)]
+    pub struct Flipped {
+        #[ink(topic)]   // Indexed element
+        caller: AccountId,
+        previous: bool,
+        current: bool,
+        block: BlockNumber,
+    }
+
+    #[ink(event, anonymous)]
+    pub struct FlippedLite {
+        current: bool,
+    }
+
+    #[ink(event)]
+    pub struct OperationRejected {
+        #[ink(topic)]
+        caller: AccountId,
+        reason: Error,
+    }
+
+    #[ink(event)]
+    pub struct MessageChanged {
+        message: String,
+    }
+
+    #[ink(event)]
+    pub struct DocumentHashSet {
+        #[ink(topic)]
+        hash: Hash,
+    }
+
+    #[ink(event)]
+    pub struct NoteChanged {
+        #[ink(topic)]
+        account: AccountId,
+        note: Option<String>,
+    }
+
+    #[ink(event)]
+    pub struct ConfigChanged {
+        #[ink(topic)]
+        admin: AccountId,
+        key: String,
+        old: Vec<u8>,
+        new: Vec<u8>,
+    }
+
+    #[ink(event)]
+    pub struct AllowlistUpdated {
+        #[ink(topic)]
+        admin: AccountId,
+        #[ink(topic)]
+        account: AccountId,
+        allowed: bool,
+    }
+
+    #[ink(event)]
+    pub struct ActionQueued {
+        #[ink(topic)]
+        id: u32,
+        action: AdminAction,
+        ready_at: BlockNumber,
+    }
+
+    #[ink(event)]
+    pub struct ActionExecuted {
+        #[ink(topic)]
+        id: u32,
+    }
+
+    #[ink(event)]
+    pub struct ActionCancelled {
+        #[ink(topic)]
+        id: u32,
+    }
+
+    #[ink(event)]
+    pub struct AccountBanned {
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct AccountUnbanned {
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct RoleGranted {
+        #[ink(topic)]
+        role: Role,
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct RoleRevoked {
+        #[ink(topic)]
+        role: Role,
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct OwnershipRenounced {
+        #[ink(topic)]
+        previous: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct OwnerProposed {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        proposed: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        previous: AccountId,
+        #[ink(topic)]
+        new: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct VoteCast {
+        #[ink(topic)]
+        voter: AccountId,
+        votes: u32,
+    }
+
+    #[ink(event)]
+    pub struct Paused {
+        #[ink(topic)]
+        by: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct Unpaused {
+        #[ink(topic)]
+        by: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct PersonalFlipped {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        operator: AccountId,
         value: bool,
+    }
+
+    #[ink(event)]
+    pub struct OperatorApproval {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        operator: AccountId,
+        approved: bool,
+    }
+
+    #[ink(event)]
+    pub struct FlippedWithReason {
+        #[ink(topic)]
+        caller: AccountId,
+        reason: String,
+    }
+
+    #[ink(event)]
+    pub struct StatusChanged {
+        #[ink(topic)]
+        id: u32,
+        from: Status,
+        to: Status,
+    }
+
+    #[ink(event)]
+    pub struct AuctionCreated {
+        #[ink(topic)]
+        id: u32,
+        #[ink(topic)]
+        seller: AccountId,
+        #[ink(topic)]
+        subject: Hash,
+    }
+
+    #[ink(event)]
+    pub struct BidPlaced {
+        #[ink(topic)]
+        id: u32,
+        #[ink(topic)]
+        bidder: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct AuctionEnded {
+        #[ink(topic)]
+        id: u32,
+    }
+
+    #[ink(event)]
+    pub struct AuctionFinalized {
+        #[ink(topic)]
+        id: u32,
+        #[ink(topic)]
+        winner: Option<AccountId>,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct AuctionCancelled {
+        #[ink(topic)]
+        id: u32,
+    }
+
+    #[ink(event)]
+    pub struct ReserveNotMet {
+        #[ink(topic)]
+        id: u32,
+        #[ink(topic)]
+        bidder: AccountId,
+        highest_bid: Balance,
+        reserve_price: Balance,
+    }
+
+    #[ink(event)]
+    pub struct DeadlineExtended {
+        #[ink(topic)]
+        id: u32,
+        new_end: Timestamp,
+    }
+
+    #[ink(event)]
+    pub struct AuctionTransferred {
+        #[ink(topic)]
+        id: u32,
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct BoughtNow {
+        #[ink(topic)]
+        id: u32,
+        #[ink(topic)]
+        buyer: AccountId,
+        price: Balance,
+    }
+
+    #[ink(event)]
+    pub struct ExpiredFinalized {
+        settled: u32,
+        failed: u32,
+    }
+
+    #[ink(event)]
+    pub struct BeneficiaryChanged {
+        #[ink(topic)]
+        old: AccountId,
+        #[ink(topic)]
+        new: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct FeesWithdrawn {
+        #[ink(topic)]
+        to: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Deposited {
+        #[ink(topic)]
+        from: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct Terminated {
+        #[ink(topic)]
+        beneficiary: AccountId,
+        balance: Balance,
+    }
+
+    #[ink(event)]
+    pub struct SweptOut {
+        #[ink(topic)]
+        to: AccountId,
+        amount: Balance,
+    }
+
+    #[ink(event)]
+    pub struct DonationReceived {
+        #[ink(topic)]
+        donor: AccountId,
+        amount: Balance,
+        total: Balance,
+    }
+
+    #[ink(event)]
+    pub struct DonationsWithdrawn {
+        #[ink(topic)]
+        to: AccountId,
+        amount: Balance,
+    }
+
+    /// Errors that can occur upon calling this contract.
+    ///
+    /// Every variant has a stable numeric code, its discriminant, which is
+    /// also its SCALE index and is returned by `Error::code`. Codes are never
+    /// changed or reused: new variants take the next unused number and
+    /// removed ones leave a gap.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        /// The stored value did not match the expected one.
+        UnexpectedValue = 0,
+        /// The caller is not the owner of the contract.
+        NotOwner = 1,
+        /// The scheduled flip's block has not been reached yet, or the
+        /// queued action's delay has not passed.
+        TooEarly = 2,
+        /// A flip is already scheduled.
+        AlreadyScheduled = 3,
+        /// No flip is currently scheduled.
+        NothingScheduled = 4,
+        /// The caller flipped too recently and has to wait for the cooldown.
+        CooldownActive = 5,
+        /// The caller already voted in the current round.
+        AlreadyVoted = 6,
+        /// A threshold or limit must be at least one.
+        InvalidThreshold = 7,
+        /// The caller is not part of the multisig signer set.
+        NotSigner = 8,
+        /// Fewer than the required number of signers approved the flip.
+        NotEnoughApprovals = 9,
+        /// The caller already approved the pending flip.
+        AlreadyApproved = 10,
+        /// The caller has not approved the pending flip.
+        NotApproved = 11,
+        /// The multisig configuration is invalid.
+        InvalidSigners = 12,
+        /// The revealed salt and value don't match the stored commitment.
+        CommitmentMismatch = 13,
+        /// The caller has no stored commitment to reveal.
+        NoCommitment = 14,
+        /// The signature was not produced by the given public key.
+        InvalidSignature = 15,
+        /// The nonce was already used by this signer.
+        NonceTooLow = 16,
+        /// The contract is paused.
+        Paused = 17,
+        /// The contract is not paused.
+        NotPaused = 18,
+        /// The contract's expiry block has been reached and the value is frozen.
+        Expired = 19,
+        /// Fewer than `quorum` accounts have their personal flag set.
+        QuorumNotMet = 20,
+        /// The flip reason exceeds `MAX_REASON_LEN` bytes.
+        ReasonTooLong = 21,
+        /// The observer list is full.
+        TooManyObservers = 22,
+        /// The account is already a registered observer.
+        AlreadyObserver = 23,
+        /// The account is not a registered observer.
+        NotObserver = 24,
+        /// The maximum number of flips for the current block has been reached.
+        RateLimited = 25,
+        /// No auction exists with the given id.
+        NoSuchAuction = 26,
+        /// The auction id space is exhausted.
+        TooManyAuctions = 27,
+        /// The auction is not in the status required for this transition.
+        InvalidStatusTransition = 28,
+        /// The auction does not accept bids in its current status.
+        AuctionNotOpen = 29,
+        /// The bid does not exceed the current highest bid.
+        BidTooLow = 30,
+        /// The caller has nothing to withdraw.
+        NothingToWithdraw = 31,
+        /// Transferring funds out of the contract failed.
+        TransferFailed = 32,
+        /// The auction has not ended yet.
+        AuctionNotEnded = 33,
+        /// The auction was already finalized.
+        AlreadyFinalized = 34,
+        /// The bid does not exceed the highest bid by at least `min_increment`.
+        IncrementTooSmall = 35,
+        /// The auction has already started.
+        AuctionAlreadyStarted = 36,
+        /// The auction's deadline has passed.
+        AuctionClosed = 37,
+        /// The caller is not the seller of the auction.
+        NotSeller = 38,
+        /// The auction already started taking bids or is over.
+        CannotCancel = 39,
+        /// The auction was cancelled.
+        Cancelled = 40,
+        /// The message does not apply to this kind of auction.
+        WrongAuctionKind = 41,
+        /// The caller already committed a sealed bid on this auction.
+        AlreadyCommitted = 42,
+        /// The caller already revealed their sealed bid.
+        AlreadyRevealed = 43,
+        /// The sealed bid exceeds the deposit committed with it.
+        DepositTooLow = 44,
+        /// Sealed bids can only be revealed between the bidding deadline and
+        /// the reveal deadline.
+        NotRevealPhase = 45,
+        /// The reveal phase of the sealed-bid auction is not over yet.
+        RevealNotOver = 46,
+        /// The start price of a Dutch auction is below its floor price.
+        InvalidPriceSchedule = 47,
+        /// The caller is not on the allowlist of the restricted auction, or
+        /// not on the flip allowlist while it is enabled.
+        NotAllowed = 48,
+        /// The protocol fee exceeds `MAX_FEE_BPS`.
+        FeeTooHigh = 49,
+        /// The buy-now price is below the reserve price.
+        InvalidBuyNowPrice = 50,
+        /// The message exceeds `MAX_MESSAGE_LEN` bytes.
+        MessageTooLong = 51,
+        /// The number vector already holds `MAX_NUMBERS` entries.
+        VectorFull = 52,
+        /// The note exceeds `MAX_NOTE_LEN` bytes.
+        NoteTooLong = 53,
+        /// The new owner is the all-zero account.
+        InvalidOwner = 54,
+        /// No ownership transfer has been proposed.
+        NoOwnerProposal = 55,
+        /// The caller is not the proposed owner.
+        NotPendingOwner = 56,
+        /// The caller did not confirm an irreversible action.
+        NotConfirmed = 57,
+        /// The caller lacks the role this message requires.
+        MissingRole = 58,
+        /// The only remaining admin cannot give up the `Admin` role.
+        LastAdmin = 59,
+        /// More than `MAX_ALLOWLIST_BATCH` accounts were passed at once.
+        BatchTooLarge = 60,
+        /// The caller has been banned by the owner.
+        Banned = 61,
+        /// The owner cannot ban themselves.
+        CannotBanOwner = 62,
+        /// The caller is not an approved operator of the account.
+        NotOperator = 63,
+        /// No admin action is queued under this id.
+        ActionNotFound = 64,
+        /// The admin action id space is exhausted.
+        TooManyActions = 65,
+        /// A constructor argument is out of its accepted range.
+        InvalidConfig = 66,
+        /// Less than `flip_fee` was transferred with `paid_flip`.
+        FeeTooLow = 67,
+        /// The contract was deployed without free flips, use `paid_flip`.
+        FreeFlipsDisabled = 68,
+        /// No value was transferred with `deposit`.
+        ZeroDeposit = 69,
+        /// More than `owner_fees` was requested from `withdraw_fees`.
+        InsufficientFees = 70,
+        /// More than the caller's deposit was requested from `withdraw`.
+        InsufficientDeposit = 71,
+        /// An auction is still live or deposits remain, see `terminate`.
+        CannotTerminate = 72,
+        /// More than `sweepable_balance` was requested from `transfer_out`.
+        ExceedsSweepable = 73,
+        /// Less than the required endowment was sent to `new_funded`.
+        EndowmentTooLow = 74,
+        /// The caller is not the treasury account.
+        NotTreasury = 75,
+        /// Value was sent to an unknown message while donations are off.
+        DirectTransfersNotAccepted = 76,
+        /// The input exceeds `MAX_HASH_INPUT_LEN` bytes.
+        InputTooLarge = 77,
+        /// The runtime's `RandomnessExtension` has no randomness, see
+        /// `random_flip`.
+        RandomnessUnavailable = 78,
+        /// The runtime rejected the call dispatched by `runtime_transfer`.
+        RuntimeCallFailed = 79,
+        /// Value was sent to a message that doesn't expect any while
+        /// `strict_value_checks` is on.
+        UnexpectedPayment = 80,
+        /// The `pow_flip` nonce doesn't meet `pow_difficulty`.
+        InvalidProof = 81,
+    }
+
+    impl Error {
+        /// Returns the stable numeric code of the error, see `Error`.
+        pub fn code(&self) -> u32 {
+            *self as u32
+        }
+    }
+
+    /// The contract storage.
+    ///
+    /// The growing collections `history`, `change_points` and `numbers` live
+    /// in `Lazy` cells under their own storage keys instead of the packed root
+    /// cell, so messages like `get` no longer load and decode them. Moving
+    /// them changed the storage layout: deployments from before can't switch
+    /// to this code with `set_code_hash` without migrating that data.
+    #[ink(storage)]
+    pub struct Foobar {
+        value: bool,
+        /// How many times `value` has been flipped since instantiation.
+        flip_count: u64,
+        /// The account that performed the most recent flip, if any.
+        last_flipper: Option<AccountId>,
+        /// Ring buffer of the most recent flips, at most `HISTORY_CAPACITY` long.
+        history: Lazy<Vec<FlipRecord>>,
+        /// Slot of `history` that the next record overwrites once it is full.
+        history_head: u32,
+        /// The account that instantiated the contract.
+        owner: AccountId,
+        /// Block from which the scheduled flip may be executed, if any.
+        pending_flip: Option<BlockNumber>,
+        /// Minimum number of blocks between two flips of the same account.
+        cooldown_blocks: u32,
+        /// The block at which each account last called `flip`.
+        last_flip_block_of: Mapping<AccountId, BlockNumber>,
+        /// Number of distinct votes needed in a round to trigger a flip.
+        vote_threshold: u32,
+        /// The current voting round; it advances every time a vote triggers a flip.
+        vote_round: u32,
+        /// Number of votes cast in the current round.
+        votes: u32,
+        /// The latest round each account voted in.
+        voted_in_round: Mapping<AccountId, u32>,
+        /// Accounts allowed to approve multisig flips.
+        signers: Vec<AccountId>,
+        /// Number of distinct signer approvals needed for `execute_flip`.
+        required_approvals: u32,
+        /// Number of approvals collected for the pending multisig flip.
+        approval_count: u32,
+        /// Signers that approved the pending multisig flip.
+        approvals: Mapping<AccountId, ()>,
+        /// Pending commit-reveal commitments per account.
+        commitments: Mapping<AccountId, Hash>,
+        /// Next unused meta-transaction nonce per ECDSA public key.
+        signature_nonces: Mapping<[u8; 33], u64>,
+        /// Next unused meta-transaction nonce per sr25519 public key.
+        #[cfg(feature = "sr25519")]
+        sr25519_nonces: Mapping<[u8; 32], u64>,
+        /// Whether state changes by users are currently frozen.
+        paused: bool,
+        /// Block from which the value can no longer change, if any.
+        expiry: Option<BlockNumber>,
+        /// `(block, value)` pairs recorded whenever the value changed, oldest first.
+        /// The first entry is the instantiation block until it gets evicted.
+        change_points: Lazy<Vec<(BlockNumber, bool)>>,
+        /// Maximum length of `change_points`; the oldest entries are evicted beyond it.
+        max_change_points: u32,
+        /// Per-account flags, independent of `value`. Only `true` flags are stored.
+        personal_flags: Mapping<AccountId, bool>,
+        /// Number of accounts whose personal flag is currently `true`.
+        support: u32,
+        /// Minimum `support` needed for `flip` to succeed.
+        quorum: u32,
+        /// Reason given for the latest flip; empty if it was a plain `flip`.
+        last_reason: String,
+        /// Number of flips performed by each account.
+        flips_of: Mapping<AccountId, u32>,
+        /// The most active flippers, sorted by descending flip count.
+        leaderboard: Vec<(AccountId, u32)>,
+        /// Contracts notified through `OnFlip::on_flip` after every flip.
+        observers: Vec<AccountId>,
+        /// Number of observer notifications that failed.
+        failed_notifications: u32,
+        /// Maximum number of `flip` calls per block across all callers.
+        max_flips_per_block: u32,
+        /// The block `flips_in_block` refers to.
+        rate_limit_block: BlockNumber,
+        /// Number of `flip` calls in `rate_limit_block`.
+        flips_in_block: u32,
+        /// Auctions run by this contract, keyed by id.
+        auctions: Mapping<u32, Auction>,
+        /// Id assigned to the next created auction.
+        next_auction_id: u32,
+        /// Number of auctions that have started but are neither finalized nor
+        /// cancelled, see `terminate`.
+        live_auctions: u32,
+        /// Lowest auction id `finalize_expired` still has to look at.
+        expired_cursor: u32,
+        /// Ids of the auctions in each status, keyed by status and position
+        /// below `status_counts`.
+        auctions_by_status: Mapping<(Status, u32), u32>,
+        /// Number of auctions in each status.
+        status_counts: Mapping<Status, u32>,
+        /// Position of each auction in `auctions_by_status` under its current
+        /// status.
+        status_positions: Mapping<u32, u32>,
+        /// Outbid amounts each account can withdraw with `withdraw_refund`.
+        pending_refunds: Mapping<AccountId, Balance>,
+        /// Sealed bids per auction and bidder.
+        sealed_bids: Mapping<(u32, AccountId), SealedBid>,
+        /// The latest accepted bids per auction and bidder, oldest first and
+        /// at most `MAX_BIDS_PER_BIDDER` long.
+        bid_history: Mapping<(u32, AccountId), BidHistory>,
+        /// Accounts allowed to bid on restricted auctions.
+        allowed_bidders: Mapping<(u32, AccountId), ()>,
+        /// Protocol fee in basis points, charged on auctions created from now on.
+        fee_bps: u16,
+        /// Owner's share of the fees accrued from finalized auctions and
+        /// `paid_flip`, see `withdraw_fees`.
+        owner_fees: Balance,
+        /// Treasury's share of the same fees, see `withdraw_treasury_fees`.
+        treasury_fees: Balance,
+        /// Account receiving `treasury_share_bps` of all fees.
+        treasury: AccountId,
+        /// Share of all fees in basis points that goes to the treasury.
+        treasury_share_bps: u16,
+        /// Contract-wide message set by the owner.
+        message: String,
+        /// Numbers pushed with `push_number`, at most `MAX_NUMBERS` long.
+        numbers: Lazy<Vec<u32>>,
+        /// Account designated for the contract's own payouts; the deployer by
+        /// default.
+        beneficiary: AccountId,
+        /// Sum of the value transferred with all payable messages.
+        total_received: Balance,
+        /// Reference hash that `verify_document` checks preimages against.
+        document_hash: Hash,
+        /// The example composite value set with `set_my_struct`.
+        my_struct: Lazy<MyStruct>,
+        /// Profile note of each account, see `set_my_note`.
+        notes: Mapping<AccountId, String>,
+        /// Account that may take over ownership with `accept_ownership`.
+        pending_owner: Option<AccountId>,
+        /// Whether the owner gave up its rights with `renounce_ownership`.
+        owner_renounced: bool,
+        /// Roles held by each account.
+        roles: Mapping<(AccountId, Role), ()>,
+        /// Number of accounts holding `Role::Admin`.
+        admin_count: u32,
+        /// Whether only accounts holding `Role::Flipper` may flip.
+        restricted_mode: bool,
+        /// Whether only accounts on `flip_allowlist` may flip.
+        allowlist_enabled: bool,
+        /// Accounts allowed to flip while `allowlist_enabled` is set.
+        flip_allowlist: Mapping<AccountId, ()>,
+        /// Accounts banned from all state-changing messages.
+        banned: Mapping<AccountId, ()>,
+        /// Operators approved by each account, keyed by `(owner, operator)`.
+        operators: Mapping<(AccountId, AccountId), ()>,
+        /// Blocks a queued admin action has to wait before it can be executed.
+        delay_blocks: BlockNumber,
+        /// Admin actions waiting in the timelock, keyed by id.
+        queued_actions: Mapping<u32, QueuedAction>,
+        /// Id assigned to the next queued admin action.
+        next_action_id: u32,
+        /// Whether flips emit the full `Flipped` event or `FlippedLite`.
+        verbose_events: bool,
+        /// Code of the error `attempt_flip` rejected the latest attempt with.
+        last_error_code: Option<u32>,
+        /// Minimum value that has to be transferred with `paid_flip`.
+        flip_fee: Balance,
+        /// Whether the free `flip` is available besides `paid_flip`.
+        free_flips_allowed: bool,
+        /// Leading zero bits required from a `pow_flip` proof.
+        pow_difficulty: u8,
+        /// Value parked by each account with `deposit`.
+        deposits: Mapping<AccountId, Balance>,
+        /// Sum of all `deposits`.
+        total_deposits: Balance,
+        /// Value received for auctions and not paid out yet, see
+        /// `escrowed_bids`.
+        escrowed_bids: Balance,
+        /// Value transferred with the constructor.
+        initial_endowment: Balance,
+        /// Whether `receive` accepts value as a donation.
+        accept_donations: bool,
+        /// Whether messages that are payable only for technical reasons
+        /// reject value, see `new_with_strict_value_checks`.
+        strict_value_checks: bool,
+        /// Donations received and not yet withdrawn.
+        donations: Balance,
+        /// Sum of all donations made by each account.
+        donated: Mapping<AccountId, Balance>,
+        /// The `TOP_DONORS_SIZE` largest donors, largest first.
+        top_donors: Vec<(AccountId, Balance)>,
+        /// Block of the latest flip, or of the instantiation before any.
+        last_flip_block: BlockNumber,
+        /// Timestamp of the latest flip, or of the instantiation before any.
+        last_flip_timestamp: Timestamp,
+    }
+    
+    impl Foobar {
+        /// Constructor that initializes the `bool` value to the given `init_value`.
+        #[ink(constructor)]
+        pub fn new(init_value: bool) -> Self {
+            Self::init(init_value).created(CONSTRUCTOR_NEW)
+        }
+
+        /// Constructor that additionally requires each account to wait
+        /// `cooldown_blocks` blocks between two flips.
+        #[ink(constructor)]
+        pub fn new_with_cooldown(init_value: bool, cooldown_blocks: u32) -> Self {
+            let mut instance = Self::init(init_value);
+            instance.cooldown_blocks = cooldown_blocks;
+            instance.created(CONSTRUCTOR_WITH_COOLDOWN)
+        }
+
+        /// Constructor for a limited-time deployment whose value becomes
+        /// immutable once block `expiry` is reached.
+        #[ink(constructor)]
+        pub fn new_with_expiry(init_value: bool, expiry: BlockNumber) -> Self {
+            let mut instance = Self::init(init_value);
+            instance.expiry = Some(expiry);
+            instance.created(CONSTRUCTOR_WITH_EXPIRY)
+        }
+
+        /// Constructor that accepts at most `max_per_block` calls to `flip` per
+        /// block across all callers.
+        #[ink(constructor)]
+        pub fn new_with_rate_limit(init_value: bool, max_per_block: u32) -> Self {
+            let mut instance = Self::init(init_value);
+            instance.max_flips_per_block = max_per_block;
+            instance.created(CONSTRUCTOR_WITH_RATE_LIMIT)
+        }
+
+        /// Constructor whose admin actions queued with `queue_action` can only
+        /// be executed `delay_blocks` blocks later.
+        #[ink(constructor)]
+        pub fn new_with_timelock(init_value: bool, delay_blocks: BlockNumber) -> Self {
+            let mut instance = Self::init(init_value);
+            instance.delay_blocks = delay_blocks;
+            instance.created(CONSTRUCTOR_WITH_TIMELOCK)
+        }
+
+        /// Constructor that charges a protocol fee of `fee_bps` basis points
+        /// on the winning bid of every finalized auction.
+        ///
+        /// Fails with `Error::FeeTooHigh` if `fee_bps` exceeds `MAX_FEE_BPS`.
+        #[ink(constructor)]
+        pub fn new_with_fee(init_value: bool, fee_bps: u16) -> Result<Self, Error> {
+            if fee_bps > MAX_FEE_BPS {
+                return Err(Error::FeeTooHigh);
+            }
+            let mut instance = Self::init(init_value);
+            instance.fee_bps = fee_bps;
+            Ok(instance.created(CONSTRUCTOR_WITH_FEE))
+        }
+
+        /// Constructor that checks its configuration before deploying, so a
+        /// bad argument fails the instantiation instead of producing a
+        /// contract nobody can use.
+        ///
+        /// Fails with `Error::InvalidConfig` if `fee_bps` exceeds `MAX_FEE_BPS`
+        /// or `cooldown_blocks` exceeds `MAX_COOLDOWN_BLOCKS`.
+        #[ink(constructor)]
+        pub fn try_new(init_value: bool, cooldown_blocks: u32, fee_bps: u16) -> Result<Self, Error> {
+            if fee_bps > MAX_FEE_BPS || cooldown_blocks > MAX_COOLDOWN_BLOCKS {
+                return Err(Error::InvalidConfig);
+            }
+            let mut instance = Self::init(init_value);
+            instance.cooldown_blocks = cooldown_blocks;
+            instance.fee_bps = fee_bps;
+            Ok(instance.created(CONSTRUCTOR_TRY_NEW))
+        }
+
+        /// Constructor that charges at least `flip_fee` for each `paid_flip`.
+        ///
+        /// Unless `free_flips_allowed` is set, `flip` and every other message
+        /// that flips the value fail with `Error::FreeFlipsDisabled`, so every
+        /// flip has to be paid for.
+        #[ink(constructor)]
+        pub fn new_with_flip_fee(init_value: bool, flip_fee: Balance, free_flips_allowed: bool) -> Self {
+            let mut instance = Self::init(init_value);
+            instance.flip_fee = flip_fee;
+            instance.free_flips_allowed = free_flips_allowed;
+            instance.created(CONSTRUCTOR_WITH_FLIP_FEE)
+        }
+
+        /// Constructor that must be sent at least `min_endowment`, which is
+        /// recorded as `initial_endowment`.
+        ///
+        /// Fails with `Error::EndowmentTooLow` otherwise, which returns the
+        /// value to the deployer.
+        #[ink(constructor, payable)]
+        pub fn new_funded(init_value: bool, min_endowment: Balance) -> Result<Self, Error> {
+            if Self::env().transferred_value() < min_endowment {
+                return Err(Error::EndowmentTooLow);
+            }
+            Ok(Self::init(init_value).created(CONSTRUCTOR_FUNDED))
+        }
+
+        /// Constructor that sends `treasury_share_bps` basis points of all
+        /// fees to `treasury`, see `withdraw_treasury_fees`.
+        ///
+        /// Fails with `Error::InvalidConfig` if the share exceeds 10 000 basis
+        /// points.
+        #[ink(constructor)]
+        pub fn new_with_treasury(
+            init_value: bool,
+            treasury: AccountId,
+            treasury_share_bps: u16,
+        ) -> Result<Self, Error> {
+            if treasury_share_bps > 10_000 {
+                return Err(Error::InvalidConfig);
+            }
+            let mut instance = Self::init(init_value);
+            instance.treasury = treasury;
+            instance.treasury_share_bps = treasury_share_bps;
+            Ok(instance.created(CONSTRUCTOR_WITH_TREASURY))
+        }
+
+        /// Constructor that sets whether messages that are payable only for
+        /// technical reasons reject value with `Error::UnexpectedPayment`.
+        ///
+        /// This covers the `receive` fallback, which has to be payable to take
+        /// donations but otherwise keeps value sent to a mistyped selector.
+        #[ink(constructor)]
+        pub fn new_with_strict_value_checks(init_value: bool, strict_value_checks: bool) -> Self {
+            let mut instance = Self::init(init_value);
+            instance.strict_value_checks = strict_value_checks;
+            instance.created(CONSTRUCTOR_WITH_STRICT_VALUE_CHECKS)
+        }
+
+        /// Constructor for a multisig deployment where flipping through
+        /// `execute_flip` needs `required` approvals from `signers`.
+        ///
+        /// Fails with `Error::InvalidSigners` if `required` is zero or larger
+        /// than the number of distinct signers, or if there are more than
+        /// `MAX_SIGNERS` of them.
+        #[ink(constructor)]
+        pub fn new_multisig(signers: Vec<AccountId>, required: u32) -> Result<Self, Error> {
+            let mut signers = signers;
+            signers.sort_unstable();
+            signers.dedup();
+            if required == 0 || required as usize > signers.len() || signers.len() > MAX_SIGNERS {
+                return Err(Error::InvalidSigners);
+            }
+            let mut instance = Self::init(false);
+            instance.signers = signers;
+            instance.required_approvals = required;
+            Ok(instance.created(CONSTRUCTOR_MULTISIG))
+        }
+
+        /// Constructor that initializes the `bool` value to `false`.
+        #[ink(constructor)]
+        pub fn default() -> Self {
+            Self::init(Default::default()).created(CONSTRUCTOR_DEFAULT)
+        }
+
+        /// Builds the initial state shared by all constructors.
+        ///
+        /// Constructors don't delegate to each other but to this, so that each
+        /// instantiation emits exactly one `Created` event, tagged with the
+        /// constructor that actually ran, through `created`.
+        fn init(init_value: bool) -> Self {
+            let mut instance = Self {
+                value: init_value,
+                flip_count: 0,
+                last_flipper: None,
+                history: Lazy::new(),
+                history_head: 0,
+                owner: Self::env().caller(),
+                pending_flip: None,
+                cooldown_blocks: 0,
+                last_flip_block_of: Mapping::default(),
+                vote_threshold: 1,
+                vote_round: 0,
+                votes: 0,
+                voted_in_round: Mapping::default(),
+                signers: Vec::new(),
+                required_approvals: 0,
+                approval_count: 0,
+                approvals: Mapping::default(),
+                commitments: Mapping::default(),
+                signature_nonces: Mapping::default(),
+                #[cfg(feature = "sr25519")]
+                sr25519_nonces: Mapping::default(),
+                paused: false,
+                expiry: None,
+                change_points: Lazy::new(),
+                max_change_points: DEFAULT_MAX_CHANGE_POINTS,
+                personal_flags: Mapping::default(),
+                support: 0,
+                quorum: 0,
+                last_reason: String::new(),
+                flips_of: Mapping::default(),
+                leaderboard: Vec::new(),
+                observers: Vec::new(),
+                failed_notifications: 0,
+                max_flips_per_block: u32::MAX,
+                rate_limit_block: 0,
+                flips_in_block: 0,
+                auctions: Mapping::default(),
+                next_auction_id: 0,
+                live_auctions: 0,
+                expired_cursor: 0,
+                auctions_by_status: Mapping::default(),
+                status_counts: Mapping::default(),
+                status_positions: Mapping::default(),
+                pending_refunds: Mapping::default(),
+                sealed_bids: Mapping::default(),
+                bid_history: Mapping::default(),
+                allowed_bidders: Mapping::default(),
+                fee_bps: 0,
+                owner_fees: 0,
+                treasury_fees: 0,
+                treasury: Self::env().caller(),
+                treasury_share_bps: 0,
+                message: String::new(),
+                numbers: Lazy::new(),
+                beneficiary: Self::env().caller(),
+                total_received: 0,
+                document_hash: Hash::default(),
+                my_struct: Lazy::new(),
+                notes: Mapping::default(),
+                pending_owner: None,
+                owner_renounced: false,
+                roles: Mapping::default(),
+                admin_count: 1,
+                restricted_mode: false,
+                allowlist_enabled: false,
+                flip_allowlist: Mapping::default(),
+                banned: Mapping::default(),
+                operators: Mapping::default(),
+                delay_blocks: 0,
+                queued_actions: Mapping::default(),
+                next_action_id: 0,
+                verbose_events: true,
+                last_error_code: None,
+                flip_fee: 0,
+                free_flips_allowed: true,
+                pow_difficulty: DEFAULT_POW_DIFFICULTY,
+                deposits: Mapping::default(),
+                total_deposits: 0,
+                escrowed_bids: 0,
+                initial_endowment: Self::env().transferred_value(),
+                accept_donations: false,
+                strict_value_checks: false,
+                donations: 0,
+                donated: Mapping::default(),
+                top_donors: Vec::new(),
+                last_flip_block: Self::env().block_number(),
+                last_flip_timestamp: Self::env().block_timestamp(),
+            };
+            instance.roles.insert((Self::env().caller(), Role::Admin), &());
+            instance
+                .change_points
+                .set(&vec![(Self::env().block_number(), init_value)]);
+            instance.my_struct.set(&MyStruct::empty(Self::env().caller()));
+            instance
+        }
+
+        /// Emits the `Created` event for the freshly built `self`, see `init`.
+        fn created(self, constructor: u8) -> Self {
+            self.env().emit_event(Created {
+                creator: self.env().caller(),
+                message: String::from("Foobar created"),
+                constructor,
+                init_value: self.value,
+                endowment: self.initial_endowment,
+                code_hash: self.own_code_hash(),
+            });
+            self
+        }
+
+        /// Returns the code hash of the contract, or the default hash if the
+        /// environment can't tell.
+        ///
+        /// The off-chain test environment doesn't implement `own_code_hash` and
+        /// panics instead of failing, so unit tests always see the default.
+        fn own_code_hash(&self) -> Hash {
+            #[cfg(not(test))]
+            return self.env().own_code_hash().unwrap_or_default();
+            #[cfg(test)]
+            Hash::default()
+        }
+
+        /// Returns the gas left for the current call, or 0 in unit tests,
+        /// where the off-chain environment doesn't implement `gas_left`.
+        #[cfg(feature = "debug")]
+        fn gas_left(&self) -> u64 {
+            #[cfg(not(test))]
+            return self.env().gas_left();
+            #[cfg(test)]
+            0
+        }
+
+        /// A message that can be called on instantiated contracts.
+        /// This one flips the value of the stored `bool` from `true`
+        /// to `false` and vice versa.
+        /// 
+        /// Selector calculation
+        /// 
+        /// 1. Grab the name of the message or constructor
+        ///  
+        /// 2. Compute the BLACKE2 hash of the name
+        /// 
+        /// 3. Take the first 4 bytes of the hash as the selector
+        /// 
+        /// BLACKE2(flip) = 0x633aa551........................
+        /// 
+        /// Returns `Error::QuorumNotMet` while fewer than `quorum` accounts have
+        /// their personal flag set, and `Error::CooldownActive` if the caller
+        /// flipped less than `cooldown_blocks` blocks ago. At most
+        /// `max_flips_per_block` flips are accepted per block, later ones get
+        /// `Error::RateLimited`.
+        ///
+        /// Emits `Flipped`, or the cheaper but unfilterable `FlippedLite` if
+        /// the owner turned `verbose_events` off.
+        ///
+        /// Returns `Error::FreeFlipsDisabled` if the contract was deployed
+        /// with `free_flips_allowed` unset; use `paid_flip` then. The same
+        /// checks apply to every other message that flips the value.
+        #[ink(message)]
+        pub fn flip(&mut self) -> Result<(), Error> {
+            debug_log!("flip: caller={:?}", self.env().caller());
+            self.checked_flip()?;
+            if !self.last_reason.is_empty() {
+                self.last_reason = String::new();
+            }
+            Ok(())
+        }
+
+        /// Flips the value like `flip`, but never reverts.
+        ///
+        /// Returns `None` on success. If `flip` would fail, the error is
+        /// returned instead and logged in an `OperationRejected` event, so
+        /// rejected attempts stay visible on-chain. The error is deliberately
+        /// not returned as `Err`: that would revert the call and with it the
+        /// event.
+        ///
+        /// This is the `try_` variant of `flip`; the name `try_flip` is taken
+        /// by the method ink! generates on `FoobarRef`.
+        #[ink(message)]
+        pub fn attempt_flip(&mut self) -> Option<Error> {
+            debug_log!("attempt_flip: caller={:?}", self.env().caller());
+            let reason = self.flip().err()?;
+            self.last_error_code = Some(reason.code());
+            self.env().emit_event(OperationRejected {
+                caller: self.env().caller(),
+                reason,
+            });
+            Some(reason)
+        }
+
+        /// Returns the code of the error that `attempt_flip` most recently
+        /// rejected an attempt with, if any, see `Error::code`.
+        ///
+        /// Errors returned by other messages revert the call, so there is
+        /// nothing left to record for them.
+        #[ink(message)]
+        pub fn last_error_code(&self) -> Option<u32> {
+            self.last_error_code
+        }
+
+        /// Flips the value like `flip`, paying for it with the transferred
+        /// value. The payment is split into `owner_fees` and `treasury_fees`
+        /// by `treasury_share_bps`.
+        ///
+        /// Returns `Error::FeeTooLow` if less than `flip_fee` was transferred.
+        /// Like any error this reverts the call, so the value goes back to the
+        /// caller. Anything above `flip_fee` is kept as well.
+        #[ink(message, payable)]
+        pub fn paid_flip(&mut self) -> Result<(), Error> {
+            debug_log!("paid_flip: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            if self.env().transferred_value() < self.flip_fee {
+                return Err(Error::FeeTooLow);
+            }
+            self.admit_flip(true)?;
+            self.toggle();
+            let paid = self.accept_value();
+            self.accrue_fees(paid);
+            if !self.last_reason.is_empty() {
+                self.last_reason = String::new();
+            }
+            Ok(())
+        }
+
+        /// Sets the minimum value to transfer with `paid_flip`. Only the owner
+        /// may call this.
+        #[ink(message)]
+        pub fn set_flip_fee(&mut self, flip_fee: Balance) -> Result<(), Error> {
+            debug_log!("set_flip_fee: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            let old = core::mem::replace(&mut self.flip_fee, flip_fee);
+            self.emit_config_changed(self.owner, "flip_fee", &old, &flip_fee);
+            Ok(())
+        }
+
+        /// Returns the minimum value to transfer with `paid_flip`.
+        #[ink(message)]
+        pub fn flip_fee(&self) -> Balance {
+            self.flip_fee
+        }
+
+        /// Returns whether the free `flip` is available.
+        #[ink(message)]
+        pub fn free_flips_allowed(&self) -> bool {
+            self.free_flips_allowed
+        }
+
+        /// Flips the value like `flip`, once the caller presents a proof of
+        /// work.
+        ///
+        /// The proof is a `nonce` for which
+        /// `blake2b_256(contract_account ++ caller ++ last_flip_block ++ nonce)`
+        /// starts with at least `pow_difficulty` zero bits, with the block
+        /// number as little-endian `u32` and the nonce as little-endian `u64`.
+        /// Binding it to the block of the latest flip stops proofs from being
+        /// computed far ahead. Fails with `Error::InvalidProof` otherwise.
+        #[ink(message)]
+        pub fn pow_flip(&mut self, nonce: u64) -> Result<(), Error> {
+            debug_log!("pow_flip: caller={:?}", self.env().caller());
+            if Self::leading_zero_bits(&self.pow_hash(self.env().caller(), nonce))
+                < u32::from(self.pow_difficulty)
+            {
+                return Err(Error::InvalidProof);
+            }
+            self.checked_flip()
+        }
+
+        /// Sets the number of leading zero bits `pow_flip` requires. Only the
+        /// owner may call this.
+        ///
+        /// Fails with `Error::InvalidConfig` above `MAX_POW_DIFFICULTY`.
+        #[ink(message)]
+        pub fn set_pow_difficulty(&mut self, difficulty: u8) -> Result<(), Error> {
+            debug_log!("set_pow_difficulty: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            if difficulty > MAX_POW_DIFFICULTY {
+                return Err(Error::InvalidConfig);
+            }
+            let old = core::mem::replace(&mut self.pow_difficulty, difficulty);
+            self.emit_config_changed(self.owner, "pow_difficulty", &old, &difficulty);
+            Ok(())
+        }
+
+        /// Returns the number of leading zero bits `pow_flip` requires.
+        #[ink(message)]
+        pub fn pow_difficulty(&self) -> u8 {
+            self.pow_difficulty
+        }
+
+        /// Flips the value like `flip` and records why.
+        ///
+        /// The reason is stored as `last_reason` and emitted in a
+        /// `FlippedWithReason` event. Reasons longer than `MAX_REASON_LEN`
+        /// bytes are rejected with `Error::ReasonTooLong`.
+        #[ink(message)]
+        pub fn flip_with_reason(&mut self, reason: String) -> Result<(), Error> {
+            debug_log!("flip_with_reason: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            if reason.len() > MAX_REASON_LEN {
+                return Err(Error::ReasonTooLong);
+            }
+            self.checked_flip()?;
+            self.env().emit_event(FlippedWithReason {
+                caller: self.env().caller(),
+                reason: reason.clone(),
+            });
+            self.last_reason = reason;
+            Ok(())
+        }
+
+        /// Drives the value to `target` and returns whether it changed.
+        ///
+        /// When the value already equals `target` nothing is written and no
+        /// `Flipped` event is emitted.
+        #[ink(message)]
+        pub fn flip_to(&mut self, target: bool) -> Result<bool, Error> {
+            debug_log!("flip_to: caller={:?}", self.env().caller());
+            self.admit_flip(false)?;
+            Ok(self.set_value(target))
+        }
+
+        /// Flips the value only if it currently equals `expected`.
+        ///
+        /// Returns `Error::UnexpectedValue` otherwise, so that two clients
+        /// racing to flip from the same state can't double-toggle.
+        #[ink(message)]
+        pub fn compare_and_flip(&mut self, expected: bool) -> Result<(), Error> {
+            debug_log!("compare_and_flip: caller={:?}", self.env().caller());
+            self.admit_flip(false)?;
+            if self.value != expected {
+                return Err(Error::UnexpectedValue);
+            }
+            self.toggle();
+            Ok(())
+        }
+
+        /// Flips the value `times` times in a single transaction.
+        ///
+        /// Gas is constant: only the parity of `times` is applied to the value,
+        /// while the flip counter advances by `times`. Passing `0` is a no-op
+        /// that neither writes storage nor emits an event.
+        #[ink(message)]
+        pub fn flip_n(&mut self, times: u32) -> Result<(), Error> {
+            debug_log!("flip_n: caller={:?}", self.env().caller());
+            if times == 0 {
+                return Ok(());
+            }
+            self.admit_flip(false)?;
+            self.apply_flips(u64::from(times));
+            Ok(())
+        }
+
+        /// Flips the value and returns the value as it was *before* the flip.
+        ///
+        /// Reading and toggling happen in the same transaction, so there is no
+        /// window for another caller to flip in between.
+        ///
+        /// Selector calculation follows the same steps as for `flip`:
+        ///
+        /// BLAKE2(get_and_flip) = 0xc1c44e9e........................
+        ///
+        #[ink(message)]
+        pub fn get_and_flip(&mut self) -> Result<bool, Error> {
+            debug_log!("get_and_flip: caller={:?}", self.env().caller());
+            self.admit_flip(false)?;
+            let previous = self.value;
+            self.toggle();
+            Ok(previous)
+        }
+
+        /// Simply returns the current value of our `bool`.
+        #[ink(message)]
+        pub fn get(&self) -> bool {
+            self.value
+        }
+
+        /// Returns how many times the value has been flipped.
+        #[ink(message)]
+        pub fn flip_count(&self) -> u64 {
+            self.flip_count
+        }
+
+        /// Returns the account that flipped the value last, if anyone did.
+        #[ink(message)]
+        pub fn last_flipper(&self) -> Option<AccountId> {
+            self.last_flipper
+        }
+
+        /// Returns the account that may call privileged messages, or the
+        /// all-zero account once ownership has been renounced.
+        #[ink(message)]
+        pub fn owner(&self) -> AccountId {
+            self.owner
+        }
+
+        /// Hands the contract over to `new_owner`, effective immediately. Only
+        /// the owner may call this.
+        ///
+        /// The all-zero account is rejected with `Error::InvalidOwner`, as
+        /// nobody could use it.
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<(), Error> {
+            debug_log!("transfer_ownership: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            self.apply_transfer_ownership(new_owner)
+        }
+
+        /// Proposes `new` as the next owner, replacing any earlier proposal.
+        /// Ownership only moves once `new` calls `accept_ownership`. Only the
+        /// owner may call this.
+        #[ink(message)]
+        pub fn propose_owner(&mut self, new: AccountId) -> Result<(), Error> {
+            debug_log!("propose_owner: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            if new == AccountId::from([0x00; 32]) {
+                return Err(Error::InvalidOwner);
+            }
+            self.pending_owner = Some(new);
+            self.env().emit_event(OwnerProposed {
+                owner: self.owner,
+                proposed: new,
+            });
+            Ok(())
+        }
+
+        /// Completes the ownership transfer proposed with `propose_owner`.
+        /// Only the proposed owner may call this.
+        #[ink(message)]
+        pub fn accept_ownership(&mut self) -> Result<(), Error> {
+            debug_log!("accept_ownership: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            let pending_owner = self.pending_owner.ok_or(Error::NoOwnerProposal)?;
+            if self.env().caller() != pending_owner {
+                return Err(Error::NotPendingOwner);
+            }
+            self.set_owner(pending_owner);
+            Ok(())
+        }
+
+        /// Withdraws the pending ownership proposal. Only the owner may call
+        /// this.
+        #[ink(message)]
+        pub fn cancel_ownership_proposal(&mut self) -> Result<(), Error> {
+            debug_log!(
+                "cancel_ownership_proposal: caller={:?}",
+                self.env().caller()
+            );
+            self.ensure_owner()?;
+            let old = self.pending_owner.take();
+            if old.is_none() {
+                return Err(Error::NoOwnerProposal);
+            }
+            self.emit_config_changed(self.owner, "pending_owner", &old, &None);
+            Ok(())
+        }
+
+        /// Permanently gives up ownership: afterwards every owner-only message
+        /// returns `Error::NotOwner` and every admin-only message
+        /// `Error::MissingRole`. `confirm` must be `true`. Only the owner may
+        /// call this.
+        #[ink(message)]
+        pub fn renounce_ownership(&mut self, confirm: bool) -> Result<(), Error> {
+            debug_log!("renounce_ownership: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            if !confirm {
+                return Err(Error::NotConfirmed);
+            }
+            self.owner_renounced = true;
+            self.pending_owner = None;
+            let previous = core::mem::replace(&mut self.owner, AccountId::from([0x00; 32]));
+            self.hand_over_admin(previous, None);
+            self.env().emit_event(OwnershipRenounced { previous });
+            Ok(())
+        }
+
+        /// Returns whether ownership has been renounced.
+        #[ink(message)]
+        pub fn is_owner_renounced(&self) -> bool {
+            self.owner_renounced
+        }
+
+        /// Grants `role` to `who`. Only admins may call this.
+        #[ink(message)]
+        pub fn grant_role(&mut self, role: Role, who: AccountId) -> Result<(), Error> {
+            debug_log!("grant_role: caller={:?}", self.env().caller());
+            self.ensure_role(Role::Admin)?;
+            if self.roles.insert((who, role), &()).is_none() {
+                if role == Role::Admin {
+                    self.admin_count += 1;
+                }
+                self.env().emit_event(RoleGranted { role, account: who });
+            }
+            Ok(())
+        }
+
+        /// Revokes `role` from `who`. Only admins may call this, and the last
+        /// admin cannot revoke its own `Role::Admin`.
+        #[ink(message)]
+        pub fn revoke_role(&mut self, role: Role, who: AccountId) -> Result<(), Error> {
+            debug_log!("revoke_role: caller={:?}", self.env().caller());
+            self.ensure_role(Role::Admin)?;
+            if !self.has_role(role, who) {
+                return Ok(());
+            }
+            if role == Role::Admin {
+                if self.admin_count == 1 {
+                    return Err(Error::LastAdmin);
+                }
+                self.admin_count -= 1;
+            }
+            self.roles.remove((who, role));
+            self.env().emit_event(RoleRevoked { role, account: who });
+            Ok(())
+        }
+
+        /// Returns whether `who` holds `role`.
+        #[ink(message)]
+        pub fn has_role(&self, role: Role, who: AccountId) -> bool {
+            self.roles.contains((who, role))
+        }
+
+        /// Turns `restricted_mode` on or off. While on, only accounts holding
+        /// `Role::Flipper` may flip. Only admins may call this.
+        #[ink(message)]
+        pub fn set_restricted_mode(&mut self, enabled: bool) -> Result<(), Error> {
+            debug_log!("set_restricted_mode: caller={:?}", self.env().caller());
+            self.ensure_role(Role::Admin)?;
+            let old = core::mem::replace(&mut self.restricted_mode, enabled);
+            self.emit_config_changed(self.env().caller(), "restricted_mode", &old, &enabled);
+            Ok(())
+        }
+
+        /// Returns whether only accounts holding `Role::Flipper` may flip.
+        #[ink(message)]
+        pub fn restricted_mode(&self) -> bool {
+            self.restricted_mode
+        }
+
+        /// Allows `who` to flip while the allowlist is enabled. Only the owner
+        /// may call this.
+        #[ink(message)]
+        pub fn add_to_allowlist(&mut self, who: AccountId) -> Result<(), Error> {
+            debug_log!("add_to_allowlist: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            self.set_allowlisted(who, true);
+            Ok(())
+        }
+
+        /// Adds all of `accounts` to the allowlist, like `add_to_allowlist`.
+        /// Fails with `Error::BatchTooLarge` for more than
+        /// `MAX_ALLOWLIST_BATCH` accounts. Only the owner may call this.
+        #[ink(message)]
+        pub fn add_many_to_allowlist(&mut self, accounts: Vec<AccountId>) -> Result<(), Error> {
+            debug_log!("add_many_to_allowlist: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            if accounts.len() > MAX_ALLOWLIST_BATCH {
+                return Err(Error::BatchTooLarge);
+            }
+            for who in accounts {
+                self.set_allowlisted(who, true);
+            }
+            Ok(())
+        }
+
+        /// Removes `who` from the allowlist. Only the owner may call this.
+        #[ink(message)]
+        pub fn remove_from_allowlist(&mut self, who: AccountId) -> Result<(), Error> {
+            debug_log!("remove_from_allowlist: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            self.set_allowlisted(who, false);
+            Ok(())
+        }
+
+        /// Turns the allowlist on or off. While on, only listed accounts may
+        /// flip. Only the owner may call this.
+        #[ink(message)]
+        pub fn set_allowlist_enabled(&mut self, enabled: bool) -> Result<(), Error> {
+            debug_log!("set_allowlist_enabled: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            let old = core::mem::replace(&mut self.allowlist_enabled, enabled);
+            self.emit_config_changed(self.owner, "allowlist_enabled", &old, &enabled);
+            Ok(())
+        }
+
+        /// Returns whether `who` is on the allowlist.
+        #[ink(message)]
+        pub fn is_allowed(&self, who: AccountId) -> bool {
+            self.flip_allowlist.contains(who)
+        }
+
+        /// Returns whether only allowlisted accounts may flip.
+        #[ink(message)]
+        pub fn allowlist_enabled(&self) -> bool {
+            self.allowlist_enabled
+        }
+
+        /// Bans `who` from every state-changing message, whatever their roles
+        /// or allowlist entries. Only the owner may call this, and not on
+        /// themselves.
+        #[ink(message)]
+        pub fn ban(&mut self, who: AccountId) -> Result<(), Error> {
+            debug_log!("ban: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            if who == self.owner {
+                return Err(Error::CannotBanOwner);
+            }
+            if self.banned.insert(who, &()).is_none() {
+                self.env().emit_event(AccountBanned { account: who });
+            }
+            Ok(())
+        }
+
+        /// Lifts a ban placed with `ban`. Only the owner may call this.
+        #[ink(message)]
+        pub fn unban(&mut self, who: AccountId) -> Result<(), Error> {
+            debug_log!("unban: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            if self.banned.take(who).is_some() {
+                self.env().emit_event(AccountUnbanned { account: who });
+            }
+            Ok(())
+        }
+
+        /// Returns whether `who` is banned.
+        #[ink(message)]
+        pub fn is_banned(&self, who: AccountId) -> bool {
+            self.banned.contains(who)
+        }
+
+        /// Returns the account proposed as the next owner, if any.
+        #[ink(message)]
+        pub fn pending_owner(&self) -> Option<AccountId> {
+            self.pending_owner
+        }
+
+        /// Forces the value back to `to`. Only the owner may call this.
+        ///
+        /// `Flipped` is only emitted if the value actually changes.
+        #[ink(message)]
+        pub fn reset_value(&mut self, to: bool) -> Result<(), Error> {
+            debug_log!("reset_value: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            self.ensure_not_expired()?;
+            self.set_value(to);
+            Ok(())
+        }
+
+        /// Schedules a flip that anyone may execute from block `at_block` on.
+        ///
+        /// Only the owner may schedule, and only one flip can be pending at a time.
+        #[ink(message)]
+        pub fn schedule_flip(&mut self, at_block: BlockNumber) -> Result<(), Error> {
+            debug_log!("schedule_flip: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            if self.pending_flip.is_some() {
+                return Err(Error::AlreadyScheduled);
+            }
+            self.pending_flip = Some(at_block);
+            self.emit_config_changed(self.owner, "pending_flip", &None, &self.pending_flip);
+            Ok(())
+        }
+
+        /// Executes the scheduled flip once its block has been reached.
+        #[ink(message)]
+        pub fn execute_scheduled_flip(&mut self) -> Result<(), Error> {
+            debug_log!("execute_scheduled_flip: caller={:?}", self.env().caller());
+            self.admit_flip(false)?;
+            let at_block = self.pending_flip.ok_or(Error::NothingScheduled)?;
+            if self.env().block_number() < at_block {
+                return Err(Error::TooEarly);
+            }
+            self.pending_flip = None;
+            self.toggle();
+            Ok(())
+        }
+
+        /// Cancels the pending scheduled flip. Only the owner may call this.
+        #[ink(message)]
+        pub fn cancel_scheduled_flip(&mut self) -> Result<(), Error> {
+            debug_log!("cancel_scheduled_flip: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            let old = self.pending_flip.take();
+            if old.is_none() {
+                return Err(Error::NothingScheduled);
+            }
+            self.emit_config_changed(self.owner, "pending_flip", &old, &None);
+            Ok(())
+        }
+
+        /// Returns the block from which the scheduled flip may be executed, if any.
+        #[ink(message)]
+        pub fn pending_flip(&self) -> Option<BlockNumber> {
+            self.pending_flip
+        }
+
+        /// Sets the value to a pseudo-random bit and returns it.
+        ///
+        /// With the `chain-ext` feature the bit comes from the runtime's
+        /// `RandomnessExtension`, asked for randomness on
+        /// `blake2b_256(caller ++ seed)`, and the call fails with
+        /// `Error::RandomnessUnavailable` if the runtime has none.
+        ///
+        /// Otherwise the bit is derived from the caller, the current block
+        /// number and timestamp and the given `seed`. This is **not** secure
+        /// randomness: block producers and callers can predict or influence
+        /// the outcome, so don't use it for anything of value.
+        ///
+        /// `Flipped` is only emitted if the value actually changes.
+        #[ink(message)]
+        pub fn random_flip(&mut self, seed: Vec<u8>) -> Result<bool, Error> {
+            debug_log!("random_flip: caller={:?}", self.env().caller());
+            self.admit_flip(false)?;
+            let bit = self.random_bit(&seed)?;
+            self.set_value(bit);
+            Ok(bit)
+        }
+
+        /// Sets the number of blocks an account has to wait between two flips.
+        /// Only the owner may call this.
+        #[ink(message)]
+        pub fn set_cooldown(&mut self, cooldown_blocks: u32) -> Result<(), Error> {
+            debug_log!("set_cooldown: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            let old = core::mem::replace(&mut self.cooldown_blocks, cooldown_blocks);
+            self.emit_config_changed(self.owner, "cooldown_blocks", &old, &cooldown_blocks);
+            Ok(())
+        }
+
+        /// Returns the number of blocks `account` still has to wait before it may flip.
+        #[ink(message)]
+        pub fn cooldown_remaining(&self, account: AccountId) -> BlockNumber {
+            match self.last_flip_block_of.get(account) {
+                Some(last_block) => last_block
+                    .saturating_add(self.cooldown_blocks)
+                    .saturating_sub(self.env().block_number()),
+                None => 0,
+            }
+        }
+
+        /// Returns the most recent flips, oldest first.
+        ///
+        /// Only the last `HISTORY_CAPACITY` flips are kept; older ones are evicted.
+        #[ink(message)]
+        pub fn history(&self) -> Vec<FlipRecord> {
+            let mut history = self.history.get().unwrap_or_default();
+            history.rotate_left(self.history_head as usize);
+            history
+        }
+
+        /// Casts the caller's vote to flip the value.
+        ///
+        /// Once `vote_threshold` distinct accounts voted in the current round the
+        /// value flips, a new round starts and `Ok(true)` is returned.
+        #[ink(message)]
+        pub fn vote_flip(&mut self) -> Result<bool, Error> {
+            debug_log!("vote_flip: caller={:?}", self.env().caller());
+            self.admit_flip(false)?;
+            let voter = self.env().caller();
+            if self.voted_in_round.get(voter) == Some(self.vote_round) {
+                return Err(Error::AlreadyVoted);
+            }
+            self.voted_in_round.insert(voter, &self.vote_round);
+            self.votes = self.votes.saturating_add(1);
+            self.env().emit_event(VoteCast {
+                voter,
+                votes: self.votes,
+            });
+
+            if self.votes < self.vote_threshold {
+                return Ok(false);
+            }
+            self.vote_round = self.vote_round.wrapping_add(1);
+            self.votes = 0;
+            self.toggle();
+            Ok(true)
+        }
+
+        /// Sets the number of votes needed to flip. Only the owner may call this.
+        #[ink(message)]
+        pub fn set_vote_threshold(&mut self, threshold: u32) -> Result<(), Error> {
+            debug_log!("set_vote_threshold: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            if threshold == 0 {
+                return Err(Error::InvalidThreshold);
+            }
+            let old = core::mem::replace(&mut self.vote_threshold, threshold);
+            self.emit_config_changed(self.owner, "vote_threshold", &old, &threshold);
+            Ok(())
+        }
+
+        /// Returns the number of votes needed to flip.
+        #[ink(message)]
+        pub fn vote_threshold(&self) -> u32 {
+            self.vote_threshold
+        }
+
+        /// Returns the number of votes cast in the current round.
+        #[ink(message)]
+        pub fn votes(&self) -> u32 {
+            self.votes
+        }
+
+        /// Approves the pending multisig flip. Only signers may call this.
+        #[ink(message)]
+        pub fn approve_flip(&mut self) -> Result<(), Error> {
+            debug_log!("approve_flip: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            let caller = self.ensure_signer()?;
+            if self.approvals.contains(caller) {
+                return Err(Error::AlreadyApproved);
+            }
+            self.approvals.insert(caller, &());
+            self.approval_count += 1;
+            Ok(())
+        }
+
+        /// Withdraws the caller's approval of the pending multisig flip.
+        #[ink(message)]
+        pub fn revoke_approval(&mut self) -> Result<(), Error> {
+            debug_log!("revoke_approval: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            let caller = self.ensure_signer()?;
+            if !self.approvals.contains(caller) {
+                return Err(Error::NotApproved);
+            }
+            self.approvals.remove(caller);
+            self.approval_count -= 1;
+            Ok(())
+        }
+
+        /// Flips the value once `required_approvals` signers approved it, then
+        /// clears all approvals. Only signers may call this.
+        #[ink(message)]
+        pub fn execute_flip(&mut self) -> Result<(), Error> {
+            debug_log!("execute_flip: caller={:?}", self.env().caller());
+            self.admit_flip(false)?;
+            self.ensure_signer()?;
+            if self.approval_count < self.required_approvals {
+                return Err(Error::NotEnoughApprovals);
+            }
+            for signer in &self.signers {
+                self.approvals.remove(signer);
+            }
+            self.approval_count = 0;
+            self.toggle();
+            Ok(())
+        }
+
+        /// Returns the multisig signer set.
+        #[ink(message)]
+        pub fn signers(&self) -> Vec<AccountId> {
+            self.signers.clone()
+        }
+
+        /// Returns the number of approvals collected for the pending multisig flip.
+        #[ink(message)]
+        pub fn approval_count(&self) -> u32 {
+            self.approval_count
+        }
+
+        /// Stores `commitment = blake2b_256(salt ++ desired_value)` for the caller,
+        /// replacing any previous commitment.
+        ///
+        /// `desired_value` is encoded as a single byte, `0x00` or `0x01`.
+        #[ink(message)]
+        pub fn commit(&mut self, commitment: Hash) -> Result<(), Error> {
+            debug_log!("commit: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            self.commitments.insert(self.env().caller(), &commitment);
+            Ok(())
+        }
+
+        /// Reveals the caller's commitment and sets the value to `desired`.
+        ///
+        /// The commitment is cleared on success and kept on mismatch.
+        #[ink(message)]
+        pub fn reveal(&mut self, salt: Vec<u8>, desired: bool) -> Result<(), Error> {
+            debug_log!("reveal: caller={:?}", self.env().caller());
+            self.admit_flip(false)?;
+            let caller = self.env().caller();
+            let commitment = self.commitments.get(caller).ok_or(Error::NoCommitment)?;
+            if Self::commitment_of(&salt, desired) != commitment {
+                return Err(Error::CommitmentMismatch);
+            }
+            self.commitments.remove(caller);
+            self.set_value(desired);
+            Ok(())
+        }
+
+        /// Flips the value on behalf of the owner of `signer_pubkey`, so that a
+        /// relayer can submit the transaction and pay for it.
+        ///
+        /// The signer signs the BLAKE2b-256 hash of the SCALE encoding of
+        /// `(contract_account_id, nonce, FLIP_ACTION)`, i.e. the 32 account id
+        /// bytes, the nonce as little-endian `u64` and the raw bytes `b"flip"`.
+        /// `signature` is the 64 byte compact signature followed by the recovery
+        /// id. Frontends should query `signature_nonce` and sign with that nonce.
+        ///
+        /// Every nonce can be used once; afterwards only larger nonces are accepted.
+        #[ink(message)]
+        pub fn flip_with_signature(
+            &mut self,
+            signer_pubkey: [u8; 33],
+            nonce: u64,
+            signature: [u8; 65],
+        ) -> Result<(), Error> {
+            debug_log!("flip_with_signature: caller={:?}", self.env().caller());
+            self.admit_flip(false)?;
+            if nonce < self.signature_nonce(signer_pubkey) {
+                return Err(Error::NonceTooLow);
+            }
+            let payload = (self.env().account_id(), nonce, FLIP_ACTION).encode();
+            let message_hash = self.env().hash_bytes::<Blake2x256>(&payload);
+            match self.env().ecdsa_recover(&signature, &message_hash) {
+                Ok(recovered) if recovered == signer_pubkey => {}
+                _ => return Err(Error::InvalidSignature),
+            }
+            self.signature_nonces.insert(signer_pubkey, &nonce.saturating_add(1));
+            self.toggle();
+            Ok(())
+        }
+
+        /// Returns the lowest nonce `signer_pubkey` may sign a flip with.
+        #[ink(message)]
+        pub fn signature_nonce(&self, signer_pubkey: [u8; 33]) -> u64 {
+            self.signature_nonces.get(signer_pubkey).unwrap_or(0)
+        }
+
+        /// Returns whether `signature` is an sr25519 signature of `message` by
+        /// `pubkey`, made with the `substrate` signing context like Substrate
+        /// keys sign with.
+        ///
+        /// Only available with the `sr25519` feature, see `crate::sr25519` for
+        /// the runtime requirements.
+        #[cfg(feature = "sr25519")]
+        #[ink(message)]
+        pub fn verify_sr25519(&self, message: Vec<u8>, signature: [u8; 64], pubkey: [u8; 32]) -> bool {
+            crate::sr25519::verify(&signature, &message, &pubkey)
+        }
+
+        /// Like `flip_with_signature`, but for an sr25519 `signer_pubkey`.
+        ///
+        /// The signer signs the SCALE encoding of
+        /// `(contract_account_id, nonce, FLIP_ACTION)` itself, not its hash,
+        /// since sr25519 hashes the message while signing. Frontends should
+        /// query `sr25519_nonce` and sign with that nonce.
+        ///
+        /// Only available with the `sr25519` feature, see `crate::sr25519` for
+        /// the runtime requirements.
+        #[cfg(feature = "sr25519")]
+        #[ink(message)]
+        pub fn sr25519_flip(
+            &mut self,
+            signer_pubkey: [u8; 32],
+            nonce: u64,
+            signature: [u8; 64],
+        ) -> Result<(), Error> {
+            debug_log!("sr25519_flip: caller={:?}", self.env().caller());
+            self.admit_flip(false)?;
+            if nonce < self.sr25519_nonce(signer_pubkey) {
+                return Err(Error::NonceTooLow);
+            }
+            let payload = (self.env().account_id(), nonce, FLIP_ACTION).encode();
+            if !self.verify_sr25519(payload, signature, signer_pubkey) {
+                return Err(Error::InvalidSignature);
+            }
+            self.sr25519_nonces.insert(signer_pubkey, &nonce.saturating_add(1));
+            self.toggle();
+            Ok(())
+        }
+
+        /// Returns the lowest nonce `signer_pubkey` may sign an `sr25519_flip`
+        /// with.
+        #[cfg(feature = "sr25519")]
+        #[ink(message)]
+        pub fn sr25519_nonce(&self, signer_pubkey: [u8; 32]) -> u64 {
+            self.sr25519_nonces.get(signer_pubkey).unwrap_or(0)
+        }
+
+        /// Freezes all user-facing state changes. Only the owner may call this.
+        ///
+        /// Read-only messages and owner administration, including
+        /// `execute_action`, keep working while paused. So do `withdraw`,
+        /// `withdraw_entire_deposit`, `withdraw_refund`, `withdraw_all`,
+        /// `reclaim_deposit` and the fee and donation withdrawals: they only
+        /// hand out funds that are already owed, so a pause never locks anyone's
+        /// balance in the contract.
+        #[ink(message)]
+        pub fn pause(&mut self) -> Result<(), Error> {
+            debug_log!("pause: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            self.apply_pause()
+        }
+
+        /// Lifts a previous `pause`. Only the owner may call this.
+        #[ink(message)]
+        pub fn unpause(&mut self) -> Result<(), Error> {
+            debug_log!("unpause: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            self.apply_unpause()
+        }
+
+        /// Queues `action` and returns its id. It can be executed with
+        /// `execute_action` once `delay_blocks` blocks have passed. Only the
+        /// owner may call this.
+        #[ink(message)]
+        pub fn queue_action(&mut self, action: AdminAction) -> Result<u32, Error> {
+            debug_log!("queue_action: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            let id = self.next_action_id;
+            self.next_action_id = id.checked_add(1).ok_or(Error::TooManyActions)?;
+            let queued_at = self.env().block_number();
+            self.queued_actions.insert(
+                id,
+                &QueuedAction {
+                    action: action.clone(),
+                    queued_at,
+                },
+            );
+            self.env().emit_event(ActionQueued {
+                id,
+                action,
+                ready_at: queued_at.saturating_add(self.delay_blocks),
+            });
+            Ok(id)
+        }
+
+        /// Executes the queued action `id` once its delay has passed, exactly
+        /// like the corresponding owner message would. Anyone may call this,
+        /// as long as ownership has not been renounced.
+        ///
+        /// If the action itself fails, it stays queued. Like the owner
+        /// messages it stands in for, this keeps working while paused.
+        #[ink(message)]
+        pub fn execute_action(&mut self, id: u32) -> Result<(), Error> {
+            debug_log!(
+                "execute_action: caller={:?}, id={:?}",
+                self.env().caller(),
+                id
+            );
+            self.ensure_not_banned()?;
+            if self.owner_renounced {
+                return Err(Error::NotOwner);
+            }
+            let queued = self.queued_actions.get(id).ok_or(Error::ActionNotFound)?;
+            if self.env().block_number() < queued.queued_at.saturating_add(self.delay_blocks) {
+                return Err(Error::TooEarly);
+            }
+            match queued.action {
+                AdminAction::SetFeeBps(fee_bps) => self.apply_fee_bps(fee_bps)?,
+                AdminAction::Pause => self.apply_pause()?,
+                AdminAction::Unpause => self.apply_unpause()?,
+                AdminAction::TransferOwnership(new_owner) => {
+                    self.apply_transfer_ownership(new_owner)?
+                }
+            }
+            self.queued_actions.remove(id);
+            self.env().emit_event(ActionExecuted { id });
+            Ok(())
+        }
+
+        /// Drops the queued action `id`. Only the owner may call this.
+        #[ink(message)]
+        pub fn cancel_action(&mut self, id: u32) -> Result<(), Error> {
+            debug_log!(
+                "cancel_action: caller={:?}, id={:?}",
+                self.env().caller(),
+                id
+            );
+            self.ensure_owner()?;
+            self.queued_actions.take(id).ok_or(Error::ActionNotFound)?;
+            self.env().emit_event(ActionCancelled { id });
+            Ok(())
+        }
+
+        /// Returns the queued action `id`, if it is still pending.
+        #[ink(message)]
+        pub fn queued_action(&self, id: u32) -> Option<QueuedAction> {
+            self.queued_actions.get(id)
+        }
+
+        /// Returns how many blocks queued actions have to wait.
+        #[ink(message)]
+        pub fn delay_blocks(&self) -> BlockNumber {
+            self.delay_blocks
+        }
+
+        /// Selects the event emitted on every flip. Only the owner may call
+        /// this.
+        ///
+        /// With `verbose` set, flips emit `Flipped`, which indexers can filter
+        /// by caller. Otherwise they emit the anonymous `FlippedLite`, which
+        /// has no topics at all and only carries the new value. It is cheaper
+        /// for high-frequency flipping such as load tests, but can't be
+        /// filtered for, not even by event type.
+        #[ink(message)]
+        pub fn set_verbose_events(&mut self, verbose: bool) -> Result<(), Error> {
+            debug_log!("set_verbose_events: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            let old = core::mem::replace(&mut self.verbose_events, verbose);
+            self.emit_config_changed(self.owner, "verbose_events", &old, &verbose);
+            Ok(())
+        }
+
+        /// Returns whether flips emit the full `Flipped` event.
+        #[ink(message)]
+        pub fn verbose_events(&self) -> bool {
+            self.verbose_events
+        }
+
+        /// Returns the path and signature topic of every event with one, see
+        /// `event_topics::ALL`.
+        #[ink(message)]
+        pub fn event_topics(&self) -> Vec<(String, Hash)> {
+            crate::event_topics::ALL
+                .iter()
+                .map(|path| {
+                    let topic = crate::event_topics::signature_topic(path);
+                    (String::from(*path), Hash::from(topic))
+                })
+                .collect()
+        }
+
+        /// Returns whether the contract is paused.
+        #[ink(message)]
+        pub fn is_paused(&self) -> bool {
+            self.paused
+        }
+
+        /// Returns whether the expiry block has been reached.
+        ///
+        /// The contract counts as expired *at* the expiry block already, so the
+        /// last block in which the value can change is `expiry - 1`.
+        #[ink(message)]
+        pub fn is_expired(&self) -> bool {
+            self.expiry
+                .is_some_and(|expiry| self.env().block_number() >= expiry)
+        }
+
+        /// Returns the block from which the value is frozen, if any.
+        #[ink(message)]
+        pub fn expiry(&self) -> Option<BlockNumber> {
+            self.expiry
+        }
+
+        /// Returns the value as it was at the end of `block`.
+        ///
+        /// Returns `None` for blocks before instantiation, and for blocks older
+        /// than the oldest retained change point once old change points have
+        /// been evicted (see `set_max_change_points`).
+        #[ink(message)]
+        pub fn value_at(&self, block: BlockNumber) -> Option<bool> {
+            let change_points = self.change_points.get().unwrap_or_default();
+            let index = change_points.partition_point(|(changed_at, _)| *changed_at <= block);
+            let (_, value) = change_points.get(index.checked_sub(1)?)?;
+            Some(*value)
+        }
+
+        /// Sets how many change points are kept for `value_at`, evicting the
+        /// oldest ones if there are more. Only the owner may call this.
+        #[ink(message)]
+        pub fn set_max_change_points(&mut self, max: u32) -> Result<(), Error> {
+            debug_log!("set_max_change_points: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            if max == 0 {
+                return Err(Error::InvalidThreshold);
+            }
+            let old = core::mem::replace(&mut self.max_change_points, max);
+            self.emit_config_changed(self.owner, "max_change_points", &old, &max);
+            let mut change_points = self.change_points.get().unwrap_or_default();
+            self.evict_change_points(&mut change_points);
+            self.change_points.set(&change_points);
+            Ok(())
+        }
+
+        /// Flips the caller's personal flag.
+        #[ink(message)]
+        pub fn flip_mine(&mut self) -> Result<(), Error> {
+            debug_log!("flip_mine: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            self.set_personal_flag(caller, !self.get_of(caller));
+            Ok(())
+        }
+
+        /// Sets the caller's personal flag to `v`.
+        ///
+        /// Nothing is written and no event is emitted if the flag already equals `v`.
+        #[ink(message)]
+        pub fn set_mine(&mut self, v: bool) -> Result<(), Error> {
+            debug_log!("set_mine: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            self.set_personal_flag(self.env().caller(), v);
+            Ok(())
+        }
+
+        /// Approves or, with `approved` set to `false`, revokes `operator` to
+        /// act on the caller's behalf through `flip_for` and `set_mine_for`.
+        #[ink(message)]
+        pub fn approve_operator(&mut self, operator: AccountId, approved: bool) -> Result<(), Error> {
+            debug_log!("approve_operator: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            let owner = self.env().caller();
+            if approved {
+                self.operators.insert((owner, operator), &());
+            } else {
+                self.operators.remove((owner, operator));
+            }
+            self.env().emit_event(OperatorApproval {
+                owner,
+                operator,
+                approved,
+            });
+            Ok(())
+        }
+
+        /// Returns whether `operator` may act on behalf of `owner`.
+        #[ink(message)]
+        pub fn is_operator(&self, owner: AccountId, operator: AccountId) -> bool {
+            self.operators.contains((owner, operator))
+        }
+
+        /// Flips the personal flag of `on_behalf_of`, like `flip_mine` does
+        /// for the caller. Fails with `Error::NotOperator` unless the caller
+        /// is an approved operator of `on_behalf_of`.
+        #[ink(message)]
+        pub fn flip_for(&mut self, on_behalf_of: AccountId) -> Result<(), Error> {
+            debug_log!("flip_for: caller={:?}", self.env().caller());
+            self.ensure_operator_of(on_behalf_of)?;
+            self.ensure_not_paused()?;
+            self.set_personal_flag(on_behalf_of, !self.get_of(on_behalf_of));
+            Ok(())
+        }
+
+        /// Sets the personal flag of `on_behalf_of` to `v`, like `set_mine`
+        /// does for the caller. Fails with `Error::NotOperator` unless the
+        /// caller is an approved operator of `on_behalf_of`.
+        #[ink(message)]
+        pub fn set_mine_for(&mut self, on_behalf_of: AccountId, v: bool) -> Result<(), Error> {
+            debug_log!("set_mine_for: caller={:?}", self.env().caller());
+            self.ensure_operator_of(on_behalf_of)?;
+            self.ensure_not_paused()?;
+            self.set_personal_flag(on_behalf_of, v);
+            Ok(())
+        }
+
+        /// Returns the personal flag of `who`, `false` if they never set it.
+        #[ink(message)]
+        pub fn get_of(&self, who: AccountId) -> bool {
+            self.personal_flags.get(who).unwrap_or(false)
+        }
+
+        /// Sets how many personal flags must be `true` for `flip` to succeed.
+        /// Only the owner may call this.
+        #[ink(message)]
+        pub fn set_quorum(&mut self, quorum: u32) -> Result<(), Error> {
+            debug_log!("set_quorum: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            let old = core::mem::replace(&mut self.quorum, quorum);
+            self.emit_config_changed(self.owner, "quorum", &old, &quorum);
+            Ok(())
+        }
+
+        /// Returns how many personal flags must be `true` for `flip` to succeed.
+        #[ink(message)]
+        pub fn quorum(&self) -> u32 {
+            self.quorum
+        }
+
+        /// Returns the number of accounts whose personal flag is `true`.
+        #[ink(message)]
+        pub fn current_support(&self) -> u32 {
+            self.support
+        }
+
+        /// Returns the reason given for the latest `flip` or `flip_with_reason`.
+        #[ink(message)]
+        pub fn last_reason(&self) -> String {
+            self.last_reason.clone()
+        }
+
+        /// Replaces the contract-wide message and emits it in a
+        /// `MessageChanged` event. Only the owner may call this. Messages
+        /// longer than `MAX_MESSAGE_LEN` bytes, which may be fewer characters,
+        /// are rejected with `Error::MessageTooLong`.
+        #[ink(message)]
+        pub fn set_message(&mut self, msg: String) -> Result<(), Error> {
+            debug_log!("set_message: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            if msg.len() > MAX_MESSAGE_LEN {
+                return Err(Error::MessageTooLong);
+            }
+            self.env().emit_event(MessageChanged {
+                message: msg.clone(),
+            });
+            self.message = msg;
+            Ok(())
+        }
+
+        /// Returns the contract-wide message, empty until one is set.
+        #[ink(message)]
+        pub fn get_message(&self) -> String {
+            self.message.clone()
+        }
+
+        /// Sets the reference hash checked by `verify_document`. Only the
+        /// owner may call this.
+        #[ink(message)]
+        pub fn set_document_hash(&mut self, hash: Hash) -> Result<(), Error> {
+            debug_log!("set_document_hash: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            self.document_hash = hash;
+            self.env().emit_event(DocumentHashSet { hash });
+            Ok(())
+        }
+
+        /// Returns whether `preimage` hashes to the reference hash, see
+        /// `hash_of`.
+        #[ink(message)]
+        pub fn verify_document(&self, preimage: Vec<u8>) -> bool {
+            self.hash_of(preimage) == self.document_hash
+        }
+
+        /// Returns `blake2b_256(data)`, the hash `verify_document` compares.
+        #[ink(message)]
+        pub fn hash_of(&self, data: Vec<u8>) -> Hash {
+            Hash::from(self.env().hash_bytes::<Blake2x256>(&data))
+        }
+
+        /// Returns `blake2b_256(data)`, the hash used for commitments and
+        /// auction subjects. Inputs over `MAX_HASH_INPUT_LEN` bytes are
+        /// rejected with `Error::InputTooLarge`.
+        #[ink(message)]
+        pub fn hash_blake2(&self, data: Vec<u8>) -> Result<Hash, Error> {
+            if data.len() > MAX_HASH_INPUT_LEN {
+                return Err(Error::InputTooLarge);
+            }
+            Ok(Hash::from(self.env().hash_bytes::<Blake2x256>(&data)))
+        }
+
+        /// Returns `keccak_256(data)`. Inputs over `MAX_HASH_INPUT_LEN` bytes
+        /// are rejected with `Error::InputTooLarge`.
+        #[ink(message)]
+        pub fn hash_keccak(&self, data: Vec<u8>) -> Result<[u8; 32], Error> {
+            if data.len() > MAX_HASH_INPUT_LEN {
+                return Err(Error::InputTooLarge);
+            }
+            Ok(self.env().hash_bytes::<Keccak256>(&data))
+        }
+
+        /// Returns the stored example struct. Until one is set, it is empty
+        /// apart from `my_account`, which holds the deployer.
+        #[ink(message)]
+        pub fn get_my_struct(&self) -> MyStruct {
+            // The constructors always store one, the fallback just keeps this
+            // message free of panics.
+            self.my_struct.get().unwrap_or_else(|| MyStruct::empty(self.owner))
+        }
+
+        /// Stores `s` as the example struct. Only the owner may call this.
+        ///
+        /// Fails with `Error::MessageTooLong` if `my_string` exceeds
+        /// `MAX_MESSAGE_LEN` bytes and with `Error::VectorFull` if
+        /// `my_vector` exceeds `MAX_NUMBERS` entries.
+        #[ink(message)]
+        pub fn set_my_struct(&mut self, s: MyStruct) -> Result<(), Error> {
+            debug_log!("set_my_struct: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            if s.my_string.len() > MAX_MESSAGE_LEN {
+                return Err(Error::MessageTooLong);
+            }
+            if s.my_vector.len() > MAX_NUMBERS {
+                return Err(Error::VectorFull);
+            }
+            self.my_struct.set(&s);
+            Ok(())
+        }
+
+        /// Sets the caller's profile note. Notes longer than `MAX_NOTE_LEN`
+        /// bytes are rejected with `Error::NoteTooLong`.
+        #[ink(message)]
+        pub fn set_my_note(&mut self, note: String) -> Result<(), Error> {
+            debug_log!("set_my_note: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            if note.len() > MAX_NOTE_LEN {
+                return Err(Error::NoteTooLong);
+            }
+            let account = self.env().caller();
+            self.notes.insert(account, &note);
+            self.env().emit_event(NoteChanged {
+                account,
+                note: Some(note),
+            });
+            Ok(())
+        }
+
+        /// Returns the profile note of `who`, if any.
+        #[ink(message)]
+        pub fn note_of(&self, who: AccountId) -> Option<String> {
+            self.notes.get(who)
+        }
+
+        /// Removes the caller's profile note, releasing its storage deposit.
+        #[ink(message)]
+        pub fn clear_my_note(&mut self) -> Result<(), Error> {
+            debug_log!("clear_my_note: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            let account = self.env().caller();
+            self.notes.remove(account);
+            self.env().emit_event(NoteChanged {
+                account,
+                note: None,
+            });
+            Ok(())
+        }
+
+        /// Appends `number` to the number vector.
+        ///
+        /// The vector is a single storage cell, so every message touching it
+        /// loads and decodes all of it. That's why it is capped at
+        /// `MAX_NUMBERS` entries, beyond which this fails with
+        /// `Error::VectorFull`.
+        #[ink(message)]
+        pub fn push_number(&mut self, number: u32) -> Result<(), Error> {
+            debug_log!("push_number: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            let mut numbers = self.numbers.get().unwrap_or_default();
+            if numbers.len() >= MAX_NUMBERS {
+                return Err(Error::VectorFull);
+            }
+            numbers.push(number);
+            self.numbers.set(&numbers);
+            Ok(())
+        }
+
+        /// Removes and returns the last number, if any.
+        #[ink(message)]
+        pub fn pop_number(&mut self) -> Result<Option<u32>, Error> {
+            debug_log!("pop_number: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            let mut numbers = self.numbers.get().unwrap_or_default();
+            let Some(number) = numbers.pop() else {
+                return Ok(None);
+            };
+            self.numbers.set(&numbers);
+            Ok(Some(number))
+        }
+
+        /// Returns the number at `index`, if it exists.
+        #[ink(message)]
+        pub fn number_at(&self, index: u32) -> Option<u32> {
+            self.numbers.get().unwrap_or_default().get(index as usize).copied()
+        }
+
+        /// Returns how many numbers are stored.
+        #[ink(message)]
+        pub fn numbers_len(&self) -> u32 {
+            self.numbers.get().unwrap_or_default().len() as u32
+        }
+
+        /// Returns the minimum, maximum, sum and count of the stored numbers,
+        /// or `None` if there are none.
+        #[ink(message)]
+        pub fn stats(&self) -> Option<NumberStats> {
+            let numbers = self.numbers.get().unwrap_or_default();
+            let min = *numbers.iter().min()?;
+            let max = *numbers.iter().max()?;
+            // Summing in `u64` can't overflow: `MAX_NUMBERS * u32::MAX` fits.
+            let sum = numbers.iter().map(|&number| u64::from(number)).sum();
+            Some(NumberStats {
+                min,
+                max,
+                sum,
+                count: numbers.len() as u32,
+            })
+        }
+
+        /// Returns how many times `who` flipped the value.
+        #[ink(message)]
+        pub fn flips_of(&self, who: AccountId) -> u32 {
+            self.flips_of.get(who).unwrap_or(0)
+        }
+
+        /// Returns the `LEADERBOARD_SIZE` most active flippers, most active first.
+        ///
+        /// Accounts with equal counts keep the order in which they got there.
+        #[ink(message)]
+        pub fn leaderboard(&self) -> Vec<(AccountId, u32)> {
+            self.leaderboard.clone()
+        }
+
+        /// Returns the state a frontend typically displays in a single call.
+        #[ink(message)]
+        pub fn info(&self) -> FoobarInfo {
+            FoobarInfo {
+                value: self.value,
+                flip_count: self.flip_count,
+                last_flipper: self.last_flipper,
+                last_flip_block: self.last_flip_block(),
+                paused: self.paused,
+            }
+        }
+
+        /// Returns the current block number.
+        #[ink(message)]
+        pub fn current_block(&self) -> BlockNumber {
+            self.env().block_number()
+        }
+
+        /// Returns the timestamp of the current block in milliseconds.
+        #[ink(message)]
+        pub fn current_timestamp(&self) -> Timestamp {
+            self.env().block_timestamp()
+        }
+
+        /// Returns the block number and timestamp of the latest flip, or of
+        /// the instantiation if the value was never flipped.
+        #[ink(message)]
+        pub fn last_flip_at(&self) -> (BlockNumber, Timestamp) {
+            (self.last_flip_block, self.last_flip_timestamp)
+        }
+
+        /// Returns the hash of the code the contract currently runs, for
+        /// upgrade tooling. It is the default hash if it can't be determined.
+        #[ink(message)]
+        pub fn code_hash(&self) -> Hash {
+            self.own_code_hash()
+        }
+
+        /// Returns the account of the contract itself.
+        #[ink(message)]
+        pub fn account_id(&self) -> AccountId {
+            self.env().account_id()
+        }
+
+        /// Reports the gas left, the balances and the size of the root storage,
+        /// to debug out-of-gas failures. It is a mutating message so that it
+        /// is metered like one.
+        ///
+        /// Only available with the `debug` feature, which also enables the
+        /// debug log of every mutating message. Release builds should leave
+        /// it off.
+        #[cfg(feature = "debug")]
+        #[ink(message)]
+        pub fn debug_meter(&mut self) -> MeterInfo {
+            debug_log!("debug_meter: caller={:?}", self.env().caller());
+            let mut encoded = Vec::new();
+            ink::storage::traits::Storable::encode(self, &mut encoded);
+            MeterInfo {
+                gas_left: self.gas_left(),
+                balance: self.contract_balance(),
+                minimum_balance: self.minimum_balance(),
+                storage_size: encoded.len() as u32,
+            }
+        }
+
+        /// Returns the caller, as seen by the contract.
+        #[ink(message)]
+        pub fn whoami(&self) -> AccountId {
+            self.env().caller()
+        }
+
+        /// Returns what the contract knows about the caller, to debug why a
+        /// message is rejected.
+        ///
+        /// `is_allowed` tells whether the caller is on the flip allowlist,
+        /// which only matters while `allowlist_enabled` is set.
+        #[ink(message)]
+        pub fn caller_context(&self) -> CallerContext {
+            let caller = self.env().caller();
+            CallerContext {
+                caller,
+                is_owner: self.ensure_owner().is_ok(),
+                is_allowed: self.is_allowed(caller),
+                is_banned: self.is_banned(caller),
+                cooldown_remaining: self.cooldown_remaining(caller),
+                deposit: self.deposit_of(caller),
+            }
+        }
+
+        /// Registers a contract implementing `OnFlip` to be notified after every
+        /// flip. Only the owner may call this; at most `MAX_OBSERVERS` may be registered.
+        #[ink(message)]
+        pub fn register_observer(&mut self, observer: AccountId) -> Result<(), Error> {
+            debug_log!("register_observer: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            if self.observers.contains(&observer) {
+                return Err(Error::AlreadyObserver);
+            }
+            if self.observers.len() >= MAX_OBSERVERS {
+                return Err(Error::TooManyObservers);
+            }
+            let old = self.observers.clone();
+            self.observers.push(observer);
+            self.emit_config_changed(self.owner, "observers", &old, &self.observers);
+            Ok(())
+        }
+
+        /// Stops notifying `observer`. Only the owner may call this.
+        #[ink(message)]
+        pub fn unregister_observer(&mut self, observer: AccountId) -> Result<(), Error> {
+            debug_log!("unregister_observer: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            let index = self
+                .observers
+                .iter()
+                .position(|registered| *registered == observer)
+                .ok_or(Error::NotObserver)?;
+            let old = self.observers.clone();
+            self.observers.remove(index);
+            self.emit_config_changed(self.owner, "observers", &old, &self.observers);
+            Ok(())
+        }
+
+        /// Returns the registered observers.
+        #[ink(message)]
+        pub fn observers(&self) -> Vec<AccountId> {
+            self.observers.clone()
+        }
+
+        /// Returns how many observer notifications have failed so far.
+        #[ink(message)]
+        pub fn failed_notifications(&self) -> u32 {
+            self.failed_notifications
+        }
+
+        /// Sets the maximum number of `flip` calls per block. Only the owner may call this.
+        #[ink(message)]
+        pub fn set_max_flips_per_block(&mut self, max_per_block: u32) -> Result<(), Error> {
+            debug_log!("set_max_flips_per_block: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            let old = core::mem::replace(&mut self.max_flips_per_block, max_per_block);
+            self.emit_config_changed(self.owner, "max_flips_per_block", &old, &max_per_block);
+            Ok(())
+        }
+
+        /// Returns the maximum number of `flip` calls per block.
+        #[ink(message)]
+        pub fn max_flips_per_block(&self) -> u32 {
+            self.max_flips_per_block
+        }
+
+        /// Creates an auction for `subject`, sold by the caller, and returns
+        /// its id.
+        ///
+        /// Every bid after the first must exceed the highest bid by at least
+        /// `min_increment`. Bids below `reserve_price` are accepted, but the
+        /// auction only sells if the highest bid meets it. No bids are
+        /// accepted from `duration_ms` milliseconds after creation on. A bid
+        /// of at least `buy_now_price`, if given, wins the auction at once.
+        #[ink(message)]
+        pub fn create_auction(
+            &mut self,
+            name: String,
+            subject: Hash,
+            min_increment: Balance,
+            reserve_price: Balance,
+            duration_ms: u64,
+            buy_now_price: Option<Balance>,
+        ) -> Result<u32, Error> {
+            debug_log!("create_auction: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            if buy_now_price.is_some_and(|price| price < reserve_price) {
+                return Err(Error::InvalidBuyNowPrice);
+            }
+            let mut auction =
+                self.new_auction(name, subject, min_increment, reserve_price, duration_ms);
+            auction.buy_now_price = buy_now_price;
+            self.insert_auction(auction)
+        }
+
+        /// Creates a sealed-bid auction for `subject`, sold by the caller, and
+        /// returns its id.
+        ///
+        /// Bids are committed with `commit_bid` for `bidding_ms` milliseconds
+        /// and then revealed with `reveal_bid` during the following
+        /// `reveal_ms` milliseconds. The highest revealed bid wins, provided
+        /// it meets `reserve_price`.
+        #[ink(message)]
+        pub fn create_sealed_auction(
+            &mut self,
+            name: String,
+            subject: Hash,
+            reserve_price: Balance,
+            bidding_ms: u64,
+            reveal_ms: u64,
+        ) -> Result<u32, Error> {
+            debug_log!("create_sealed_auction: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            let mut auction = self.new_auction(name, subject, 0, reserve_price, bidding_ms);
+            auction.kind = AuctionKind::Sealed;
+            auction.reveal_end = auction.end_timestamp.saturating_add(reveal_ms);
+            self.insert_auction(auction)
+        }
+
+        /// Creates a Dutch auction for `subject`, sold by the caller, and
+        /// returns its id.
+        ///
+        /// The price falls linearly from `start_price` at creation to
+        /// `floor_price` after `duration_ms` milliseconds. The first `buy` at
+        /// the current price wins.
+        #[ink(message)]
+        pub fn create_dutch_auction(
+            &mut self,
+            name: String,
+            subject: Hash,
+            start_price: Balance,
+            floor_price: Balance,
+            duration_ms: u64,
+        ) -> Result<u32, Error> {
+            debug_log!("create_dutch_auction: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            if start_price < floor_price {
+                return Err(Error::InvalidPriceSchedule);
+            }
+            let mut auction = self.new_auction(name, subject, 0, 0, duration_ms);
+            auction.kind = AuctionKind::Dutch;
+            auction.start_price = start_price;
+            auction.floor_price = floor_price;
+            self.insert_auction(auction)
+        }
+
+        /// Returns the auction with id `auction_id`, if it exists.
+        #[ink(message)]
+        pub fn get_auction(&self, auction_id: u32) -> Option<Auction> {
+            self.auctions.get(auction_id)
+        }
+
+        /// Returns the lifecycle state of the auction, if it exists.
+        #[ink(message)]
+        pub fn status(&self, auction_id: u32) -> Option<Status> {
+            self.auctions.get(auction_id).map(|auction| auction.status)
+        }
+
+        /// Returns the number of auctions created so far.
+        #[ink(message)]
+        pub fn auction_count(&self) -> u32 {
+            self.next_auction_id
+        }
+
+        /// Returns up to `limit` auctions starting at the `offset`-th one, in
+        /// creation order. `limit` is capped at `MAX_PAGE_SIZE`.
+        #[ink(message)]
+        pub fn list_auctions(&self, offset: u32, limit: u32) -> Vec<(u32, AuctionSummary)> {
+            // Ids are handed out consecutively, so they double as the index.
+            let end = offset
+                .saturating_add(limit.min(MAX_PAGE_SIZE))
+                .min(self.next_auction_id);
+            (offset..end).filter_map(|id| self.summary_of(id)).collect()
+        }
+
+        /// Returns up to `limit` auctions in `status` starting at the
+        /// `offset`-th one. `limit` is capped at `MAX_PAGE_SIZE`.
+        ///
+        /// An auction leaving a status is replaced by the last one in it, so
+        /// the order is not the creation order and may change between pages.
+        #[ink(message)]
+        pub fn list_by_status(
+            &self,
+            status: Status,
+            offset: u32,
+            limit: u32,
+        ) -> Vec<(u32, AuctionSummary)> {
+            let end = offset
+                .saturating_add(limit.min(MAX_PAGE_SIZE))
+                .min(self.count_by_status(status.clone()));
+            (offset..end)
+                .filter_map(|position| self.auctions_by_status.get((status.clone(), position)))
+                .filter_map(|id| self.summary_of(id))
+                .collect()
+        }
+
+        /// Returns the number of auctions in `status`.
+        #[ink(message)]
+        pub fn count_by_status(&self, status: Status) -> u32 {
+            self.status_counts.get(status).unwrap_or(0)
+        }
+
+        /// Replaces the subject hash of the auction. Only its seller may call
+        /// this, and only before the auction starts.
+        #[ink(message)]
+        pub fn set_subject(&mut self, auction_id: u32, subject: Hash) -> Result<(), Error> {
+            debug_log!(
+                "set_subject: caller={:?}, auction_id={:?}",
+                self.env().caller(),
+                auction_id
+            );
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            let mut auction = self.auction(auction_id)?;
+            if self.env().caller() != auction.seller {
+                return Err(Error::NotSeller);
+            }
+            if auction.status != Status::NotStarted {
+                return Err(Error::AuctionAlreadyStarted);
+            }
+            auction.subject = subject;
+            self.auctions.insert(auction_id, &auction);
+            Ok(())
+        }
+
+        /// Sets what the winner of the English or sealed-bid auction pays. Only
+        /// its seller may call this, and only before the auction starts.
+        #[ink(message)]
+        pub fn set_settlement(
+            &mut self,
+            auction_id: u32,
+            settlement: SettlementKind,
+        ) -> Result<(), Error> {
+            debug_log!(
+                "set_settlement: caller={:?}, auction_id={:?}",
+                self.env().caller(),
+                auction_id
+            );
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            let mut auction = self.auction(auction_id)?;
+            if self.env().caller() != auction.seller {
+                return Err(Error::NotSeller);
+            }
+            if auction.kind == AuctionKind::Dutch {
+                return Err(Error::WrongAuctionKind);
+            }
+            if auction.status != Status::NotStarted {
+                return Err(Error::AuctionAlreadyStarted);
+            }
+            auction.settlement = settlement;
+            self.auctions.insert(auction_id, &auction);
+            Ok(())
+        }
+
+        /// Returns whether `blake2b_256(preimage)` is the subject hash of the
+        /// auction, e.g. to check item metadata against it.
+        #[ink(message)]
+        pub fn verify_subject(&self, auction_id: u32, preimage: Vec<u8>) -> bool {
+            self.auctions.get(auction_id).is_some_and(|auction| {
+                Hash::from(self.env().hash_bytes::<Blake2x256>(&preimage)) == auction.subject
+            })
+        }
+
+        /// Changes the minimum bid increment. Only the owner may call this,
+        /// and only before the auction starts.
+        #[ink(message)]
+        pub fn set_min_increment(
+            &mut self,
+            auction_id: u32,
+            min_increment: Balance,
+        ) -> Result<(), Error> {
+            debug_log!(
+                "set_min_increment: caller={:?}, auction_id={:?}",
+                self.env().caller(),
+                auction_id
+            );
+            self.ensure_owner()?;
+            let mut auction = self.auction(auction_id)?;
+            if auction.status != Status::NotStarted {
+                return Err(Error::AuctionAlreadyStarted);
+            }
+            let old = core::mem::replace(&mut auction.min_increment, min_increment);
+            self.auctions.insert(auction_id, &auction);
+            self.emit_config_changed(
+                self.owner,
+                "min_increment",
+                &(auction_id, old),
+                &(auction_id, min_increment),
+            );
+            Ok(())
+        }
+
+        /// Configures anti-sniping: a bid placed less than `window_ms` before
+        /// the deadline pushes it out by `extension_ms`, at most
+        /// `max_extensions` times. Only the owner may call this, and only
+        /// before the auction starts.
+        #[ink(message)]
+        pub fn set_extension_policy(
+            &mut self,
+            auction_id: u32,
+            window_ms: u64,
+            extension_ms: u64,
+            max_extensions: u32,
+        ) -> Result<(), Error> {
+            debug_log!(
+                "set_extension_policy: caller={:?}, auction_id={:?}",
+                self.env().caller(),
+                auction_id
+            );
+            self.ensure_owner()?;
+            let mut auction = self.auction(auction_id)?;
+            if auction.status != Status::NotStarted {
+                return Err(Error::AuctionAlreadyStarted);
+            }
+            let old = (
+                auction_id,
+                auction.extension_window_ms,
+                auction.extension_ms,
+                auction.max_extensions,
+            );
+            auction.extension_window_ms = window_ms;
+            auction.extension_ms = extension_ms;
+            auction.max_extensions = max_extensions;
+            self.auctions.insert(auction_id, &auction);
+            self.emit_config_changed(
+                self.owner,
+                "extension_policy",
+                &old,
+                &(auction_id, window_ms, extension_ms, max_extensions),
+            );
+            Ok(())
+        }
+
+        /// Opens the auction for bids. Only the owner may call this.
+        #[ink(message)]
+        pub fn start_auction(&mut self, auction_id: u32) -> Result<(), Error> {
+            debug_log!(
+                "start_auction: caller={:?}, auction_id={:?}",
+                self.env().caller(),
+                auction_id
+            );
+            self.ensure_not_banned()?;
+            self.transition(auction_id, Status::NotStarted, Status::OpeningPeriod)?;
+            self.live_auctions += 1;
+            Ok(())
+        }
+
+        /// Moves the auction from its opening period into the ending period.
+        /// Only the owner may call this.
+        #[ink(message)]
+        pub fn begin_ending_period(&mut self, auction_id: u32) -> Result<(), Error> {
+            debug_log!(
+                "begin_ending_period: caller={:?}, auction_id={:?}",
+                self.env().caller(),
+                auction_id
+            );
+            self.ensure_not_banned()?;
+            self.transition(auction_id, Status::OpeningPeriod, Status::EndingPeriod)
+        }
+
+        /// Ends the auction. Only the owner may call this.
+        #[ink(message)]
+        pub fn end_auction(&mut self, auction_id: u32) -> Result<(), Error> {
+            debug_log!(
+                "end_auction: caller={:?}, auction_id={:?}",
+                self.env().caller(),
+                auction_id
+            );
+            self.ensure_not_banned()?;
+            self.transition(auction_id, Status::EndingPeriod, Status::Ended)
+        }
+
+        /// Cancels the auction. Only its seller may call this, and only before
+        /// the first bid, while it has not started or is in its opening period.
+        #[ink(message)]
+        pub fn cancel_auction(&mut self, auction_id: u32) -> Result<(), Error> {
+            debug_log!(
+                "cancel_auction: caller={:?}, auction_id={:?}",
+                self.env().caller(),
+                auction_id
+            );
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            let mut auction = self.auction(auction_id)?;
+            if self.env().caller() != auction.seller {
+                return Err(Error::NotSeller);
+            }
+            if !matches!(auction.status, Status::NotStarted | Status::OpeningPeriod)
+                || auction.highest_bidder.is_some()
+            {
+                return Err(Error::CannotCancel);
+            }
+            let from = core::mem::replace(&mut auction.status, Status::Cancelled);
+            if from == Status::OpeningPeriod {
+                self.live_auctions -= 1;
+            }
+            self.auctions.insert(auction_id, &auction);
+            self.record_status_change(auction_id, from, Status::Cancelled);
+            self.env().emit_event(AuctionCancelled { id: auction_id });
+            Ok(())
+        }
+
+        /// Hands the auction over to `new_seller`, who receives the proceeds
+        /// when it is finalized. Only its seller may call this, and only until
+        /// the auction is finalized.
+        #[ink(message)]
+        pub fn transfer_auction(
+            &mut self,
+            auction_id: u32,
+            new_seller: AccountId,
+        ) -> Result<(), Error> {
+            debug_log!(
+                "transfer_auction: caller={:?}, auction_id={:?}",
+                self.env().caller(),
+                auction_id
+            );
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            let mut auction = self.auction(auction_id)?;
+            let from = self.env().caller();
+            if from != auction.seller {
+                return Err(Error::NotSeller);
+            }
+            if auction.finalized {
+                return Err(Error::AlreadyFinalized);
+            }
+            if auction.status == Status::Cancelled {
+                return Err(Error::Cancelled);
+            }
+            auction.seller = new_seller;
+            self.auctions.insert(auction_id, &auction);
+            self.env().emit_event(AuctionTransferred {
+                id: auction_id,
+                from,
+                to: new_seller,
+            });
+            Ok(())
+        }
+
+        /// Allows `who` to bid on the auction and restricts bidding to the
+        /// allowed accounts from then on. Only its seller may call this, and
+        /// only until the auction ends.
+        #[ink(message)]
+        pub fn add_allowed_bidder(&mut self, auction_id: u32, who: AccountId) -> Result<(), Error> {
+            debug_log!(
+                "add_allowed_bidder: caller={:?}, auction_id={:?}",
+                self.env().caller(),
+                auction_id
+            );
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            let mut auction = self.managed_auction(auction_id)?;
+            if !auction.restricted {
+                auction.restricted = true;
+                self.auctions.insert(auction_id, &auction);
+            }
+            self.allowed_bidders.insert((auction_id, who), &());
+            Ok(())
+        }
+
+        /// Removes `who` from the allowlist of the auction. A bid they already
+        /// placed stays valid. Only its seller may call this, and only until
+        /// the auction ends.
+        #[ink(message)]
+        pub fn remove_allowed_bidder(
+            &mut self,
+            auction_id: u32,
+            who: AccountId,
+        ) -> Result<(), Error> {
+            debug_log!(
+                "remove_allowed_bidder: caller={:?}, auction_id={:?}",
+                self.env().caller(),
+                auction_id
+            );
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            self.managed_auction(auction_id)?;
+            self.allowed_bidders.remove((auction_id, who));
+            Ok(())
+        }
+
+        /// Returns whether `who` may bid on the auction.
+        #[ink(message)]
+        pub fn is_allowed_bidder(&self, auction_id: u32, who: AccountId) -> bool {
+            self.auctions.get(auction_id).is_some_and(|auction| {
+                !auction.restricted || self.allowed_bidders.contains((auction_id, who))
+            })
+        }
+
+        /// Bids the transferred value on the auction.
+        ///
+        /// The bid must strictly exceed the current highest bid, and once there
+        /// is one, exceed it by at least `min_increment`. Bids are only
+        /// accepted during the opening and ending periods, and before the
+        /// deadline. Late bids may extend the deadline, see
+        /// `set_extension_policy`. The outbid amount is
+        /// credited to the previous highest bidder, see `withdraw_refund`.
+        ///
+        /// A bid of at least the buy-now price wins at that price and ends the
+        /// auction, which anyone may then finalize. The excess is credited
+        /// back to the bidder.
+        #[ink(message, payable)]
+        pub fn place_bid(&mut self, auction_id: u32) -> Result<(), Error> {
+            debug_log!(
+                "place_bid: caller={:?}, auction_id={:?}",
+                self.env().caller(),
+                auction_id
+            );
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            let bidder = self.env().caller();
+            let amount = self.accept_escrow();
+            let now = self.env().block_timestamp();
+            let mut auction = self.auction(auction_id)?;
+            if auction.kind != AuctionKind::English {
+                return Err(Error::WrongAuctionKind);
+            }
+            if !matches!(auction.status, Status::OpeningPeriod | Status::EndingPeriod) {
+                return Err(Error::AuctionNotOpen);
+            }
+            if now >= auction.end_timestamp {
+                return Err(Error::AuctionClosed);
+            }
+            if auction.restricted && !self.allowed_bidders.contains((auction_id, bidder)) {
+                return Err(Error::NotAllowed);
+            }
+            if amount <= auction.highest_bid {
+                return Err(Error::BidTooLow);
+            }
+            let bought_now = auction.buy_now_price.filter(|price| amount >= *price);
+            if bought_now.is_none()
+                && auction.highest_bidder.is_some()
+                && amount < auction.highest_bid.saturating_add(auction.min_increment)
+            {
+                return Err(Error::IncrementTooSmall);
+            }
+            if let Some(previous) = auction.highest_bidder {
+                self.credit_refund(previous, auction.highest_bid);
+            }
+            // Raising one's own bid leaves the runner-up in place.
+            if auction.highest_bidder != Some(bidder) {
+                auction.second_bid = auction.highest_bid;
+            }
+            auction.highest_bidder = Some(bidder);
+            if let Some(price) = bought_now {
+                auction.highest_bid = price;
+                let from = core::mem::replace(&mut auction.status, Status::Ended);
+                self.auctions.insert(auction_id, &auction);
+                self.credit_refund(bidder, amount - price);
+                self.record_bid(auction_id, bidder, price);
+                self.env().emit_event(BidPlaced {
+                    id: auction_id,
+                    bidder,
+                    amount: price,
+                });
+                self.record_status_change(auction_id, from, Status::Ended);
+                self.env().emit_event(BoughtNow {
+                    id: auction_id,
+                    buyer: bidder,
+                    price,
+                });
+                return Ok(());
+            }
+            auction.highest_bid = amount;
+            let extended = auction.extensions < auction.max_extensions
+                && auction.end_timestamp - now < auction.extension_window_ms;
+            if extended {
+                auction.end_timestamp = auction.end_timestamp.saturating_add(auction.extension_ms);
+                auction.extensions += 1;
+            }
+            self.auctions.insert(auction_id, &auction);
+            self.record_bid(auction_id, bidder, amount);
+            self.env().emit_event(BidPlaced {
+                id: auction_id,
+                bidder,
+                amount,
+            });
+            if extended {
+                self.env().emit_event(DeadlineExtended {
+                    id: auction_id,
+                    new_end: auction.end_timestamp,
+                });
+            }
+            Ok(())
+        }
+
+        /// Commits a sealed bid on a sealed-bid auction, depositing the
+        /// transferred value.
+        ///
+        /// `commitment` is `blake2b_256(amount ++ salt ++ caller)` with
+        /// `amount` as little-endian `u128` and `caller` as the 32 account id
+        /// bytes. The deposit must cover the amount revealed later; depositing
+        /// more than that hides the bid. Each account can commit once per
+        /// auction.
+        #[ink(message, payable)]
+        pub fn commit_bid(&mut self, auction_id: u32, commitment: Hash) -> Result<(), Error> {
+            debug_log!(
+                "commit_bid: caller={:?}, auction_id={:?}",
+                self.env().caller(),
+                auction_id
+            );
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            let bidder = self.env().caller();
+            let auction = self.auction(auction_id)?;
+            if auction.kind != AuctionKind::Sealed {
+                return Err(Error::WrongAuctionKind);
+            }
+            if !matches!(auction.status, Status::OpeningPeriod | Status::EndingPeriod) {
+                return Err(Error::AuctionNotOpen);
+            }
+            if self.env().block_timestamp() >= auction.end_timestamp {
+                return Err(Error::AuctionClosed);
+            }
+            if self.sealed_bids.contains((auction_id, bidder)) {
+                return Err(Error::AlreadyCommitted);
+            }
+            let sealed_bid = SealedBid {
+                commitment,
+                deposit: self.accept_escrow(),
+                revealed: false,
+            };
+            self.sealed_bids.insert((auction_id, bidder), &sealed_bid);
+            Ok(())
+        }
+
+        /// Reveals the caller's sealed bid of `amount` after the bidding
+        /// deadline and before the reveal deadline.
+        ///
+        /// The part of the deposit that is not bid, and the whole deposit of
+        /// bids that don't lead, is credited to the caller, see
+        /// `withdraw_refund`. A bid that gets outbid by a later reveal is
+        /// credited back to its bidder.
+        #[ink(message)]
+        pub fn reveal_bid(
+            &mut self,
+            auction_id: u32,
+            amount: Balance,
+            salt: Vec<u8>,
+        ) -> Result<(), Error> {
+            debug_log!(
+                "reveal_bid: caller={:?}, auction_id={:?}",
+                self.env().caller(),
+                auction_id
+            );
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            let bidder = self.env().caller();
+            let now = self.env().block_timestamp();
+            let mut auction = self.auction(auction_id)?;
+            if auction.kind != AuctionKind::Sealed {
+                return Err(Error::WrongAuctionKind);
+            }
+            if auction.status == Status::Cancelled {
+                return Err(Error::Cancelled);
+            }
+            if now < auction.end_timestamp || now >= auction.reveal_end {
+                return Err(Error::NotRevealPhase);
+            }
+            let mut sealed_bid = self
+                .sealed_bids
+                .get((auction_id, bidder))
+                .ok_or(Error::NoCommitment)?;
+            if sealed_bid.revealed {
+                return Err(Error::AlreadyRevealed);
+            }
+            if Self::bid_commitment(amount, &salt, &bidder) != sealed_bid.commitment {
+                return Err(Error::CommitmentMismatch);
+            }
+            if amount > sealed_bid.deposit {
+                return Err(Error::DepositTooLow);
+            }
+            sealed_bid.revealed = true;
+            self.sealed_bids.insert((auction_id, bidder), &sealed_bid);
+            self.record_bid(auction_id, bidder, amount);
+            if amount <= auction.highest_bid {
+                self.credit_refund(bidder, sealed_bid.deposit);
+                if amount > auction.second_bid {
+                    auction.second_bid = amount;
+                    self.auctions.insert(auction_id, &auction);
+                }
+                return Ok(());
+            }
+            if let Some(previous) = auction.highest_bidder {
+                self.credit_refund(previous, auction.highest_bid);
+            }
+            self.credit_refund(bidder, sealed_bid.deposit - amount);
+            auction.second_bid = auction.highest_bid;
+            auction.highest_bid = amount;
+            auction.highest_bidder = Some(bidder);
+            self.auctions.insert(auction_id, &auction);
+            self.env().emit_event(BidPlaced {
+                id: auction_id,
+                bidder,
+                amount,
+            });
+            Ok(())
+        }
+
+        /// Credits the deposit of a sealed bid that was never revealed back to
+        /// the caller, see `withdraw_refund`, and returns its amount.
+        ///
+        /// Deposits can be reclaimed once the reveal deadline has passed, or
+        /// right away if the auction was cancelled.
+        #[ink(message)]
+        pub fn reclaim_deposit(&mut self, auction_id: u32) -> Result<Balance, Error> {
+            debug_log!(
+                "reclaim_deposit: caller={:?}, auction_id={:?}",
+                self.env().caller(),
+                auction_id
+            );
+            self.ensure_not_banned()?;
+            let bidder = self.env().caller();
+            let auction = self.auction(auction_id)?;
+            if self.env().block_timestamp() < auction.reveal_end
+                && auction.status != Status::Cancelled
+            {
+                return Err(Error::RevealNotOver);
+            }
+            let sealed_bid = self
+                .sealed_bids
+                .get((auction_id, bidder))
+                .ok_or(Error::NoCommitment)?;
+            if sealed_bid.revealed {
+                return Err(Error::AlreadyRevealed);
+            }
+            self.sealed_bids.remove((auction_id, bidder));
+            self.credit_refund(bidder, sealed_bid.deposit);
+            Ok(sealed_bid.deposit)
+        }
+
+        /// Settles the ended auction by transferring the highest bid to the
+        /// seller. Only the owner may call this, unless the deadline has passed,
+        /// in which case anyone may and the auction is ended on the way, or
+        /// the auction was won at its buy-now price.
+        ///
+        /// An auction without bids is only marked as finalized. If the highest
+        /// bid is below the reserve price nothing is sold and the bid is
+        /// credited back to its bidder, see `withdraw_refund`.
+        ///
+        /// Sealed-bid auctions can only be finalized once the reveal deadline
+        /// has passed.
+        #[ink(message)]
+        pub fn finalize_auction(&mut self, auction_id: u32) -> Result<(), Error> {
+            debug_log!(
+                "finalize_auction: caller={:?}, auction_id={:?}",
+                self.env().caller(),
+                auction_id
+            );
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            let mut auction = self.auction(auction_id)?;
+            let now = self.env().block_timestamp();
+            if auction.kind == AuctionKind::Sealed && now < auction.reveal_end {
+                return Err(Error::RevealNotOver);
+            }
+            let deadline_passed = now >= auction.end_timestamp;
+            let bought_now = auction.buy_now_price.is_some_and(|price| auction.highest_bid >= price);
+            if !deadline_passed && !bought_now {
+                self.ensure_owner()?;
+            }
+            if auction.finalized {
+                return Err(Error::AlreadyFinalized);
+            }
+            if auction.status == Status::Cancelled {
+                return Err(Error::Cancelled);
+            }
+            let from = auction.status.clone();
+            if from != Status::Ended && !deadline_passed {
+                return Err(Error::AuctionNotEnded);
+            }
+            auction.status = Status::Ended;
+            auction.finalized = true;
+            // Settle first so that a failed payout leaves no trace, which
+            // `finalize_expired` relies on.
+            self.settle(auction_id, &auction)?;
+            self.auctions.insert(auction_id, &auction);
+            if from != Status::NotStarted {
+                self.live_auctions -= 1;
+            }
+            if from != Status::Ended {
+                self.record_status_change(auction_id, from, Status::Ended);
+            }
+            Ok(())
+        }
+
+        /// Looks at up to `max` auction ids, in order, finalizes those whose
+        /// deadline has passed and returns how many were settled. Anyone may
+        /// call this.
+        ///
+        /// Auctions that fail to settle are skipped and counted in the
+        /// `ExpiredFinalized` event. Since every id looked at counts against
+        /// `max`, settled or not, a result below `max` does not mean nothing is
+        /// left: `expired_cursor` tells where the next call starts.
+        ///
+        /// The scan starts at `expired_cursor` and moves it past every
+        /// finalized or cancelled auction at the front, so those are never
+        /// looked at again. An auction that is still running holds the cursor
+        /// back; expired auctions more than `max` ids past it can still be
+        /// settled with `finalize_auction`.
+        #[ink(message)]
+        pub fn finalize_expired(&mut self, max: u32) -> Result<u32, Error> {
+            debug_log!("finalize_expired: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            let now = self.env().block_timestamp();
+            let mut settled = 0;
+            let mut failed = 0;
+            let mut cursor = self.expired_cursor;
+            let end = self.next_auction_id.min(self.expired_cursor.saturating_add(max));
+            for auction_id in self.expired_cursor..end {
+                let Some(auction) = self.auctions.get(auction_id) else {
+                    continue;
+                };
+                let expired = now >= auction.end_timestamp
+                    && (auction.kind != AuctionKind::Sealed || now >= auction.reveal_end);
+                let done = if auction.finalized || auction.status == Status::Cancelled {
+                    true
+                } else if expired {
+                    match self.finalize_auction(auction_id) {
+                        Ok(()) => {
+                            settled += 1;
+                            true
+                        }
+                        Err(_) => {
+                            failed += 1;
+                            false
+                        }
+                    }
+                } else {
+                    false
+                };
+                if done && auction_id == cursor {
+                    cursor += 1;
+                }
+            }
+            self.expired_cursor = cursor;
+            self.env().emit_event(ExpiredFinalized { settled, failed });
+            Ok(settled)
+        }
+
+        /// Returns the lowest auction id `finalize_expired` still looks at.
+        #[ink(message)]
+        pub fn expired_cursor(&self) -> u32 {
+            self.expired_cursor
+        }
+
+        /// Buys the item of a Dutch auction at its current price, ending and
+        /// finalizing the auction.
+        ///
+        /// The transferred value must cover `current_price`; any overpayment is
+        /// credited back to the caller, see `withdraw_refund`.
+        #[ink(message, payable)]
+        pub fn buy(&mut self, auction_id: u32) -> Result<(), Error> {
+            debug_log!(
+                "buy: caller={:?}, auction_id={:?}",
+                self.env().caller(),
+                auction_id
+            );
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            let buyer = self.env().caller();
+            let paid = self.accept_escrow();
+            let mut auction = self.auction(auction_id)?;
+            if auction.kind != AuctionKind::Dutch {
+                return Err(Error::WrongAuctionKind);
+            }
+            if !matches!(auction.status, Status::OpeningPeriod | Status::EndingPeriod) {
+                return Err(Error::AuctionNotOpen);
+            }
+            if self.env().block_timestamp() >= auction.end_timestamp {
+                return Err(Error::AuctionClosed);
+            }
+            let price = self.price_of(&auction);
+            if paid < price {
+                return Err(Error::BidTooLow);
+            }
+            let from = core::mem::replace(&mut auction.status, Status::Ended);
+            auction.highest_bid = price;
+            auction.highest_bidder = Some(buyer);
+            auction.finalized = true;
+            self.live_auctions -= 1;
+            self.auctions.insert(auction_id, &auction);
+            self.credit_refund(buyer, paid - price);
+            self.record_bid(auction_id, buyer, price);
+            self.env().emit_event(BidPlaced {
+                id: auction_id,
+                bidder: buyer,
+                amount: price,
+            });
+            self.record_status_change(auction_id, from, Status::Ended);
+            self.settle(auction_id, &auction)
+        }
+
+        /// Returns the price a Dutch auction can currently be bought at, or the
+        /// highest bid for other auctions. Unknown auctions cost nothing.
+        #[ink(message)]
+        pub fn current_price(&self, auction_id: u32) -> Balance {
+            self.auctions
+                .get(auction_id)
+                .map_or(0, |auction| self.price_of(&auction))
+        }
+
+        /// Returns the milliseconds left until the auction's deadline, or zero
+        /// if it has passed or the auction does not exist.
+        #[ink(message)]
+        pub fn time_remaining(&self, auction_id: u32) -> u64 {
+            self.auctions.get(auction_id).map_or(0, |auction| {
+                auction.end_timestamp.saturating_sub(self.env().block_timestamp())
+            })
+        }
+
+        /// Returns whether the auction has a highest bid that meets its
+        /// reserve price.
+        #[ink(message)]
+        pub fn reserve_met(&self, auction_id: u32) -> bool {
+            self.auctions.get(auction_id).is_some_and(|auction| {
+                auction.highest_bidder.is_some() && auction.highest_bid >= auction.reserve_price
+            })
+        }
+
+        /// Returns the latest accepted bids of `bidder` on the auction as
+        /// `(amount, timestamp)` pairs, oldest first.
+        ///
+        /// Only the last `MAX_BIDS_PER_BIDDER` bids are kept; older ones are
+        /// evicted.
+        #[ink(message)]
+        pub fn bids_of(&self, auction_id: u32, bidder: AccountId) -> BidHistory {
+            self.bid_history.get((auction_id, bidder)).unwrap_or_default()
+        }
+
+        /// Returns the amount `account` can withdraw with `withdraw_refund`.
+        #[ink(message)]
+        pub fn pending_refund(&self, account: AccountId) -> Balance {
+            self.refundable_of(account)
+        }
+
+        /// Returns the total amount `who` was outbid by or overpaid across all
+        /// auctions and can withdraw with `withdraw_all`.
+        #[ink(message)]
+        pub fn refundable_of(&self, who: AccountId) -> Balance {
+            self.pending_refunds.get(who).unwrap_or(0)
+        }
+
+        /// Sets the protocol fee in basis points. Auctions keep the fee that
+        /// was in effect when they were created. Only the owner may call this.
+        #[ink(message)]
+        pub fn set_fee_bps(&mut self, fee_bps: u16) -> Result<(), Error> {
+            debug_log!("set_fee_bps: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            self.apply_fee_bps(fee_bps)
+        }
+
+        /// Returns the protocol fee in basis points charged on new auctions.
+        #[ink(message)]
+        pub fn fee_bps(&self) -> u16 {
+            self.fee_bps
+        }
+
+        /// Returns the protocol and `paid_flip` fees accrued so far and not
+        /// yet withdrawn, the owner's and the treasury's share together.
+        #[ink(message)]
+        pub fn collected_fees(&self) -> Balance {
+            self.owner_fees.saturating_add(self.treasury_fees)
+        }
+
+        /// Returns the owner's share of `collected_fees`.
+        #[ink(message)]
+        pub fn owner_fees(&self) -> Balance {
+            self.owner_fees
+        }
+
+        /// Returns the treasury's share of `collected_fees`.
+        #[ink(message)]
+        pub fn treasury_fees(&self) -> Balance {
+            self.treasury_fees
+        }
+
+        /// Transfers `amount` of the owner's share of the fees to `to`, or all
+        /// of it if `amount` is zero. Only the owner may call this.
+        ///
+        /// Without `to` the fees go to the `beneficiary` at the time of the
+        /// withdrawal.
+        ///
+        /// Returns `Error::NothingToWithdraw` if no fees have accrued and
+        /// `Error::InsufficientFees` if `amount` exceeds `owner_fees`.
+        /// The fees are deducted before the transfer and restored if it fails
+        /// with `Error::TransferFailed`.
+        #[ink(message)]
+        pub fn withdraw_fees(&mut self, to: Option<AccountId>, amount: Balance) -> Result<(), Error> {
+            debug_log!("withdraw_fees: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            let to = to.unwrap_or(self.beneficiary);
+            if self.owner_fees == 0 {
+                return Err(Error::NothingToWithdraw);
+            }
+            let amount = if amount == 0 { self.owner_fees } else { amount };
+            if amount > self.owner_fees {
+                return Err(Error::InsufficientFees);
+            }
+            self.owner_fees -= amount;
+            if self.env().transfer(to, amount).is_err() {
+                self.owner_fees += amount;
+                return Err(Error::TransferFailed);
+            }
+            self.env().emit_event(FeesWithdrawn { to, amount });
+            Ok(())
+        }
+
+        /// Transfers the owner's whole share of the fees to the `beneficiary`
+        /// and returns the amount, see `withdraw_fees`.
+        #[ink(message)]
+        pub fn withdraw_owner_fees(&mut self) -> Result<Balance, Error> {
+            debug_log!("withdraw_owner_fees: caller={:?}", self.env().caller());
+            let amount = self.owner_fees;
+            self.withdraw_fees(None, amount)?;
+            Ok(amount)
+        }
+
+        /// Transfers the treasury's whole share of the fees to the treasury
+        /// and returns the amount. Only the treasury may call this.
+        ///
+        /// Returns `Error::NothingToWithdraw` if there is nothing to withdraw.
+        /// The fees are restored if the transfer fails with
+        /// `Error::TransferFailed`.
+        #[ink(message)]
+        pub fn withdraw_treasury_fees(&mut self) -> Result<Balance, Error> {
+            debug_log!("withdraw_treasury_fees: caller={:?}", self.env().caller());
+            let to = self.treasury;
+            if self.env().caller() != to {
+                return Err(Error::NotTreasury);
+            }
+            let amount = core::mem::take(&mut self.treasury_fees);
+            if amount == 0 {
+                return Err(Error::NothingToWithdraw);
+            }
+            if self.env().transfer(to, amount).is_err() {
+                self.treasury_fees = amount;
+                return Err(Error::TransferFailed);
+            }
+            self.env().emit_event(FeesWithdrawn { to, amount });
+            Ok(amount)
+        }
+
+        /// Sets the account receiving the treasury's share of the fees,
+        /// including the share accrued so far. Only the owner may call this;
+        /// the share itself is fixed at construction.
+        #[ink(message)]
+        pub fn set_treasury(&mut self, treasury: AccountId) -> Result<(), Error> {
+            debug_log!("set_treasury: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            let old = core::mem::replace(&mut self.treasury, treasury);
+            self.emit_config_changed(self.owner, "treasury", &old, &treasury);
+            Ok(())
+        }
+
+        /// Returns the account receiving the treasury's share of the fees.
+        #[ink(message)]
+        pub fn treasury(&self) -> AccountId {
+            self.treasury
+        }
+
+        /// Returns the treasury's share of all fees in basis points.
+        #[ink(message)]
+        pub fn treasury_share_bps(&self) -> u16 {
+            self.treasury_share_bps
+        }
+
+        /// Sets the account designated for the contract's own payouts. Only
+        /// the owner may call this.
+        #[ink(message)]
+        pub fn set_beneficiary(&mut self, new: AccountId) -> Result<(), Error> {
+            debug_log!("set_beneficiary: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            let old = core::mem::replace(&mut self.beneficiary, new);
+            self.env().emit_event(BeneficiaryChanged { old, new });
+            Ok(())
+        }
+
+        /// Returns the account designated for the contract's own payouts.
+        #[ink(message)]
+        pub fn beneficiary(&self) -> AccountId {
+            self.beneficiary
+        }
+
+        /// Credits the transferred value to the caller's deposit.
+        ///
+        /// Returns `Error::ZeroDeposit` if no value was transferred.
+        #[ink(message, payable)]
+        pub fn deposit(&mut self) -> Result<(), Error> {
+            debug_log!("deposit: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            if self.env().transferred_value() == 0 {
+                return Err(Error::ZeroDeposit);
+            }
+            let amount = self.accept_value();
+            let from = self.env().caller();
+            let deposit = self.deposit_of(from).saturating_add(amount);
+            self.deposits.insert(from, &deposit);
+            self.total_deposits = self.total_deposits.saturating_add(amount);
+            self.env().emit_event(Deposited { from, amount });
+            Ok(())
+        }
+
+        /// Returns the value `who` has deposited.
+        #[ink(message)]
+        pub fn deposit_of(&self, who: AccountId) -> Balance {
+            self.deposits.get(who).unwrap_or_default()
+        }
+
+        /// Returns the sum of the deposits of all accounts.
+        #[ink(message)]
+        pub fn total_deposits(&self) -> Balance {
+            self.total_deposits
+        }
+
+        /// Handles calls to any selector that no other message has.
+        ///
+        /// Unless the owner turned `accept_donations` on, this fails with
+        /// `Error::DirectTransfersNotAccepted`, so value sent this way goes
+        /// back to the caller. Otherwise the value is added to `donations`
+        /// and logged in a `DonationReceived` event.
+        ///
+        /// This keeps such value accounted for. Value that reaches the
+        /// contract without any call, such as a plain balance transfer, can
+        /// still only be recovered with `transfer_out`.
+        ///
+        /// With `strict_value_checks` any value is rejected with
+        /// `Error::UnexpectedPayment`, so no donations can be made this way.
+        #[ink(message, payable, selector = _)]
+        pub fn receive(&mut self) -> Result<(), Error> {
+            debug_log!("receive: caller={:?}", self.env().caller());
+            self.ensure_no_value()?;
+            self.ensure_not_banned()?;
+            self.ensure_not_paused()?;
+            if !self.accept_donations {
+                return Err(Error::DirectTransfersNotAccepted);
+            }
+            let amount = self.accept_value();
+            if amount > 0 {
+                self.donations = self.donations.saturating_add(amount);
+                let donor = self.env().caller();
+                let total = self.record_donation(donor, amount);
+                self.env().emit_event(DonationReceived { donor, amount, total });
+            }
+            Ok(())
+        }
+
+        /// Sets whether `receive` accepts donations. Only the owner may call
+        /// this.
+        #[ink(message)]
+        pub fn set_accept_donations(&mut self, accept: bool) -> Result<(), Error> {
+            debug_log!("set_accept_donations: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            let old = core::mem::replace(&mut self.accept_donations, accept);
+            self.emit_config_changed(self.owner, "accept_donations", &old, &accept);
+            Ok(())
+        }
+
+        /// Returns whether `receive` accepts donations.
+        #[ink(message)]
+        pub fn accept_donations(&self) -> bool {
+            self.accept_donations
+        }
+
+        /// Returns whether messages that are payable only for technical
+        /// reasons reject value, see `new_with_strict_value_checks`.
+        #[ink(message)]
+        pub fn strict_value_checks(&self) -> bool {
+            self.strict_value_checks
+        }
+
+        /// Returns the value the message with `selector` expects to be sent
+        /// with it: `flip_fee` for `paid_flip`, `VALUE_DEPENDS_ON_CALL` for
+        /// `deposit` and the bidding messages, and 0 for all others, which
+        /// reject value.
+        ///
+        /// Selectors of messages that don't exist reach `receive`, which only
+        /// keeps value while `accept_donations` is on and `strict_value_checks`
+        /// is off. This view can't tell them apart and returns 0 for them too.
+        #[ink(message)]
+        pub fn expected_value_of(&self, selector: [u8; 4]) -> Balance {
+            const PAID_FLIP: [u8; 4] = ink::selector_bytes!("paid_flip");
+            const DEPOSIT: [u8; 4] = ink::selector_bytes!("deposit");
+            const PLACE_BID: [u8; 4] = ink::selector_bytes!("place_bid");
+            const COMMIT_BID: [u8; 4] = ink::selector_bytes!("commit_bid");
+            const BUY: [u8; 4] = ink::selector_bytes!("buy");
+            match selector {
+                PAID_FLIP => self.flip_fee,
+                DEPOSIT | PLACE_BID | COMMIT_BID | BUY => VALUE_DEPENDS_ON_CALL,
+                _ => 0,
+            }
+        }
+
+        /// Returns the donations received and not yet withdrawn.
+        #[ink(message)]
+        pub fn donations(&self) -> Balance {
+            self.donations
+        }
+
+        /// Returns the sum of all donations `who` has made.
+        #[ink(message)]
+        pub fn donated_by(&self, who: AccountId) -> Balance {
+            self.donated.get(who).unwrap_or_default()
+        }
+
+        /// Returns the `TOP_DONORS_SIZE` largest donors, largest first.
+        #[ink(message)]
+        pub fn top_donors(&self) -> Vec<(AccountId, Balance)> {
+            self.top_donors.clone()
+        }
+
+        /// Transfers all donations to `to` and returns the amount. Only the
+        /// owner may call this.
+        ///
+        /// Without `to` the donations go to the `beneficiary` at the time of
+        /// the withdrawal.
+        ///
+        /// Returns `Error::NothingToWithdraw` if there are none. The donations
+        /// are restored if the transfer fails with `Error::TransferFailed`.
+        #[ink(message)]
+        pub fn withdraw_donations(&mut self, to: Option<AccountId>) -> Result<Balance, Error> {
+            debug_log!("withdraw_donations: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            let to = to.unwrap_or(self.beneficiary);
+            let amount = core::mem::take(&mut self.donations);
+            if amount == 0 {
+                return Err(Error::NothingToWithdraw);
+            }
+            if self.env().transfer(to, amount).is_err() {
+                self.donations = amount;
+                return Err(Error::TransferFailed);
+            }
+            self.env().emit_event(DonationsWithdrawn { to, amount });
+            Ok(amount)
+        }
+
+        /// Transfers `amount` of the caller's deposit back to them.
+        ///
+        /// Returns `Error::InsufficientDeposit` if `amount` exceeds the
+        /// caller's deposit and `Error::NothingToWithdraw` if it is zero.
+        ///
+        /// The contract's balance must not drop below the existential deposit,
+        /// see `minimum_balance`. A withdrawal that would leave less fails
+        /// with `Error::TransferFailed`, even if the caller's deposit covers
+        /// it, which can happen once other funds have left the contract.
+        #[ink(message)]
+        pub fn withdraw(&mut self, amount: Balance) -> Result<(), Error> {
+            debug_log!("withdraw: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            self.withdraw_deposit(amount)
+        }
+
+        /// Transfers the caller's whole deposit back to them and returns the
+        /// amount, see `withdraw`.
+        ///
+        /// Not to be confused with `withdraw_all`, which pays out auction
+        /// refunds.
+        #[ink(message)]
+        pub fn withdraw_entire_deposit(&mut self) -> Result<Balance, Error> {
+            debug_log!("withdraw_entire_deposit: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            let amount = self.deposit_of(self.env().caller());
+            self.withdraw_deposit(amount)?;
+            Ok(amount)
+        }
+
+        /// Returns the sum of the value ever transferred with payable
+        /// messages.
+        ///
+        /// Payouts and refunds don't reduce it, so it drifts apart from
+        /// `contract_free_balance` as soon as funds leave the contract.
+        #[ink(message)]
+        pub fn total_received(&self) -> Balance {
+            self.total_received
+        }
+
+        /// Returns the contract's current free balance.
+        /// Same as `contract_balance`.
+        #[ink(message)]
+        pub fn contract_free_balance(&self) -> Balance {
+            self.contract_balance()
+        }
+
+        /// Returns the contract's current free balance, including the part
+        /// locked by `minimum_balance`.
+        #[ink(message)]
+        pub fn contract_balance(&self) -> Balance {
+            self.env().balance()
+        }
+
+        /// Returns the existential deposit the contract's balance must not
+        /// drop below.
+        #[ink(message)]
+        pub fn minimum_balance(&self) -> Balance {
+            self.env().minimum_balance()
+        }
+
+        /// Returns how much of `contract_balance` can be transferred out,
+        /// that is everything above `minimum_balance`.
+        #[ink(message)]
+        pub fn spendable_balance(&self) -> Balance {
+            self.contract_balance().saturating_sub(self.minimum_balance())
+        }
+
+        /// Returns the value received for auctions that hasn't been paid out
+        /// yet: bids and deposits held by auctions, and refunds not yet
+        /// withdrawn.
+        #[ink(message)]
+        pub fn escrowed_bids(&self) -> Balance {
+            self.escrowed_bids
+        }
+
+        /// Returns how much `transfer_out` may move, that is the part of
+        /// `spendable_balance` no one else has a claim on.
+        ///
+        /// Deposits, `escrowed_bids`, `collected_fees` and `donations` are
+        /// excluded, the latter two because they have their own withdrawal
+        /// messages. What remains is value that reached the contract without
+        /// going through any of them, such as a plain balance transfer.
+        #[ink(message)]
+        pub fn sweepable_balance(&self) -> Balance {
+            self.spendable_balance()
+                .saturating_sub(self.total_deposits)
+                .saturating_sub(self.escrowed_bids)
+                .saturating_sub(self.collected_fees())
+                .saturating_sub(self.donations)
+        }
+
+        /// Transfers `amount` of `sweepable_balance` to `to`. Only the owner
+        /// may call this.
+        ///
+        /// Returns `Error::ExceedsSweepable` if `amount` exceeds
+        /// `sweepable_balance`.
+        #[ink(message)]
+        pub fn transfer_out(&mut self, to: AccountId, amount: Balance) -> Result<(), Error> {
+            debug_log!("transfer_out: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            if amount > self.sweepable_balance() {
+                return Err(Error::ExceedsSweepable);
+            }
+            self.env()
+                .transfer(to, amount)
+                .map_err(|_| Error::TransferFailed)?;
+            self.env().emit_event(SweptOut { to, amount });
+            Ok(())
+        }
+
+        /// Like `transfer_out`, but dispatches `Balances::transfer_keep_alive`
+        /// through `call_runtime`, so the transfer emits the usual
+        /// pallet-balances events. Only the owner may call this.
+        ///
+        /// Only available with the `call-runtime` feature. `call_runtime` is
+        /// part of pallet-contracts' unstable interface, so the runtime has to
+        /// set `Config::UnsafeUnstableInterface` to `ConstBool<true>` and let
+        /// `Config::CallFilter` through balance transfers. See `RuntimeCall`
+        /// for the call indices. Fails with `Error::RuntimeCallFailed` if the
+        /// runtime rejects the call.
+        #[cfg(feature = "call-runtime")]
+        #[ink(message)]
+        pub fn runtime_transfer(&mut self, to: AccountId, amount: Balance) -> Result<(), Error> {
+            debug_log!("runtime_transfer: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            if amount > self.sweepable_balance() {
+                return Err(Error::ExceedsSweepable);
+            }
+            self.env()
+                .call_runtime(&RuntimeCall::Balances(BalancesCall::TransferKeepAlive {
+                    dest: MultiAddress::Id(to),
+                    value: amount,
+                }))
+                .map_err(|_| Error::RuntimeCallFailed)?;
+            self.env().emit_event(SweptOut { to, amount });
+            Ok(())
+        }
+
+        /// Returns the value transferred when the contract was instantiated.
+        #[ink(message)]
+        pub fn initial_endowment(&self) -> Balance {
+            self.initial_endowment
+        }
+
+        /// Removes the contract and sends its whole balance to `beneficiary`.
+        /// Only the owner may call this.
+        ///
+        /// Returns `Error::CannotTerminate` while an auction has started but
+        /// is neither finalized nor cancelled, or while any deposits remain.
+        /// The same goes for value still held in `escrowed_bids`, such as
+        /// refunds not yet claimed with `withdraw_refund` and unrevealed
+        /// sealed-bid deposits, and for `treasury_fees` not yet withdrawn.
+        ///
+        /// On success this never returns, so all checks happen up front. The
+        /// `Terminated` event emitted right before still lands.
+        #[ink(message)]
+        pub fn terminate(&mut self, beneficiary: AccountId) -> Result<(), Error> {
+            debug_log!("terminate: caller={:?}", self.env().caller());
+            self.ensure_owner()?;
+            if self.has_live_auction()
+                || self.total_deposits > 0
+                || self.escrowed_bids > 0
+                || self.treasury_fees > 0
+            {
+                return Err(Error::CannotTerminate);
+            }
+            self.env().emit_event(Terminated {
+                beneficiary,
+                balance: self.contract_balance(),
+            });
+            self.env().terminate_contract(beneficiary)
+        }
+
+        /// Transfers the caller's accumulated outbid amounts back to them.
+        /// Same as `withdraw_all`.
+        #[ink(message)]
+        pub fn withdraw_refund(&mut self) -> Result<Balance, Error> {
+            debug_log!("withdraw_refund: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            self.withdraw_all()
+        }
+
+        /// Transfers everything the caller can claim back across all auctions,
+        /// see `refundable_of`, and returns the amount.
+        #[ink(message)]
+        pub fn withdraw_all(&mut self) -> Result<Balance, Error> {
+            debug_log!("withdraw_all: caller={:?}", self.env().caller());
+            self.ensure_not_banned()?;
+            let caller = self.env().caller();
+            let amount = self.refundable_of(caller);
+            if amount == 0 {
+                return Err(Error::NothingToWithdraw);
+            }
+            // Clear the entry before transferring so the refund can't be
+            // claimed twice.
+            self.pending_refunds.remove(caller);
+            self.escrowed_bids = self.escrowed_bids.saturating_sub(amount);
+            self.env()
+                .transfer(caller, amount)
+                .map_err(|_| Error::TransferFailed)?;
+            Ok(amount)
+        }
+
+        /// Returns whether any auction has started but is neither finalized
+        /// nor cancelled, and so may still hold bids.
+        fn has_live_auction(&self) -> bool {
+            self.live_auctions > 0
+        }
+
+        /// Shared logic of `withdraw` and `withdraw_entire_deposit`.
+        fn withdraw_deposit(&mut self, amount: Balance) -> Result<(), Error> {
+            let caller = self.env().caller();
+            let deposit = self.deposit_of(caller);
+            if amount > deposit {
+                return Err(Error::InsufficientDeposit);
+            }
+            if amount == 0 {
+                return Err(Error::NothingToWithdraw);
+            }
+            // Checked up front, as the transfer would otherwise trap in the
+            // off-chain environment instead of failing.
+            if amount > self.spendable_balance() {
+                return Err(Error::TransferFailed);
+            }
+            // Deduct before transferring so the deposit can't be claimed twice.
+            self.deposits.insert(caller, &(deposit - amount));
+            self.total_deposits -= amount;
+            if self.env().transfer(caller, amount).is_err() {
+                self.deposits.insert(caller, &deposit);
+                self.total_deposits += amount;
+                return Err(Error::TransferFailed);
+            }
+            Ok(())
+        }
+
+        /// Adds `who` to or removes them from the flip allowlist, emitting
+        /// `AllowlistUpdated` if that changed anything.
+        fn set_allowlisted(&mut self, who: AccountId, allowed: bool) {
+            let changed = if allowed {
+                self.flip_allowlist.insert(who, &()).is_none()
+            } else {
+                self.flip_allowlist.take(who).is_some()
+            };
+            if changed {
+                self.env().emit_event(AllowlistUpdated {
+                    admin: self.owner,
+                    account: who,
+                    allowed,
+                });
+            }
+        }
+
+        /// Emits `ConfigChanged` for the setting `key` with the SCALE encoded
+        /// values.
+        fn emit_config_changed<T: Encode>(&self, admin: AccountId, key: &str, old: &T, new: &T) {
+            self.env().emit_event(ConfigChanged {
+                admin,
+                key: String::from(key),
+                old: old.encode(),
+                new: new.encode(),
+            });
+        }
+
+        /// Shared logic of `pause` and `AdminAction::Pause`. The owner who
+        /// authorized it is logged as `by`, also when executed by someone else
+        /// through the timelock.
+        fn apply_pause(&mut self) -> Result<(), Error> {
+            self.ensure_not_paused()?;
+            self.paused = true;
+            self.env().emit_event(Paused { by: self.owner });
+            Ok(())
+        }
+
+        /// Shared logic of `unpause` and `AdminAction::Unpause`, logged like
+        /// `apply_pause`.
+        fn apply_unpause(&mut self) -> Result<(), Error> {
+            if !self.paused {
+                return Err(Error::NotPaused);
+            }
+            self.paused = false;
+            self.env().emit_event(Unpaused { by: self.owner });
+            Ok(())
+        }
+
+        /// Shared logic of `set_fee_bps` and `AdminAction::SetFeeBps`, logged
+        /// like `apply_pause`.
+        fn apply_fee_bps(&mut self, fee_bps: u16) -> Result<(), Error> {
+            if fee_bps > MAX_FEE_BPS {
+                return Err(Error::FeeTooHigh);
+            }
+            let old = core::mem::replace(&mut self.fee_bps, fee_bps);
+            self.emit_config_changed(self.owner, "fee_bps", &old, &fee_bps);
+            Ok(())
+        }
+
+        /// Shared logic of `transfer_ownership` and
+        /// `AdminAction::TransferOwnership`.
+        fn apply_transfer_ownership(&mut self, new_owner: AccountId) -> Result<(), Error> {
+            if new_owner == AccountId::from([0x00; 32]) {
+                return Err(Error::InvalidOwner);
+            }
+            self.set_owner(new_owner);
+            Ok(())
+        }
+
+        /// Makes `new_owner` the owner, dropping any pending proposal and
+        /// moving `Role::Admin` along with ownership.
+        fn set_owner(&mut self, new_owner: AccountId) {
+            self.pending_owner = None;
+            let previous = core::mem::replace(&mut self.owner, new_owner);
+            self.hand_over_admin(previous, Some(new_owner));
+            self.env().emit_event(OwnershipTransferred {
+                previous,
+                new: new_owner,
+            });
+        }
+
+        /// Revokes `Role::Admin` from the `previous` owner and grants it to
+        /// the `new` one, if any.
+        fn hand_over_admin(&mut self, previous: AccountId, new: Option<AccountId>) {
+            if self.roles.take((previous, Role::Admin)).is_some() {
+                self.admin_count -= 1;
+                self.env().emit_event(RoleRevoked { role: Role::Admin, account: previous });
+            }
+            if let Some(new) = new {
+                if self.roles.insert((new, Role::Admin), &()).is_none() {
+                    self.admin_count += 1;
+                    self.env().emit_event(RoleGranted { role: Role::Admin, account: new });
+                }
+            }
+        }
+
+        /// Returns `Error::Banned` if the caller is banned.
+        fn ensure_not_banned(&self) -> Result<(), Error> {
+            if self.is_banned(self.env().caller()) {
+                return Err(Error::Banned);
+            }
+            Ok(())
+        }
+
+        /// Returns `Error::NotOperator` unless the caller is an approved
+        /// operator of `account`, or `Error::Banned` if either is banned.
+        fn ensure_operator_of(&self, account: AccountId) -> Result<(), Error> {
+            self.ensure_not_banned()?;
+            if self.is_banned(account) {
+                return Err(Error::Banned);
+            }
+            if !self.is_operator(account, self.env().caller()) {
+                return Err(Error::NotOperator);
+            }
+            Ok(())
+        }
+
+        /// Returns `Error::MissingRole` unless the caller holds `role`, or
+        /// `Error::Banned` if they are banned. Once ownership is renounced,
+        /// nobody holds `Role::Admin` any more.
+        fn ensure_role(&self, role: Role) -> Result<(), Error> {
+            self.ensure_not_banned()?;
+            if (role == Role::Admin && self.owner_renounced)
+                || !self.has_role(role, self.env().caller())
+            {
+                return Err(Error::MissingRole);
+            }
+            Ok(())
+        }
+
+        /// Returns `Error::NotOwner` unless the caller is the owner.
+        fn ensure_owner(&self) -> Result<(), Error> {
+            if self.owner_renounced || self.env().caller() != self.owner {
+                return Err(Error::NotOwner);
+            }
+            Ok(())
+        }
+
+        /// Returns the bit used by `random_flip`, from the runtime's
+        /// `RandomnessExtension`.
+        #[cfg(feature = "chain-ext")]
+        fn random_bit(&self, seed: &[u8]) -> Result<bool, Error> {
+            let mut input = Vec::new();
+            input.extend_from_slice(self.env().caller().as_ref());
+            input.extend_from_slice(seed);
+            let subject = self.env().hash_bytes::<Blake2x256>(&input);
+            let random = self
+                .env()
+                .extension()
+                .fetch_random(subject)
+                .map_err(|_| Error::RandomnessUnavailable)?;
+            Ok(random[0] & 1 == 1)
+        }
+
+        /// Returns the bit used by `random_flip`, see `entropy_bit`.
+        #[cfg(not(feature = "chain-ext"))]
+        fn random_bit(&self, seed: &[u8]) -> Result<bool, Error> {
+            Ok(self.entropy_bit(seed))
+        }
+
+        /// Derives a pseudo-random bit from
+        /// `blake2b_256(caller ++ block_number ++ block_timestamp ++ seed)`.
+        #[cfg(not(feature = "chain-ext"))]
+        fn entropy_bit(&self, seed: &[u8]) -> bool {
+            let mut input = Vec::new();
+            input.extend_from_slice(self.env().caller().as_ref());
+            input.extend_from_slice(&self.env().block_number().to_le_bytes());
+            input.extend_from_slice(&self.env().block_timestamp().to_le_bytes());
+            input.extend_from_slice(seed);
+            let hash = self.env().hash_bytes::<Blake2x256>(&input);
+            hash[0] & 1 == 1
+        }
+
+        /// Sets the value to `target` and returns whether it changed.
+        fn set_value(&mut self, target: bool) -> bool {
+            if self.value == target {
+                return false;
+            }
+            self.toggle();
+            true
+        }
+
+        /// Toggles the value, updates the flip bookkeeping and emits `Flipped`.
+        fn toggle(&mut self) {
+            self.apply_flips(1);
+        }
+
+        /// Applies `times` logical flips at once.
+        ///
+        /// Only the parity of `times` affects the value, but the flip counter
+        /// records every logical flip. A single `Flipped` event carries the
+        /// value from before and after all of them, or `FlippedLite` just the
+        /// final one if `verbose_events` is off.
+        fn apply_flips(&mut self, times: u64) {
+            let previous = self.value;
+            if times % 2 == 1 {
+                self.value = !self.value;
+                self.record_change_point();
+            }
+            self.flip_count = self.flip_count.saturating_add(times);
+            self.last_flipper = Some(self.env().caller());
+            self.last_flip_block = self.env().block_number();
+            self.last_flip_timestamp = self.env().block_timestamp();
+            self.record_flip();
+            self.count_flips_of_caller(times);
+
+            if self.verbose_events {
+                self.env().emit_event(Flipped {
+                    caller: self.env().caller(),
+                    previous,
+                    current: self.value,
+                    block: self.env().block_number(),
+                });
+            } else {
+                self.env().emit_event(FlippedLite {
+                    current: self.value,
+                });
+            }
+            self.notify_observers();
+        }
+
+        /// Appends the current state to the history, evicting the oldest record when full.
+        fn record_flip(&mut self) {
+            let record = FlipRecord {
+                caller: self.env().caller(),
+                new_value: self.value,
+                block: self.env().block_number(),
+            };
+            let mut history = self.history.get().unwrap_or_default();
+            if history.len() < HISTORY_CAPACITY {
+                history.push(record);
+            } else {
+                history[self.history_head as usize] = record;
+                self.history_head = (self.history_head + 1) % HISTORY_CAPACITY as u32;
+            }
+            self.history.set(&history);
+        }
+
+        /// Returns the caller if it is a multisig signer, `Error::NotSigner` otherwise.
+        fn ensure_signer(&self) -> Result<AccountId, Error> {
+            let caller = self.env().caller();
+            if !self.signers.contains(&caller) {
+                return Err(Error::NotSigner);
+            }
+            Ok(caller)
+        }
+
+        /// Computes the commit-reveal commitment for `salt` and `desired`.
+        fn commitment_of(salt: &[u8], desired: bool) -> Hash {
+            let mut input = salt.to_vec();
+            input.push(u8::from(desired));
+            Hash::from(Self::env().hash_bytes::<Blake2x256>(&input))
+        }
+
+        /// Computes the sealed-bid commitment `blake2b_256(amount ++ salt ++ bidder)`.
+        fn bid_commitment(amount: Balance, salt: &[u8], bidder: &AccountId) -> Hash {
+            let mut input = amount.encode();
+            input.extend_from_slice(salt);
+            input.extend_from_slice(bidder.as_ref());
+            Hash::from(Self::env().hash_bytes::<Blake2x256>(&input))
+        }
+
+        /// Returns `Error::Paused` while the contract is paused.
+        fn ensure_not_paused(&self) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            Ok(())
+        }
+
+        /// Returns `Error::Expired` once the expiry block has been reached.
+        fn ensure_not_expired(&self) -> Result<(), Error> {
+            if self.is_expired() {
+                return Err(Error::Expired);
+            }
+            Ok(())
+        }
+
+        /// Records that the value changed in the current block.
+        ///
+        /// Several changes within one block collapse into a single change point
+        /// holding the value at the end of that block.
+        fn record_change_point(&mut self) {
+            let block = self.env().block_number();
+            let mut change_points = self.change_points.get().unwrap_or_default();
+            match change_points.last_mut() {
+                Some((changed_at, value)) if *changed_at == block => *value = self.value,
+                _ => change_points.push((block, self.value)),
+            }
+            self.evict_change_points(&mut change_points);
+            self.change_points.set(&change_points);
+        }
+
+        /// Drops the oldest of `change_points` beyond `max_change_points`.
+        fn evict_change_points(&self, change_points: &mut Vec<(BlockNumber, bool)>) {
+            let max = self.max_change_points as usize;
+            if change_points.len() > max {
+                let excess = change_points.len() - max;
+                change_points.drain(..excess);
+            }
+        }
+
+        /// Stores the personal flag of `account`, keeps `support` in sync and
+        /// emits `PersonalFlipped` with the caller as operator. Does nothing
+        /// if the flag already is `value`.
+        ///
+        /// `false` flags are removed rather than stored to release the deposit.
+        fn set_personal_flag(&mut self, account: AccountId, value: bool) {
+            if self.get_of(account) == value {
+                return;
+            }
+            if value {
+                self.personal_flags.insert(account, &true);
+                self.support += 1;
+            } else {
+                self.personal_flags.remove(account);
+                self.support -= 1;
+            }
+            self.env().emit_event(PersonalFlipped {
+                account,
+                operator: self.env().caller(),
+                value,
+            });
+        }
+
+        /// Performs the checks of `flip` and, if they pass, toggles the value.
+        fn checked_flip(&mut self) -> Result<(), Error> {
+            self.admit_flip(false)?;
+            self.toggle();
+            Ok(())
+        }
+
+        /// Runs the checks every message that flips the value goes through and
+        /// books the flip against the caller's cooldown and the block's rate
+        /// limit.
+        ///
+        /// Only `paid_flip` passes `paid`; any other flip fails with
+        /// `Error::FreeFlipsDisabled` unless `free_flips_allowed` is set.
+        fn admit_flip(&mut self, paid: bool) -> Result<(), Error> {
+            self.ensure_not_banned()?;
+            if !paid && !self.free_flips_allowed {
+                return Err(Error::FreeFlipsDisabled);
+            }
+            self.ensure_not_paused()?;
+            self.ensure_not_expired()?;
+            if self.restricted_mode {
+                self.ensure_role(Role::Flipper)?;
+            }
+            let caller = self.env().caller();
+            if self.allowlist_enabled && !self.is_allowed(caller) {
+                return Err(Error::NotAllowed);
+            }
+            if self.support < self.quorum {
+                return Err(Error::QuorumNotMet);
+            }
+            if self.cooldown_remaining(caller) > 0 {
+                return Err(Error::CooldownActive);
+            }
+            self.consume_rate_limit()?;
+            self.last_flip_block_of.insert(caller, &self.env().block_number());
+            Ok(())
+        }
+
+        /// Adds `amount` to the donations of `donor`, updates `top_donors` and
+        /// returns the donor's new total.
+        fn record_donation(&mut self, donor: AccountId, amount: Balance) -> Balance {
+            let total = self.donated_by(donor).saturating_add(amount);
+            self.donated.insert(donor, &total);
+
+            let mut index = match self.top_donors.iter().position(|(account, _)| *account == donor) {
+                Some(index) => index,
+                None if self.top_donors.len() < TOP_DONORS_SIZE => {
+                    self.top_donors.push((donor, total));
+                    self.top_donors.len() - 1
+                }
+                None => {
+                    let last = TOP_DONORS_SIZE - 1;
+                    if total <= self.top_donors[last].1 {
+                        return total;
+                    }
+                    last
+                }
+            };
+            self.top_donors[index] = (donor, total);
+            while index > 0 && self.top_donors[index - 1].1 < total {
+                self.top_donors.swap(index - 1, index);
+                index -= 1;
+            }
+            total
+        }
+
+        /// Adds `times` flips to the caller's count and updates the leaderboard.
+        fn count_flips_of_caller(&mut self, times: u64) {
+            let caller = self.env().caller();
+            let times = u32::try_from(times).unwrap_or(u32::MAX);
+            let count = self.flips_of(caller).saturating_add(times);
+            self.flips_of.insert(caller, &count);
+
+            let mut index = match self.leaderboard.iter().position(|(account, _)| *account == caller) {
+                Some(index) => index,
+                None if self.leaderboard.len() < LEADERBOARD_SIZE => {
+                    self.leaderboard.push((caller, count));
+                    self.leaderboard.len() - 1
+                }
+                None => {
+                    let last = LEADERBOARD_SIZE - 1;
+                    if count <= self.leaderboard[last].1 {
+                        return;
+                    }
+                    last
+                }
+            };
+            self.leaderboard[index] = (caller, count);
+            while index > 0 && self.leaderboard[index - 1].1 < count {
+                self.leaderboard.swap(index - 1, index);
+                index -= 1;
+            }
+        }
+
+        /// Returns the block of the latest recorded flip, if any.
+        fn last_flip_block(&self) -> Option<BlockNumber> {
+            let history = self.history.get().unwrap_or_default();
+            let latest = match self.history_head {
+                0 => history.last(),
+                head => history.get(head as usize - 1),
+            };
+            latest.map(|record| record.block)
+        }
+
+        /// Calls `OnFlip::on_flip` on every observer.
+        ///
+        /// Failing observers are counted in `failed_notifications` but never
+        /// make the flip itself fail, so a broken observer can't brick the contract.
+        fn notify_observers(&mut self) {
+            for observer in &self.observers {
+                let result = build_call::<Environment>()
+                    .call(*observer)
+                    .gas_limit(OBSERVER_GAS_LIMIT)
+                    .exec_input(
+                        ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                            "OnFlip::on_flip"
+                        )))
+                        .push_arg(self.value),
+                    )
+                    .returns::<()>()
+                    .try_invoke();
+                if !matches!(result, Ok(Ok(()))) {
+                    self.failed_notifications = self.failed_notifications.saturating_add(1);
+                }
+            }
+        }
+
+        /// Counts a flip against the per-block limit, resetting the counter
+        /// whenever a new block started.
+        fn consume_rate_limit(&mut self) -> Result<(), Error> {
+            let block = self.env().block_number();
+            if block != self.rate_limit_block {
+                self.rate_limit_block = block;
+                self.flips_in_block = 0;
+            }
+            if self.flips_in_block >= self.max_flips_per_block {
+                return Err(Error::RateLimited);
+            }
+            self.flips_in_block += 1;
+            Ok(())
+        }
+
+        /// Builds a not yet started English auction sold by the caller.
+        fn new_auction(
+            &self,
+            name: String,
+            subject: Hash,
+            min_increment: Balance,
+            reserve_price: Balance,
+            duration_ms: u64,
+        ) -> Auction {
+            Auction {
+                name,
+                seller: self.env().caller(),
+                subject,
+                status: Status::NotStarted,
+                finalized: false,
+                vector: Vec::new(),
+                highest_bid: 0,
+                highest_bidder: None,
+                min_increment,
+                reserve_price,
+                end_timestamp: self.env().block_timestamp().saturating_add(duration_ms),
+                extension_window_ms: 0,
+                extension_ms: 0,
+                max_extensions: 0,
+                extensions: 0,
+                kind: AuctionKind::English,
+                reveal_end: 0,
+                start_timestamp: self.env().block_timestamp(),
+                start_price: 0,
+                floor_price: 0,
+                restricted: false,
+                fee_bps: self.fee_bps,
+                buy_now_price: None,
+                settlement: SettlementKind::FirstPrice,
+                second_bid: 0,
+            }
+        }
+
+        /// Pays the highest bid of a finalized auction out to the seller minus
+        /// the protocol fee, or credits it back to the bidder if it misses the
+        /// reserve price.
+        fn settle(&mut self, auction_id: u32, auction: &Auction) -> Result<(), Error> {
+            let winner = auction.highest_bidder;
+            let amount = auction.highest_bid;
+            if let Some(bidder) = winner.filter(|_| amount < auction.reserve_price) {
+                self.credit_refund(bidder, amount);
+                self.env().emit_event(ReserveNotMet {
+                    id: auction_id,
+                    bidder,
+                    highest_bid: amount,
+                    reserve_price: auction.reserve_price,
+                });
+                return Ok(());
+            }
+            let price = Self::clearing_price(auction);
+            if let Some(bidder) = winner {
+                // Rounds the fee down, so any remainder goes to the seller.
+                let fee = price.saturating_mul(Balance::from(auction.fee_bps)) / 10_000;
+                self.env()
+                    .transfer(auction.seller, price - fee)
+                    .map_err(|_| Error::TransferFailed)?;
+                self.escrowed_bids = self.escrowed_bids.saturating_sub(price);
+                self.accrue_fees(fee);
+                if price < amount {
+                    self.credit_refund(bidder, amount - price);
+                }
+            }
+            self.env().emit_event(AuctionFinalized {
+                id: auction_id,
+                winner,
+                amount: price,
+            });
+            Ok(())
+        }
+
+        /// Returns what the winner of `auction` pays, see `SettlementKind`.
+        /// A buy-now purchase always pays the buy-now price, and without a
+        /// winner nothing is paid.
+        fn clearing_price(auction: &Auction) -> Balance {
+            if auction.highest_bidder.is_none() {
+                return 0;
+            }
+            let bought_now = auction.buy_now_price.is_some_and(|price| auction.highest_bid >= price);
+            if auction.settlement == SettlementKind::FirstPrice || bought_now {
+                return auction.highest_bid;
+            }
+            if auction.second_bid == 0 && auction.reserve_price == 0 {
+                return auction.highest_bid;
+            }
+            auction.second_bid.max(auction.reserve_price)
+        }
+
+        /// Returns the current Dutch price of `auction`, or its highest bid
+        /// for other kinds of auctions.
+        fn price_of(&self, auction: &Auction) -> Balance {
+            if auction.kind != AuctionKind::Dutch {
+                return auction.highest_bid;
+            }
+            let now = self.env().block_timestamp();
+            let duration = auction.end_timestamp - auction.start_timestamp;
+            if now >= auction.end_timestamp || duration == 0 {
+                return auction.floor_price;
+            }
+            let elapsed = now.saturating_sub(auction.start_timestamp);
+            let discount = (auction.start_price - auction.floor_price)
+                .saturating_mul(Balance::from(elapsed))
+                / Balance::from(duration);
+            auction.start_price - discount
+        }
+
+        /// Stores `auction` under the next free id and returns that id.
+        fn insert_auction(&mut self, auction: Auction) -> Result<u32, Error> {
+            let auction_id = self.next_auction_id;
+            self.next_auction_id = auction_id.checked_add(1).ok_or(Error::TooManyAuctions)?;
+            self.auctions.insert(auction_id, &auction);
+            self.index_status(auction_id, None, &auction.status);
+            self.env().emit_event(AuctionCreated {
+                id: auction_id,
+                seller: auction.seller,
+                subject: auction.subject,
+            });
+            Ok(auction_id)
+        }
+
+        /// Returns the auction with id `auction_id` or `Error::NoSuchAuction`.
+        fn auction(&self, auction_id: u32) -> Result<Auction, Error> {
+            self.auctions.get(auction_id).ok_or(Error::NoSuchAuction)
+        }
+
+        /// Returns the auction if the caller is its seller and it has neither
+        /// ended nor been cancelled.
+        fn managed_auction(&self, auction_id: u32) -> Result<Auction, Error> {
+            let auction = self.auction(auction_id)?;
+            if self.env().caller() != auction.seller {
+                return Err(Error::NotSeller);
+            }
+            if matches!(auction.status, Status::Ended | Status::Cancelled) {
+                return Err(Error::AuctionNotOpen);
+            }
+            Ok(auction)
+        }
+
+        /// Appends a bid of `amount` at the current timestamp to the bid
+        /// history of `bidder`, evicting the oldest entry once it is full.
+        fn record_bid(&mut self, auction_id: u32, bidder: AccountId, amount: Balance) {
+            let mut bids = self.bids_of(auction_id, bidder);
+            if bids.len() >= MAX_BIDS_PER_BIDDER {
+                bids.remove(0);
+            }
+            bids.push((amount, self.env().block_timestamp()));
+            self.bid_history.insert((auction_id, bidder), &bids);
+        }
+
+        /// Returns the hash a `pow_flip` proof by `caller` with `nonce` is
+        /// checked on.
+        fn pow_hash(&self, caller: AccountId, nonce: u64) -> [u8; 32] {
+            let mut input = Vec::with_capacity(76);
+            input.extend_from_slice(self.env().account_id().as_ref());
+            input.extend_from_slice(caller.as_ref());
+            input.extend_from_slice(&self.last_flip_block.to_le_bytes());
+            input.extend_from_slice(&nonce.to_le_bytes());
+            self.env().hash_bytes::<Blake2x256>(&input)
+        }
+
+        /// Returns the number of zero bits `hash` starts with.
+        fn leading_zero_bits(hash: &[u8; 32]) -> u32 {
+            let mut bits = 0;
+            for byte in hash {
+                bits += byte.leading_zeros();
+                if *byte != 0 {
+                    break;
+                }
+            }
+            bits
+        }
+
+        /// Fails with `Error::UnexpectedPayment` if value was sent while
+        /// `strict_value_checks` is on.
+        fn ensure_no_value(&self) -> Result<(), Error> {
+            if self.strict_value_checks && self.env().transferred_value() > 0 {
+                return Err(Error::UnexpectedPayment);
+            }
+            Ok(())
+        }
+
+        /// Returns the transferred value and adds it to `total_received`.
+        fn accept_value(&mut self) -> Balance {
+            let amount = self.env().transferred_value();
+            self.total_received = self.total_received.saturating_add(amount);
+            amount
+        }
+
+        /// Splits `fee` between `owner_fees` and `treasury_fees` according to
+        /// `treasury_share_bps`. The owner's share is rounded down, so any
+        /// remainder goes to the treasury.
+        fn accrue_fees(&mut self, fee: Balance) {
+            let owner_share = fee.saturating_mul(Balance::from(10_000 - self.treasury_share_bps)) / 10_000;
+            self.owner_fees = self.owner_fees.saturating_add(owner_share);
+            self.treasury_fees = self.treasury_fees.saturating_add(fee - owner_share);
+        }
+
+        /// Like `accept_value`, but also holds the value in `escrowed_bids`.
+        fn accept_escrow(&mut self) -> Balance {
+            let amount = self.accept_value();
+            self.escrowed_bids = self.escrowed_bids.saturating_add(amount);
+            amount
+        }
+
+        /// Adds `amount` to what `account` can withdraw with `withdraw_refund`.
+        fn credit_refund(&mut self, account: AccountId, amount: Balance) {
+            let refund = self.pending_refunds.get(account).unwrap_or(0) + amount;
+            self.pending_refunds.insert(account, &refund);
+        }
+
+        /// Moves the auction from status `from` to `to`, failing with
+        /// `Error::InvalidStatusTransition` if it is not currently in `from`.
+        fn transition(&mut self, auction_id: u32, from: Status, to: Status) -> Result<(), Error> {
+            self.ensure_owner()?;
+            self.ensure_not_paused()?;
+            let mut auction = self.auction(auction_id)?;
+            if auction.status != from {
+                return Err(Error::InvalidStatusTransition);
+            }
+            auction.status = to.clone();
+            self.auctions.insert(auction_id, &auction);
+            self.record_status_change(auction_id, from, to);
+            Ok(())
+        }
+
+        /// Moves the auction to its new status index and emits `StatusChanged`,
+        /// followed by `AuctionEnded` if the auction moved to `Status::Ended`.
+        fn record_status_change(&mut self, auction_id: u32, from: Status, to: Status) {
+            self.index_status(auction_id, Some(&from), &to);
+            let ended = to == Status::Ended;
+            self.env().emit_event(StatusChanged {
+                id: auction_id,
+                from,
+                to,
+            });
+            if ended {
+                self.env().emit_event(AuctionEnded { id: auction_id });
+            }
+        }
+
+        /// Removes the auction from the index of status `from`, if any, and
+        /// appends it to the index of status `to`.
+        ///
+        /// The last auction of `from` takes the freed position, so both steps
+        /// touch a constant number of entries.
+        fn index_status(&mut self, auction_id: u32, from: Option<&Status>, to: &Status) {
+            if let Some(from) = from {
+                let last = self.count_by_status(from.clone()).saturating_sub(1);
+                let position = self.status_positions.get(auction_id).unwrap_or(last);
+                if position != last {
+                    if let Some(moved) = self.auctions_by_status.get((from.clone(), last)) {
+                        self.auctions_by_status.insert((from.clone(), position), &moved);
+                        self.status_positions.insert(moved, &position);
+                    }
+                }
+                self.auctions_by_status.remove((from.clone(), last));
+                self.status_counts.insert(from, &last);
+            }
+            let position = self.count_by_status(to.clone());
+            self.auctions_by_status.insert((to.clone(), position), &auction_id);
+            self.status_positions.insert(auction_id, &position);
+            self.status_counts.insert(to, &(position + 1));
+        }
+
+        /// Returns the listing summary of the auction with id `auction_id`.
+        fn summary_of(&self, auction_id: u32) -> Option<(u32, AuctionSummary)> {
+            let auction = self.auctions.get(auction_id)?;
+            Some((
+                auction_id,
+                AuctionSummary {
+                    id: auction_id,
+                    name: auction.name,
+                    status: auction.status,
+                    highest_bid: auction.highest_bid,
+                    end_timestamp: auction.end_timestamp,
+                },
+            ))
+        }
+    }
+
+    /// Unit tests in Rust are normally defined within such a `#[cfg(test)]`
+    /// module and test functions are marked with a `#[test]` attribute.
+    /// The below code is technically just normal Rust code.
+    #[cfg(test)]
+    mod tests {
+        /// Imports all the definitions from the outer scope so we can use them here.
+        use super::*;
+        use crate::event_topics;
+
+        /// The contract-wide event type, used to decode `recorded_events()`.
+        type Event = <Foobar as ::ink::reflect::ContractEventBase>::Type;
+
+        /// Public key of the secp256k1 test secret key `[0x2a; 32]`.
+        const TEST_PUBKEY: [u8; 33] = [
+            0x03, 0x5b, 0xe5, 0xe9, 0x47, 0x82, 0x09, 0x67, 0x4a, 0x96, 0xe6,
+            0x0f, 0x1f, 0x03, 0x7f, 0x61, 0x76, 0x54, 0x0f, 0xd0, 0x01, 0xfa,
+            0x1d, 0x64, 0x69, 0x47, 0x70, 0xc5, 0x6a, 0x77, 0x09, 0xc4, 0x2c,
+        ];
+
+        /// Signatures by `TEST_PUBKEY` over the flip payload for contract `[0xff; 32]`
+        /// with nonces 0 and 1.
+        const TEST_SIGNATURE_NONCE_0: [u8; 65] = [
+            0x18, 0x98, 0x5e, 0x85, 0xcb, 0xe3, 0x32, 0x3d, 0x0f, 0xb4, 0x47,
+            0xad, 0xe8, 0xd0, 0x1d, 0x8b, 0xa3, 0x82, 0xad, 0x23, 0xb8, 0xd9,
+            0x50, 0x0a, 0x04, 0xe8, 0x97, 0x6e, 0x2a, 0x59, 0x52, 0x68, 0x31,
+            0x05, 0x93, 0x8d, 0xce, 0x71, 0x96, 0x54, 0x3b, 0x11, 0xb2, 0x9c,
+            0x9b, 0x7a, 0x97, 0x77, 0x2c, 0x59, 0x3f, 0xc1, 0x35, 0x9c, 0x61,
+            0xcf, 0x23, 0x43, 0xb6, 0x38, 0x7a, 0x7d, 0x38, 0x60, 0x00,
+        ];
+
+        const TEST_SIGNATURE_NONCE_1: [u8; 65] = [
+            0x69, 0x55, 0xc0, 0x23, 0x6b, 0xb1, 0xae, 0xe9, 0xd5, 0x1b, 0xce,
+            0x8c, 0x9d, 0xbd, 0x96, 0xf8, 0x9c, 0x7f, 0xc7, 0x72, 0x33, 0x4d,
+            0x0a, 0x84, 0x93, 0x38, 0xd7, 0x87, 0x7f, 0x07, 0xa9, 0xc0, 0x11,
+            0xf7, 0x20, 0x60, 0x18, 0xad, 0xf6, 0x90, 0xda, 0xc6, 0xf3, 0x14,
+            0xff, 0x1f, 0x1c, 0x04, 0xb7, 0x9a, 0x64, 0x29, 0x3e, 0xad, 0x4c,
+            0xf8, 0xee, 0x98, 0x24, 0x40, 0x1f, 0x12, 0xda, 0x5c, 0x00,
+        ];
+
+        /// Public key of the `//Alice` sr25519 development account.
+        #[cfg(feature = "sr25519")]
+        const ALICE_SR25519_PUBKEY: [u8; 32] = [
+            0xd4, 0x35, 0x93, 0xc7, 0x15, 0xfd, 0xd3, 0x1c, 0x61, 0x14, 0x1a,
+            0xbd, 0x04, 0xa9, 0x9f, 0xd6, 0x82, 0x2c, 0x85, 0x58, 0x85, 0x4c,
+            0xcd, 0xe3, 0x9a, 0x56, 0x84, 0xe7, 0xa5, 0x6d, 0xa2, 0x7d,
+        ];
+
+        /// Signature by `ALICE_SR25519_PUBKEY` over `b"foobar"`.
+        #[cfg(feature = "sr25519")]
+        const ALICE_SR25519_SIGNATURE: [u8; 64] = [
+            0x18, 0x87, 0x24, 0xfa, 0x0d, 0x70, 0xae, 0x9a, 0x69, 0x1d, 0x25,
+            0xc0, 0x59, 0x14, 0x03, 0x1b, 0x64, 0x15, 0x19, 0x3a, 0x09, 0x25,
+            0x57, 0x05, 0x79, 0x98, 0xfa, 0x5d, 0xdd, 0x5b, 0x11, 0x71, 0xa0,
+            0xe7, 0x05, 0x50, 0x5e, 0x6b, 0xdb, 0x32, 0x59, 0x79, 0x22, 0x3f,
+            0xb8, 0x50, 0xaa, 0x98, 0xa8, 0xe9, 0xee, 0x2f, 0xd4, 0xea, 0x6b,
+            0x84, 0xb1, 0xde, 0x5f, 0xf8, 0xd2, 0x8e, 0x66, 0x86,
+        ];
+
+        /// Signatures by `ALICE_SR25519_PUBKEY` over the flip payload for
+        /// contract `[0xff; 32]` with nonces 0 and 1.
+        #[cfg(feature = "sr25519")]
+        const ALICE_SR25519_FLIP_NONCE_0: [u8; 64] = [
+            0xaa, 0xa4, 0x3c, 0xf1, 0x3b, 0x6e, 0x17, 0x3b, 0x9e, 0xeb, 0x5f,
+            0x4b, 0x2e, 0x9a, 0xb1, 0xe4, 0x14, 0xbb, 0xb6, 0x35, 0xd1, 0x8c,
+            0x2d, 0xc6, 0xff, 0x39, 0xc4, 0x83, 0x7a, 0xd1, 0x8b, 0x67, 0x55,
+            0x33, 0x02, 0xeb, 0x79, 0xe8, 0xd0, 0xf7, 0x3d, 0xba, 0xa1, 0x58,
+            0xcc, 0xbd, 0x98, 0xb8, 0xc6, 0x57, 0x15, 0x02, 0x84, 0x7d, 0xeb,
+            0x03, 0x88, 0xdb, 0xf0, 0x92, 0xdc, 0x9c, 0xe5, 0x8d,
+        ];
+
+        #[cfg(feature = "sr25519")]
+        const ALICE_SR25519_FLIP_NONCE_1: [u8; 64] = [
+            0x2c, 0x3f, 0x20, 0xa5, 0xaf, 0x77, 0x59, 0x38, 0x5e, 0x64, 0x8e,
+            0xae, 0x8a, 0x7f, 0xfb, 0x2e, 0xc4, 0x8d, 0x6a, 0xd3, 0x5d, 0x64,
+            0xc6, 0x4f, 0x52, 0xe1, 0x90, 0x30, 0x58, 0x30, 0x03, 0x74, 0x1d,
+            0x7d, 0x14, 0xfa, 0xcf, 0xa0, 0xd1, 0x41, 0x6d, 0xdb, 0x84, 0x00,
+            0x2b, 0xa6, 0x9a, 0x46, 0x6b, 0x7e, 0xff, 0x91, 0x2a, 0xb3, 0x8e,
+            0xac, 0x96, 0x62, 0x21, 0xbe, 0xb2, 0x24, 0x2c, 0x8e,
+        ];
+
+        /// Computes the topic the off-chain environment records for `value`
+        /// published under `prefix`.
+        fn topic_of<T: scale::Encode>(prefix: &[u8], value: &T) -> Vec<u8> {
+            use ink::env::hash::CryptoHash;
+
+            let encoded = ink::env::topics::PrefixedValue { prefix, value }.encode();
+            let mut topic = [0u8; 32];
+            if encoded.len() <= topic.len() {
+                topic[..encoded.len()].copy_from_slice(&encoded);
+            } else {
+                Blake2x256::hash(&encoded, &mut topic);
+            }
+            topic.to_vec()
+        }
+
+        /// Decodes the data of an event recorded by the off-chain environment.
+        fn decode_event(event: &ink::env::test::EmittedEvent) -> Event {
+            <Event as scale::Decode>::decode(&mut &event.data[..])
+                .expect("encountered invalid contract event data buffer")
+        }
+
+        /// We test if the default constructor does its job.
+        #[ink::test]
+        fn default_works() {
+            let foobar = Foobar::default();
+            assert!(!foobar.get());
+        }
+
+        /// We test a simple use case of our contract.
+        #[ink::test]
+        fn it_works() {
+            let mut foobar = Foobar::new(false);
+            assert!(!foobar.get());
+            foobar.flip().unwrap();
+            assert!(foobar.get());
+        }
+
+        /// We test that every flip is counted, starting from zero.
+        #[ink::test]
+        fn flip_count_works() {
+            let mut foobar = Foobar::new(true);
+            assert_eq!(foobar.flip_count(), 0);
+            for _ in 0..5 {
+                foobar.flip().unwrap();
+            }
+            assert_eq!(foobar.flip_count(), 5);
+            assert!(!foobar.get());
+        }
+
+        /// We test that the caller of the latest flip is recorded.
+        #[ink::test]
+        fn last_flipper_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.last_flipper(), None);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.flip().unwrap();
+            assert_eq!(foobar.last_flipper(), Some(accounts.alice));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            foobar.flip().unwrap();
+            assert_eq!(foobar.last_flipper(), Some(accounts.bob));
+        }
+
+        /// We test that `flip_to` changes the value and reports it.
+        #[ink::test]
+        fn flip_to_changes_value() {
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.flip_to(true), Ok(true));
+            assert!(foobar.get());
+            assert_eq!(foobar.flip_count(), 1);
+            assert_eq!(ink::env::test::recorded_events().count(), 2);
+        }
+
+        /// We test that `flip_to` is a no-op when the value is already `target`.
+        #[ink::test]
+        fn flip_to_same_value_is_noop() {
+            let mut foobar = Foobar::new(true);
+            assert_eq!(foobar.flip_to(true), Ok(false));
+            assert!(foobar.get());
+            assert_eq!(foobar.flip_count(), 0);
+            // Only the `Created` event has been emitted.
+            assert_eq!(ink::env::test::recorded_events().count(), 1);
+        }
+
+        /// We test that `compare_and_flip` flips when the expectation holds.
+        #[ink::test]
+        fn compare_and_flip_works() {
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.compare_and_flip(false), Ok(()));
+            assert!(foobar.get());
+        }
+
+        /// We test that `compare_and_flip` rejects a stale expectation.
+        #[ink::test]
+        fn compare_and_flip_rejects_unexpected_value() {
+            let mut foobar = Foobar::new(true);
+            assert_eq!(foobar.compare_and_flip(false), Err(Error::UnexpectedValue));
+            assert!(foobar.get());
+            assert_eq!(foobar.flip_count(), 0);
+        }
+
+        /// We test that `flip_n` applies the parity and counts every flip.
+        #[ink::test]
+        fn flip_n_works() {
+            let mut foobar = Foobar::new(false);
+
+            assert_eq!(foobar.flip_n(0), Ok(()));
+            assert!(!foobar.get());
+            assert_eq!(foobar.flip_count(), 0);
+            assert_eq!(ink::env::test::recorded_events().count(), 1);
+
+            assert_eq!(foobar.flip_n(1), Ok(()));
+            assert!(foobar.get());
+            assert_eq!(foobar.flip_count(), 1);
+
+            assert_eq!(foobar.flip_n(2), Ok(()));
+            assert!(foobar.get());
+            assert_eq!(foobar.flip_count(), 3);
+
+            assert_eq!(foobar.flip_n(1_000_001), Ok(()));
+            assert!(!foobar.get());
+            assert_eq!(foobar.flip_count(), 1_000_004);
+            assert_eq!(ink::env::test::recorded_events().count(), 4);
+        }
+
+        /// We test that `get_and_flip` returns the pre-flip value.
+        #[ink::test]
+        fn get_and_flip_works() {
+            let mut foobar = Foobar::new(true);
+            assert_eq!(foobar.get_and_flip(), Ok(true));
+            assert!(!foobar.get());
+            assert_eq!(foobar.get_and_flip(), Ok(false));
+            assert!(foobar.get());
+        }
+
+        /// We test that flips are recorded with their caller and block.
+        #[ink::test]
+        fn history_records_flips() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.flip().unwrap();
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            foobar.flip().unwrap();
+
+            assert_eq!(
+                foobar.history(),
+                vec![
+                    FlipRecord { caller: accounts.alice, new_value: true, block: 0 },
+                    FlipRecord { caller: accounts.bob, new_value: false, block: 1 },
+                ]
+            );
+        }
+
+        /// We test that the oldest records are evicted once the history is full.
+        #[ink::test]
+        fn history_evicts_oldest() {
+            let mut foobar = Foobar::new(false);
+            for _ in 0..HISTORY_CAPACITY + 3 {
+                foobar.flip().unwrap();
+                ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            }
+
+            let history = foobar.history();
+            assert_eq!(history.len(), HISTORY_CAPACITY);
+            assert_eq!(history[0].block, 3);
+            assert_eq!(history[HISTORY_CAPACITY - 1].block, HISTORY_CAPACITY as u32 + 2);
+            assert!(history.windows(2).all(|pair| pair[0].block < pair[1].block));
+        }
+
+        /// We test that the owner can reset the value.
+        #[ink::test]
+        fn reset_value_works_for_owner() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let mut foobar = Foobar::new(true);
+
+            assert_eq!(foobar.reset_value(false), Ok(()));
+            assert!(!foobar.get());
+            assert_eq!(ink::env::test::recorded_events().count(), 2);
+
+            // Resetting to the current value emits nothing.
+            assert_eq!(foobar.reset_value(false), Ok(()));
+            assert_eq!(ink::env::test::recorded_events().count(), 2);
+        }
+
+        /// We test that anyone but the owner is rejected.
+        #[ink::test]
+        fn reset_value_rejects_stranger() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let mut foobar = Foobar::new(true);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.reset_value(false), Err(Error::NotOwner));
+            assert!(foobar.get());
+        }
+
+        /// We test that a scheduled flip only executes once its block is reached.
+        #[ink::test]
+        fn scheduled_flip_works() {
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.execute_scheduled_flip(), Err(Error::NothingScheduled));
+
+            assert_eq!(foobar.schedule_flip(2), Ok(()));
+            assert_eq!(foobar.schedule_flip(3), Err(Error::AlreadyScheduled));
+            assert_eq!(foobar.pending_flip(), Some(2));
+
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert_eq!(foobar.execute_scheduled_flip(), Err(Error::TooEarly));
+            assert!(!foobar.get());
+
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert_eq!(foobar.execute_scheduled_flip(), Ok(()));
+            assert!(foobar.get());
+            assert_eq!(foobar.pending_flip(), None);
+            assert_eq!(foobar.execute_scheduled_flip(), Err(Error::NothingScheduled));
+        }
+
+        /// We test that only the owner can schedule and cancel flips.
+        #[ink::test]
+        fn cancel_scheduled_flip_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.schedule_flip(1), Ok(()));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.cancel_scheduled_flip(), Err(Error::NotOwner));
+            assert_eq!(foobar.schedule_flip(5), Err(Error::NotOwner));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.cancel_scheduled_flip(), Ok(()));
+            assert_eq!(foobar.cancel_scheduled_flip(), Err(Error::NothingScheduled));
+
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert_eq!(foobar.execute_scheduled_flip(), Err(Error::NothingScheduled));
+            assert!(!foobar.get());
+        }
+
+        /// We test that the cooldown blocks repeated flips until it expires.
+        #[ink::test]
+        fn cooldown_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new_with_cooldown(false, 2);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.flip(), Ok(()));
+            assert_eq!(foobar.cooldown_remaining(accounts.bob), 2);
+            assert_eq!(foobar.flip(), Err(Error::CooldownActive));
+
+            // Other accounts are not affected.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(foobar.flip(), Ok(()));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert_eq!(foobar.cooldown_remaining(accounts.bob), 1);
+            assert_eq!(foobar.flip(), Err(Error::CooldownActive));
+
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert_eq!(foobar.cooldown_remaining(accounts.bob), 0);
+            assert_eq!(foobar.flip(), Ok(()));
+            assert_eq!(foobar.flip_count(), 3);
+        }
+
+        /// We test that only the owner can change the cooldown.
+        #[ink::test]
+        fn set_cooldown_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.flip(), Ok(()));
+            assert_eq!(foobar.flip(), Ok(()));
+
+            assert_eq!(foobar.set_cooldown(5), Ok(()));
+            assert_eq!(foobar.flip(), Err(Error::CooldownActive));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.set_cooldown(0), Err(Error::NotOwner));
+        }
+
+        /// We test that the entropy derivation is pinned for fixed env values.
+        #[cfg(not(feature = "chain-ext"))]
+        #[ink::test]
+        fn entropy_bit_is_pinned() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            let foobar = Foobar::new(false);
+
+            assert!(foobar.entropy_bit(b"seed"));
+            assert!(!foobar.entropy_bit(b"c"));
+
+            // Advancing the block changes the outcome for the same seed.
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert!(!foobar.entropy_bit(b"seed"));
+        }
+
+        /// We test that `random_flip` applies the derived bit and only emits on change.
+        #[cfg(not(feature = "chain-ext"))]
+        #[ink::test]
+        fn random_flip_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            let mut foobar = Foobar::new(false);
+
+            assert_eq!(foobar.random_flip(b"seed".to_vec()), Ok(true));
+            assert!(foobar.get());
+            assert_eq!(ink::env::test::recorded_events().count(), 2);
+
+            assert_eq!(foobar.random_flip(b"seed".to_vec()), Ok(true));
+            assert_eq!(ink::env::test::recorded_events().count(), 2);
+
+            assert_eq!(foobar.random_flip(b"c".to_vec()), Ok(false));
+            assert!(!foobar.get());
+            assert_eq!(ink::env::test::recorded_events().count(), 3);
+        }
+
+        /// Stands in for the runtime's `RandomnessExtension`, answering with
+        /// `output` and `status`.
+        #[cfg(feature = "chain-ext")]
+        struct MockRandomness {
+            output: [u8; 32],
+            status: u32,
+        }
+
+        #[cfg(feature = "chain-ext")]
+        impl ink::env::test::ChainExtension for MockRandomness {
+            fn func_id(&self) -> u32 {
+                crate::FETCH_RANDOM_FUNC_ID
+            }
+
+            fn call(&mut self, _input: &[u8], output: &mut Vec<u8>) -> u32 {
+                scale::Encode::encode_to(&self.output, output);
+                self.status
+            }
+        }
+
+        /// We test that `random_flip` takes its bit from the chain extension.
+        #[cfg(feature = "chain-ext")]
+        #[ink::test]
+        fn random_flip_uses_chain_extension() {
+            let mut foobar = Foobar::new(false);
+
+            ink::env::test::register_chain_extension(MockRandomness { output: [0x01; 32], status: 0 });
+            assert_eq!(foobar.random_flip(b"seed".to_vec()), Ok(true));
+            assert!(foobar.get());
+            assert_eq!(foobar.random_flip(b"c".to_vec()), Ok(true));
+            assert!(foobar.get());
+
+            ink::env::test::register_chain_extension(MockRandomness { output: [0xfe; 32], status: 0 });
+            assert_eq!(foobar.random_flip(b"seed".to_vec()), Ok(false));
+            assert!(!foobar.get());
+        }
+
+        /// We test that `random_flip` fails when the runtime has no randomness.
+        #[cfg(feature = "chain-ext")]
+        #[ink::test]
+        fn random_flip_rejects_missing_randomness() {
+            let mut foobar = Foobar::new(false);
+            ink::env::test::register_chain_extension(MockRandomness { output: [0x01; 32], status: 1 });
+            assert_eq!(foobar.random_flip(b"seed".to_vec()), Err(Error::RandomnessUnavailable));
+            assert!(!foobar.get());
+        }
+
+        /// We test that an account can't vote twice in the same round.
+        #[ink::test]
+        fn vote_flip_rejects_double_vote() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.set_vote_threshold(2), Ok(()));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.vote_flip(), Ok(false));
+            assert_eq!(foobar.vote_flip(), Err(Error::AlreadyVoted));
+            assert_eq!(foobar.votes(), 1);
+            assert!(!foobar.get());
+        }
+
+        /// We test that reaching the threshold flips and starts a new round.
+        #[ink::test]
+        fn vote_flip_triggers_at_threshold() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.set_vote_threshold(0), Err(Error::InvalidThreshold));
+            assert_eq!(foobar.set_vote_threshold(3), Ok(()));
+
+            for voter in [accounts.bob, accounts.charlie] {
+                ink::env::test::set_caller::<ink::env::DefaultEnvironment>(voter);
+                assert_eq!(foobar.vote_flip(), Ok(false));
+            }
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(foobar.vote_flip(), Ok(true));
+            assert!(foobar.get());
+            assert_eq!(foobar.votes(), 0);
+
+            // The round was reset, so earlier voters may vote again.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.vote_flip(), Ok(false));
+            assert_eq!(foobar.votes(), 1);
+            assert!(foobar.get());
+
+            // Created, `ConfigChanged`, three `VoteCast`, `Flipped` and another
+            // `VoteCast`.
+            assert_eq!(ink::env::test::recorded_events().count(), 7);
+        }
+
+        /// We test the 2-of-3 multisig flow.
+        #[ink::test]
+        fn multisig_flip_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let signers = vec![accounts.alice, accounts.bob, accounts.charlie];
+            let mut foobar = Foobar::new_multisig(signers, 2).expect("valid multisig");
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.approve_flip(), Ok(()));
+            assert_eq!(foobar.approve_flip(), Err(Error::AlreadyApproved));
+            assert_eq!(foobar.execute_flip(), Err(Error::NotEnoughApprovals));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.approve_flip(), Ok(()));
+            assert_eq!(foobar.revoke_approval(), Ok(()));
+            assert_eq!(foobar.revoke_approval(), Err(Error::NotApproved));
+            assert_eq!(foobar.execute_flip(), Err(Error::NotEnoughApprovals));
+            assert_eq!(foobar.approve_flip(), Ok(()));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(foobar.execute_flip(), Ok(()));
+            assert!(foobar.get());
+
+            // Approvals are cleared after execution.
+            assert_eq!(foobar.approval_count(), 0);
+            assert_eq!(foobar.execute_flip(), Err(Error::NotEnoughApprovals));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.approve_flip(), Ok(()));
+        }
+
+        /// We test that non-signers and invalid configurations are rejected.
+        #[ink::test]
+        fn multisig_rejects_non_signers() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let signers = vec![accounts.alice, accounts.bob, accounts.charlie];
+            assert!(matches!(
+                Foobar::new_multisig(signers.clone(), 0),
+                Err(Error::InvalidSigners)
+            ));
+            assert!(matches!(
+                Foobar::new_multisig(vec![accounts.alice, accounts.alice], 2),
+                Err(Error::InvalidSigners)
+            ));
+            let mut foobar = Foobar::new_multisig(signers, 2).expect("valid multisig");
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(foobar.approve_flip(), Err(Error::NotSigner));
+            assert_eq!(foobar.execute_flip(), Err(Error::NotSigner));
+
+            // Without a signer set nobody can execute a multisig flip.
+            let mut foobar = Foobar::new(false);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.execute_flip(), Err(Error::NotSigner));
+        }
+
+        /// We test a correct commit-reveal round-trip.
+        #[ink::test]
+        fn commit_reveal_works() {
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.reveal(b"salt".to_vec(), true), Err(Error::NoCommitment));
+
+            assert_eq!(foobar.commit(Foobar::commitment_of(b"salt", true)), Ok(()));
+            assert_eq!(foobar.reveal(b"salt".to_vec(), true), Ok(()));
+            assert!(foobar.get());
+
+            // The commitment is consumed by the reveal.
+            assert_eq!(foobar.reveal(b"salt".to_vec(), true), Err(Error::NoCommitment));
+        }
+
+        /// We test that revealing with a different salt or value is rejected.
+        #[ink::test]
+        fn reveal_rejects_mismatch() {
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.commit(Foobar::commitment_of(b"salt", true)), Ok(()));
+
+            assert_eq!(foobar.reveal(b"pepper".to_vec(), true), Err(Error::CommitmentMismatch));
+            assert_eq!(foobar.reveal(b"salt".to_vec(), false), Err(Error::CommitmentMismatch));
+            assert!(!foobar.get());
+            assert_eq!(foobar.reveal(b"salt".to_vec(), true), Ok(()));
+        }
+
+        /// We test that a new commitment replaces the previous one.
+        #[ink::test]
+        fn commit_overwrites_previous_commitment() {
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.commit(Foobar::commitment_of(b"first", true)), Ok(()));
+            assert_eq!(foobar.commit(Foobar::commitment_of(b"second", true)), Ok(()));
+
+            assert_eq!(foobar.reveal(b"first".to_vec(), true), Err(Error::CommitmentMismatch));
+            assert_eq!(foobar.reveal(b"second".to_vec(), true), Ok(()));
+        }
+
+        /// We test that a valid signature flips once and can't be replayed.
+        #[ink::test]
+        fn flip_with_signature_works() {
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(AccountId::from([0xff; 32]));
+            let mut foobar = Foobar::new(false);
+
+            assert_eq!(
+                foobar.flip_with_signature(TEST_PUBKEY, 0, TEST_SIGNATURE_NONCE_0),
+                Ok(())
+            );
+            assert!(foobar.get());
+            assert_eq!(foobar.signature_nonce(TEST_PUBKEY), 1);
+
+            assert_eq!(
+                foobar.flip_with_signature(TEST_PUBKEY, 0, TEST_SIGNATURE_NONCE_0),
+                Err(Error::NonceTooLow)
+            );
+            assert_eq!(
+                foobar.flip_with_signature(TEST_PUBKEY, 1, TEST_SIGNATURE_NONCE_1),
+                Ok(())
+            );
+            assert!(!foobar.get());
+        }
+
+        /// We test that signatures over another payload or by another key are rejected.
+        #[ink::test]
+        fn flip_with_signature_rejects_invalid_signature() {
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(AccountId::from([0xff; 32]));
+            let mut foobar = Foobar::new(false);
+
+            assert_eq!(
+                foobar.flip_with_signature(TEST_PUBKEY, 0, TEST_SIGNATURE_NONCE_1),
+                Err(Error::InvalidSignature)
+            );
+            let mut other_pubkey = TEST_PUBKEY;
+            other_pubkey[0] = 0x02;
+            assert_eq!(
+                foobar.flip_with_signature(other_pubkey, 0, TEST_SIGNATURE_NONCE_0),
+                Err(Error::InvalidSignature)
+            );
+
+            // The signature is bound to the contract account it was made for.
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(AccountId::from([0xee; 32]));
+            assert_eq!(
+                foobar.flip_with_signature(TEST_PUBKEY, 0, TEST_SIGNATURE_NONCE_0),
+                Err(Error::InvalidSignature)
+            );
+            assert!(!foobar.get());
+            assert_eq!(foobar.signature_nonce(TEST_PUBKEY), 0);
+        }
+
+        /// We test sr25519 verification against signatures by the `//Alice` key.
+        #[cfg(feature = "sr25519")]
+        #[ink::test]
+        fn verify_sr25519_works() {
+            let foobar = Foobar::new(false);
+            assert!(foobar.verify_sr25519(
+                b"foobar".to_vec(),
+                ALICE_SR25519_SIGNATURE,
+                ALICE_SR25519_PUBKEY
+            ));
+
+            assert!(!foobar.verify_sr25519(
+                b"foobaz".to_vec(),
+                ALICE_SR25519_SIGNATURE,
+                ALICE_SR25519_PUBKEY
+            ));
+            let mut tampered = ALICE_SR25519_SIGNATURE;
+            tampered[0] ^= 1;
+            assert!(!foobar.verify_sr25519(b"foobar".to_vec(), tampered, ALICE_SR25519_PUBKEY));
+            assert!(!foobar.verify_sr25519(b"foobar".to_vec(), ALICE_SR25519_SIGNATURE, [0x01; 32]));
+            assert!(!foobar.verify_sr25519(b"foobar".to_vec(), [0u8; 64], ALICE_SR25519_PUBKEY));
+        }
+
+        /// We test that a valid sr25519 signature flips once and can't be replayed.
+        #[cfg(feature = "sr25519")]
+        #[ink::test]
+        fn sr25519_flip_works() {
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(AccountId::from([0xff; 32]));
+            let mut foobar = Foobar::new(false);
+
+            assert_eq!(
+                foobar.sr25519_flip(ALICE_SR25519_PUBKEY, 0, ALICE_SR25519_FLIP_NONCE_0),
+                Ok(())
+            );
+            assert!(foobar.get());
+            assert_eq!(foobar.sr25519_nonce(ALICE_SR25519_PUBKEY), 1);
+
+            assert_eq!(
+                foobar.sr25519_flip(ALICE_SR25519_PUBKEY, 0, ALICE_SR25519_FLIP_NONCE_0),
+                Err(Error::NonceTooLow)
+            );
+            assert_eq!(
+                foobar.sr25519_flip(ALICE_SR25519_PUBKEY, 1, ALICE_SR25519_FLIP_NONCE_1),
+                Ok(())
+            );
+            assert!(!foobar.get());
+        }
+
+        /// We test that sr25519 signatures over another payload or contract are rejected.
+        #[cfg(feature = "sr25519")]
+        #[ink::test]
+        fn sr25519_flip_rejects_invalid_signature() {
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(AccountId::from([0xff; 32]));
+            let mut foobar = Foobar::new(false);
+
+            assert_eq!(
+                foobar.sr25519_flip(ALICE_SR25519_PUBKEY, 0, ALICE_SR25519_FLIP_NONCE_1),
+                Err(Error::InvalidSignature)
+            );
+            assert_eq!(
+                foobar.sr25519_flip(ALICE_SR25519_PUBKEY, 0, ALICE_SR25519_SIGNATURE),
+                Err(Error::InvalidSignature)
+            );
+
+            // The signature is bound to the contract account it was made for.
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(AccountId::from([0xee; 32]));
+            assert_eq!(
+                foobar.sr25519_flip(ALICE_SR25519_PUBKEY, 0, ALICE_SR25519_FLIP_NONCE_0),
+                Err(Error::InvalidSignature)
+            );
+            assert!(!foobar.get());
+            assert_eq!(foobar.sr25519_nonce(ALICE_SR25519_PUBKEY), 0);
+        }
+
+        /// We test that pausing blocks flips until the contract is unpaused.
+        #[ink::test]
+        fn pause_works() {
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.unpause(), Err(Error::NotPaused));
+            assert_eq!(foobar.pause(), Ok(()));
+            assert_eq!(foobar.pause(), Err(Error::Paused));
+            assert!(foobar.is_paused());
+
+            assert_eq!(foobar.flip(), Err(Error::Paused));
+            assert_eq!(foobar.flip_to(true), Err(Error::Paused));
+            assert_eq!(foobar.flip_n(1), Err(Error::Paused));
+            assert_eq!(foobar.vote_flip(), Err(Error::Paused));
+            assert!(!foobar.get());
+
+            assert_eq!(foobar.unpause(), Ok(()));
+            assert_eq!(foobar.flip(), Ok(()));
+            assert!(foobar.get());
+        }
+
+        /// We test that only the owner can pause and unpause.
+        #[ink::test]
+        fn pause_rejects_non_owner() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let mut foobar = Foobar::new(false);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.pause(), Err(Error::NotOwner));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.pause(), Ok(()));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.unpause(), Err(Error::NotOwner));
+            assert!(foobar.is_paused());
+        }
+
+        /// We test that the value freezes at the expiry block.
+        #[ink::test]
+        fn expiry_works() {
+            let mut foobar = Foobar::new_with_expiry(false, 2);
+            assert_eq!(foobar.expiry(), Some(2));
+            assert_eq!(foobar.flip(), Ok(()));
+
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert!(!foobar.is_expired());
+            assert_eq!(foobar.flip(), Ok(()));
+
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert!(foobar.is_expired());
+            assert_eq!(foobar.flip(), Err(Error::Expired));
+            assert_eq!(foobar.flip_to(true), Err(Error::Expired));
+            assert_eq!(foobar.reset_value(true), Err(Error::Expired));
+            assert!(!foobar.get());
+        }
+
+        /// We test that the regular constructors never expire.
+        #[ink::test]
+        fn no_expiry_by_default() {
+            let mut foobar = Foobar::default();
+            for _ in 0..10 {
+                ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            }
+            assert_eq!(foobar.expiry(), None);
+            assert!(!foobar.is_expired());
+            assert_eq!(foobar.flip(), Ok(()));
+        }
+
+        /// We test `value_at` before, between and after change points.
+        #[ink::test]
+        fn value_at_works() {
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            for _ in 0..2 {
+                ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            }
+            assert_eq!(foobar.flip(), Ok(()));
+            for _ in 0..3 {
+                ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            }
+            // Several flips in one block leave a single change point.
+            for _ in 0..3 {
+                assert_eq!(foobar.flip(), Ok(()));
+            }
+            assert_eq!(foobar.change_points.get().unwrap().len(), 3);
+
+            assert_eq!(foobar.value_at(0), None);
+            assert_eq!(foobar.value_at(1), Some(false));
+            assert_eq!(foobar.value_at(2), Some(false));
+            assert_eq!(foobar.value_at(3), Some(true));
+            assert_eq!(foobar.value_at(5), Some(true));
+            assert_eq!(foobar.value_at(6), Some(false));
+            assert_eq!(foobar.value_at(1_000), Some(false));
+        }
+
+        /// We test that the oldest change points are evicted beyond the maximum.
+        #[ink::test]
+        fn value_at_evicts_oldest() {
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.set_max_change_points(2), Ok(()));
+            for _ in 0..3 {
+                ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+                assert_eq!(foobar.flip(), Ok(()));
+            }
+
+            assert_eq!(foobar.value_at(0), None);
+            assert_eq!(foobar.value_at(1), None);
+            assert_eq!(foobar.value_at(2), Some(false));
+            assert_eq!(foobar.value_at(3), Some(true));
+        }
+
+        /// We test that personal flags of different accounts are independent.
+        #[ink::test]
+        fn personal_flags_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            assert!(!foobar.get_of(accounts.alice));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.flip_mine(), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.set_mine(false), Ok(()));
+
+            assert!(foobar.get_of(accounts.alice));
+            assert!(!foobar.get_of(accounts.bob));
+            assert!(!foobar.get());
+
+            assert_eq!(foobar.flip_mine(), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.set_mine(false), Ok(()));
+            assert!(!foobar.get_of(accounts.alice));
+            assert!(foobar.get_of(accounts.bob));
+
+            // `Created` plus three changes; `set_mine(false)` for bob changed nothing.
+            assert_eq!(ink::env::test::recorded_events().count(), 4);
+        }
+
+        /// We test that support is counted exactly once per set flag.
+        #[ink::test]
+        fn support_bookkeeping_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.set_mine(true), Ok(()));
+            assert_eq!(foobar.set_mine(true), Ok(()));
+            assert_eq!(foobar.current_support(), 1);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(foobar.flip_mine(), Ok(()));
+            assert_eq!(foobar.current_support(), 2);
+
+            assert_eq!(foobar.flip_mine(), Ok(()));
+            assert_eq!(foobar.set_mine(false), Ok(()));
+            assert_eq!(foobar.current_support(), 1);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.set_mine(false), Ok(()));
+            assert_eq!(foobar.current_support(), 0);
+        }
+
+        /// We test that `flip` requires the quorum of personal flags.
+        #[ink::test]
+        fn flip_requires_quorum() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.set_quorum(2), Ok(()));
+            assert_eq!(foobar.quorum(), 2);
+
+            assert_eq!(foobar.set_mine(true), Ok(()));
+            assert_eq!(foobar.flip(), Err(Error::QuorumNotMet));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.set_quorum(0), Err(Error::NotOwner));
+            assert_eq!(foobar.set_mine(true), Ok(()));
+            assert_eq!(foobar.flip(), Ok(()));
+            assert!(foobar.get());
+
+            assert_eq!(foobar.set_mine(false), Ok(()));
+            assert_eq!(foobar.flip(), Err(Error::QuorumNotMet));
+        }
+
+        /// We test the reason length limit at its boundary.
+        #[ink::test]
+        fn flip_with_reason_checks_length() {
+            let mut foobar = Foobar::new(false);
+            assert_eq!(
+                foobar.flip_with_reason("x".repeat(MAX_REASON_LEN + 1)),
+                Err(Error::ReasonTooLong)
+            );
+            assert!(!foobar.get());
+
+            assert_eq!(foobar.flip_with_reason("x".repeat(MAX_REASON_LEN)), Ok(()));
+            assert!(foobar.get());
+            assert_eq!(foobar.last_reason(), "x".repeat(MAX_REASON_LEN));
+
+            // A plain flip clears the reason.
+            assert_eq!(foobar.flip(), Ok(()));
+            assert_eq!(foobar.last_reason(), "");
+        }
+
+        /// We test that `FlippedWithReason` carries the reason and caller.
+        #[ink::test]
+        fn flip_with_reason_emits_event() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.flip_with_reason(String::from("audit #1")), Ok(()));
+
+            let events: Vec<_> = ink::env::test::recorded_events().collect();
+            assert_eq!(events.len(), 3);
+            let decoded = <Event as scale::Decode>::decode(&mut &events[2].data[..])
+                .expect("encountered invalid contract event data buffer");
+            match decoded {
+                Event::FlippedWithReason(FlippedWithReason { caller, reason }) => {
+                    assert_eq!(caller, accounts.bob);
+                    assert_eq!(reason, "audit #1");
+                }
+                _ => panic!("expected a `FlippedWithReason` event"),
+            }
+        }
+
+        /// We test that only flips update `last_flip_at`.
+        #[ink::test]
+        fn last_flip_at_works() {
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(6_000);
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.current_block(), 1);
+            assert_eq!(foobar.current_timestamp(), 6_000);
+            assert_eq!(foobar.last_flip_at(), (1, 6_000));
+
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(12_000);
+            assert!(!foobar.get());
+            assert_eq!(foobar.current_block(), 2);
+            assert_eq!(foobar.last_flip_at(), (1, 6_000));
+            foobar.flip().unwrap();
+            assert_eq!(foobar.last_flip_at(), (2, 12_000));
+
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(18_000);
+            // A rejected flip leaves the stamps alone.
+            foobar.pause().unwrap();
+            assert_eq!(foobar.flip(), Err(Error::Paused));
+            assert_eq!(foobar.last_flip_at(), (2, 12_000));
+            foobar.unpause().unwrap();
+            foobar.flip().unwrap();
+            assert_eq!(foobar.last_flip_at(), (3, 18_000));
+        }
+
+        /// We test that `code_hash` and `account_id` don't trap off-chain.
+        #[ink::test]
+        fn identity_works() {
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(AccountId::from([0xff; 32]));
+            let foobar = Foobar::new(false);
+            assert_eq!(foobar.account_id(), AccountId::from([0xff; 32]));
+            assert_eq!(foobar.code_hash(), Hash::default());
+        }
+
+        /// We test that `debug_meter` reports the balances and storage size,
+        /// and that mutating messages log their caller and arguments.
+        #[cfg(feature = "debug")]
+        #[ink::test]
+        fn debug_meter_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            let meter = foobar.debug_meter();
+            assert_eq!(meter.gas_left, 0);
+            assert_eq!(meter.balance, foobar.contract_balance());
+            assert_eq!(meter.minimum_balance, foobar.minimum_balance());
+            assert!(meter.storage_size > 0);
+
+            foobar.flip().unwrap();
+            foobar.set_cooldown(3).unwrap();
+            let messages = ink::env::test::recorded_debug_messages().into_iter().collect::<Vec<_>>();
+            assert!(messages.contains(&format!("flip: caller={:?}\n", accounts.alice)));
+            assert!(messages.contains(&format!("set_cooldown: caller={:?}\n", accounts.alice)));
+        }
+
+        /// We test that mutating messages log nothing without the `debug` feature.
+        #[cfg(not(feature = "debug"))]
+        #[ink::test]
+        fn debug_log_is_off_by_default() {
+            let mut foobar = Foobar::new(false);
+            foobar.flip().unwrap();
+            assert_eq!(ink::env::test::recorded_debug_messages().into_iter().count(), 0);
+        }
+
+        /// We test that `whoami` and `caller_context` describe the caller.
+        #[ink::test]
+        fn caller_context_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new_with_cooldown(false, 3);
+            assert_eq!(foobar.whoami(), accounts.alice);
+            assert_eq!(
+                foobar.caller_context(),
+                CallerContext {
+                    caller: accounts.alice,
+                    is_owner: true,
+                    is_allowed: false,
+                    is_banned: false,
+                    cooldown_remaining: 0,
+                    deposit: 0,
+                }
+            );
+            foobar.add_to_allowlist(accounts.bob).unwrap();
+            foobar.ban(accounts.charlie).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            foobar.flip().unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(40);
+            foobar.deposit().unwrap();
+            assert_eq!(foobar.whoami(), accounts.bob);
+            assert_eq!(
+                foobar.caller_context(),
+                CallerContext {
+                    caller: accounts.bob,
+                    is_owner: false,
+                    is_allowed: true,
+                    is_banned: false,
+                    cooldown_remaining: 3,
+                    deposit: 40,
+                }
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(foobar.whoami(), accounts.charlie);
+            let context = foobar.caller_context();
+            assert!(context.is_banned);
+            assert!(!context.is_owner);
+
+            // A renounced owner is no longer treated as the owner.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.renounce_ownership(true).unwrap();
+            assert!(!foobar.caller_context().is_owner);
+        }
+
+        /// We test that an account already on the leaderboard moves up.
+        #[ink::test]
+        fn leaderboard_reorders_on_increment() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            for (account, flips) in [(accounts.alice, 3), (accounts.bob, 2), (accounts.charlie, 1)] {
+                ink::env::test::set_caller::<ink::env::DefaultEnvironment>(account);
+                for _ in 0..flips {
+                    assert_eq!(foobar.flip(), Ok(()));
+                }
+            }
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            for _ in 0..3 {
+                assert_eq!(foobar.flip(), Ok(()));
+            }
+
+            assert_eq!(foobar.flips_of(accounts.charlie), 4);
+            assert_eq!(
+                foobar.leaderboard(),
+                vec![(accounts.charlie, 4), (accounts.alice, 3), (accounts.bob, 2)]
+            );
+        }
+
+        /// We test that a newcomer only enters a full leaderboard by overtaking the last slot.
+        #[ink::test]
+        fn leaderboard_newcomer_overtakes_last_slot() {
+            let mut foobar = Foobar::new(false);
+            for i in 0..LEADERBOARD_SIZE as u8 {
+                ink::env::test::set_caller::<ink::env::DefaultEnvironment>(AccountId::from([0x10 + i; 32]));
+                assert_eq!(foobar.flip_n(u32::from(i) + 2), Ok(()));
+            }
+            let last = AccountId::from([0x10; 32]);
+            assert_eq!(foobar.leaderboard()[LEADERBOARD_SIZE - 1], (last, 2));
+
+            // Tying the last slot is not enough.
+            let newcomer = AccountId::from([0x42; 32]);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(newcomer);
+            assert_eq!(foobar.flip_n(2), Ok(()));
+            assert_eq!(foobar.leaderboard()[LEADERBOARD_SIZE - 1], (last, 2));
+
+            assert_eq!(foobar.flip_n(2), Ok(()));
+            let leaderboard = foobar.leaderboard();
+            assert_eq!(leaderboard.len(), LEADERBOARD_SIZE);
+            assert!(!leaderboard.iter().any(|(account, _)| *account == last));
+            // The newcomer ranks behind the account that reached 4 flips first.
+            assert_eq!(leaderboard[LEADERBOARD_SIZE - 3], (AccountId::from([0x12; 32]), 4));
+            assert_eq!(leaderboard[LEADERBOARD_SIZE - 2], (newcomer, 4));
+            assert!(leaderboard.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        }
+
+        /// We test that `info` reflects the state after a couple of flips.
+        #[ink::test]
+        fn info_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            assert_eq!(
+                foobar.info(),
+                FoobarInfo {
+                    value: false,
+                    flip_count: 0,
+                    last_flipper: None,
+                    last_flip_block: None,
+                    paused: false,
+                }
+            );
+
+            assert_eq!(foobar.flip(), Ok(()));
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.flip_n(2), Ok(()));
+            assert_eq!(
+                foobar.info(),
+                FoobarInfo {
+                    value: true,
+                    flip_count: 3,
+                    last_flipper: Some(accounts.bob),
+                    last_flip_block: Some(1),
+                    paused: false,
+                }
+            );
+        }
+
+        /// We test that the latest block is found once the history wrapped around.
+        #[ink::test]
+        fn info_after_history_wraps() {
+            let mut foobar = Foobar::new(false);
+            for _ in 0..HISTORY_CAPACITY + 1 {
+                ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+                assert_eq!(foobar.flip(), Ok(()));
+            }
+            assert_eq!(foobar.info().last_flip_block, Some(HISTORY_CAPACITY as u32 + 1));
+        }
+
+        /// We test the owner-only observer registration.
+        #[ink::test]
+        fn register_observer_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let mut foobar = Foobar::new(false);
+
+            assert_eq!(foobar.register_observer(accounts.eve), Ok(()));
+            assert_eq!(foobar.register_observer(accounts.eve), Err(Error::AlreadyObserver));
+            assert_eq!(foobar.observers(), vec![accounts.eve]);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.register_observer(accounts.frank), Err(Error::NotOwner));
+            assert_eq!(foobar.unregister_observer(accounts.eve), Err(Error::NotOwner));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.unregister_observer(accounts.eve), Ok(()));
+            assert_eq!(foobar.unregister_observer(accounts.eve), Err(Error::NotObserver));
+            assert!(foobar.observers().is_empty());
+            assert_eq!(foobar.failed_notifications(), 0);
+        }
+
+        /// We test that the observer list is bounded.
+        #[ink::test]
+        fn register_observer_is_bounded() {
+            let mut foobar = Foobar::new(false);
+            for i in 0..MAX_OBSERVERS as u8 {
+                assert_eq!(foobar.register_observer(AccountId::from([0x20 + i; 32])), Ok(()));
+            }
+            assert_eq!(
+                foobar.register_observer(AccountId::from([0xff; 32])),
+                Err(Error::TooManyObservers)
+            );
+        }
+
+        /// We test that exactly `max_per_block` flips fit in a block.
+        #[ink::test]
+        fn rate_limit_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new_with_rate_limit(false, 2);
+            assert_eq!(foobar.max_flips_per_block(), 2);
+
+            assert_eq!(foobar.flip(), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.flip(), Ok(()));
+            assert_eq!(foobar.flip(), Err(Error::RateLimited));
+            assert_eq!(foobar.flip_count(), 2);
+
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert_eq!(foobar.flip(), Ok(()));
+            assert_eq!(foobar.flip(), Ok(()));
+            assert_eq!(foobar.flip(), Err(Error::RateLimited));
+        }
+
+        /// We test that only the owner can change the rate limit.
+        #[ink::test]
+        fn set_max_flips_per_block_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.max_flips_per_block(), u32::MAX);
+            assert_eq!(foobar.set_max_flips_per_block(0), Ok(()));
+            assert_eq!(foobar.flip(), Err(Error::RateLimited));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.set_max_flips_per_block(1), Err(Error::NotOwner));
+        }
+
+        /// We test that auctions get consecutive ids and can be read back.
+        #[ink::test]
+        fn create_auction_works() {
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.get_auction(0), None);
+            assert_eq!(foobar.auction_count(), 0);
+
+            let subject = Hash::from([0x07; 32]);
+            assert_eq!(foobar.create_auction(String::from("lot"), subject, 1, 0, 60_000, None), Ok(0));
+            let auction = foobar.get_auction(0).expect("auction was created");
+            assert_eq!(auction.name, "lot");
+            assert_eq!(auction.subject, subject);
+            assert_eq!(auction.status, Status::NotStarted);
+            assert!(!auction.finalized);
+
+            assert_eq!(
+                foobar.create_auction(String::from("other"), subject, 1, 0, 60_000, None),
+                Ok(1)
+            );
+            assert_eq!(foobar.auction_count(), 2);
+            assert_eq!(foobar.get_auction(0), Some(auction));
+            assert_eq!(foobar.get_auction(1).unwrap().name, "other");
+            assert_eq!(foobar.get_auction(2), None);
+        }
+
+        /// We test that no auction can be created while the contract is paused.
+        #[ink::test]
+        fn create_auction_rejects_when_paused() {
+            let mut foobar = Foobar::new(false);
+            foobar.pause().unwrap();
+            let subject = Hash::from([0x07; 32]);
+            assert_eq!(
+                foobar.create_auction(String::from("lot"), subject, 1, 0, 60_000, None),
+                Err(Error::Paused)
+            );
+            assert_eq!(foobar.auction_count(), 0);
+
+            foobar.unpause().unwrap();
+            assert_eq!(foobar.create_auction(String::from("lot"), subject, 1, 0, 60_000, None), Ok(0));
+        }
+
+        /// We test that the auction walks through its statuses in order.
+        #[ink::test]
+        fn auction_status_happy_path_works() {
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.start_auction(0), Err(Error::NoSuchAuction));
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+
+            assert_eq!(foobar.start_auction(auction_id), Ok(()));
+            assert_eq!(foobar.get_auction(auction_id).unwrap().status, Status::OpeningPeriod);
+            assert_eq!(foobar.begin_ending_period(auction_id), Ok(()));
+            assert_eq!(foobar.get_auction(auction_id).unwrap().status, Status::EndingPeriod);
+            assert_eq!(foobar.end_auction(auction_id), Ok(()));
+            assert_eq!(foobar.get_auction(auction_id).unwrap().status, Status::Ended);
+
+            // The last event is `AuctionEnded`, the status change precedes it.
+            let events = ink::env::test::recorded_events().collect::<Vec<_>>();
+            let event = &events[events.len() - 2];
+            let decoded = <Event as scale::Decode>::decode(&mut &event.data[..])
+                .expect("encountered invalid contract event data buffer");
+            match decoded {
+                Event::StatusChanged(StatusChanged { id, from, to }) => {
+                    assert_eq!(id, auction_id);
+                    assert_eq!(from, Status::EndingPeriod);
+                    assert_eq!(to, Status::Ended);
+                }
+                _ => panic!("expected a StatusChanged event"),
+            }
+        }
+
+        /// We test that illegal status jumps are rejected.
+        #[ink::test]
+        fn illegal_status_transitions_fail() {
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            assert_eq!(foobar.end_auction(auction_id), Err(Error::InvalidStatusTransition));
+            assert_eq!(foobar.begin_ending_period(auction_id), Err(Error::InvalidStatusTransition));
+
+            foobar.start_auction(auction_id).unwrap();
+            assert_eq!(foobar.start_auction(auction_id), Err(Error::InvalidStatusTransition));
+            assert_eq!(foobar.end_auction(auction_id), Err(Error::InvalidStatusTransition));
+
+            foobar.begin_ending_period(auction_id).unwrap();
+            assert_eq!(foobar.start_auction(auction_id), Err(Error::InvalidStatusTransition));
+            assert_eq!(foobar.begin_ending_period(auction_id), Err(Error::InvalidStatusTransition));
+
+            foobar.end_auction(auction_id).unwrap();
+            assert_eq!(foobar.start_auction(auction_id), Err(Error::InvalidStatusTransition));
+            assert_eq!(foobar.begin_ending_period(auction_id), Err(Error::InvalidStatusTransition));
+            assert_eq!(foobar.end_auction(auction_id), Err(Error::InvalidStatusTransition));
+
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            let mut other = Foobar::new(false);
+            let auction_id = other
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(other.start_auction(auction_id), Err(Error::NotOwner));
+        }
+
+        /// We test that only strictly higher bids replace the highest bid.
+        #[ink::test]
+        fn place_bid_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10);
+            assert_eq!(foobar.place_bid(auction_id), Err(Error::AuctionNotOpen));
+            foobar.start_auction(auction_id).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.place_bid(auction_id), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(foobar.place_bid(auction_id), Err(Error::BidTooLow));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(11);
+            assert_eq!(foobar.place_bid(auction_id), Ok(()));
+
+            let auction = foobar.get_auction(auction_id).unwrap();
+            assert_eq!(auction.highest_bid, 11);
+            assert_eq!(auction.highest_bidder, Some(accounts.charlie));
+
+            let event = ink::env::test::recorded_events().last().unwrap();
+            let decoded = <Event as scale::Decode>::decode(&mut &event.data[..])
+                .expect("encountered invalid contract event data buffer");
+            match decoded {
+                Event::BidPlaced(BidPlaced { id, bidder, amount }) => {
+                    assert_eq!(id, auction_id);
+                    assert_eq!(bidder, accounts.charlie);
+                    assert_eq!(amount, 11);
+                }
+                _ => panic!("expected a BidPlaced event"),
+            }
+        }
+
+        /// We test that bids are rejected while the contract is paused.
+        #[ink::test]
+        fn place_bid_rejects_when_paused() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            foobar.pause().unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10);
+            assert_eq!(foobar.place_bid(auction_id), Err(Error::Paused));
+            assert_eq!(foobar.get_auction(auction_id).unwrap().highest_bidder, None);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.unpause().unwrap();
+            bid_as(&mut foobar, auction_id, accounts.bob, 10);
+            assert_eq!(foobar.get_auction(auction_id).unwrap().highest_bidder, Some(accounts.bob));
+        }
+
+        /// We test that outbid bidders can withdraw exactly what they bid.
+        #[ink::test]
+        fn withdraw_refund_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+
+            for (bidder, amount) in [(accounts.bob, 10), (accounts.charlie, 20), (accounts.django, 30)] {
+                ink::env::test::set_caller::<ink::env::DefaultEnvironment>(bidder);
+                ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(amount);
+                foobar.place_bid(auction_id).unwrap();
+            }
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(40);
+            foobar.place_bid(auction_id).unwrap();
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 100);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+
+            assert_eq!(foobar.pending_refund(accounts.bob), 10);
+            assert_eq!(foobar.pending_refund(accounts.charlie), 20);
+            assert_eq!(foobar.pending_refund(accounts.django), 30);
+
+            let before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.charlie,
+            )
+            .unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(foobar.withdraw_refund(), Ok(20));
+            let after = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.charlie,
+            )
+            .unwrap();
+            assert_eq!(after - before, 20);
+            assert_eq!(foobar.pending_refund(accounts.charlie), 0);
+            assert_eq!(foobar.withdraw_refund(), Err(Error::NothingToWithdraw));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(foobar.withdraw_refund(), Ok(30));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.withdraw_refund(), Ok(10));
+        }
+
+        /// We test that finalizing pays the highest bid out to the seller once.
+        #[ink::test]
+        fn finalize_auction_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            assert_eq!(foobar.finalize_auction(auction_id), Err(Error::AuctionNotEnded));
+            foobar.start_auction(auction_id).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10);
+            foobar.place_bid(auction_id).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(25);
+            foobar.place_bid(auction_id).unwrap();
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 35);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.begin_ending_period(auction_id).unwrap();
+            assert_eq!(foobar.finalize_auction(auction_id), Err(Error::AuctionNotEnded));
+            foobar.end_auction(auction_id).unwrap();
+
+            let before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.alice,
+            )
+            .unwrap();
+            assert_eq!(foobar.finalize_auction(auction_id), Ok(()));
+            let after = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.alice,
+            )
+            .unwrap();
+            assert_eq!(after - before, 25);
+            assert!(foobar.get_auction(auction_id).unwrap().finalized);
+            assert_eq!(foobar.pending_refund(accounts.bob), 10);
+
+            let event = ink::env::test::recorded_events().last().unwrap();
+            let decoded = <Event as scale::Decode>::decode(&mut &event.data[..])
+                .expect("encountered invalid contract event data buffer");
+            match decoded {
+                Event::AuctionFinalized(AuctionFinalized { id, winner, amount }) => {
+                    assert_eq!(id, auction_id);
+                    assert_eq!(winner, Some(accounts.charlie));
+                    assert_eq!(amount, 25);
+                }
+                _ => panic!("expected an AuctionFinalized event"),
+            }
+
+            assert_eq!(foobar.finalize_auction(auction_id), Err(Error::AlreadyFinalized));
+        }
+
+        /// We test that an auction without bids finalizes without a transfer.
+        #[ink::test]
+        fn finalize_auction_without_bids_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            foobar.begin_ending_period(auction_id).unwrap();
+            foobar.end_auction(auction_id).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.finalize_auction(auction_id), Err(Error::NotOwner));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.alice,
+            )
+            .unwrap();
+            assert_eq!(foobar.finalize_auction(auction_id), Ok(()));
+            let after = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.alice,
+            )
+            .unwrap();
+            assert_eq!(after, before);
+            assert!(foobar.get_auction(auction_id).unwrap().finalized);
+            assert_eq!(foobar.finalize_auction(auction_id), Err(Error::AlreadyFinalized));
+        }
+
+        /// We test that follow-up bids must clear the minimum increment exactly.
+        #[ink::test]
+        fn min_increment_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 5, 0, 60_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+
+            // The first bid only has to be positive.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1);
+            assert_eq!(foobar.place_bid(auction_id), Ok(()));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5);
+            assert_eq!(foobar.place_bid(auction_id), Err(Error::IncrementTooSmall));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(6);
+            assert_eq!(foobar.place_bid(auction_id), Ok(()));
+            assert_eq!(foobar.get_auction(auction_id).unwrap().highest_bid, 6);
+        }
+
+        /// We test that the increment can only be changed by the owner before the start.
+        #[ink::test]
+        fn set_min_increment_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.set_min_increment(0, 3), Err(Error::NoSuchAuction));
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+
+            assert_eq!(foobar.set_min_increment(auction_id, 3), Ok(()));
+            assert_eq!(foobar.get_auction(auction_id).unwrap().min_increment, 3);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.set_min_increment(auction_id, 4), Err(Error::NotOwner));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.start_auction(auction_id).unwrap();
+            assert_eq!(foobar.set_min_increment(auction_id, 4), Err(Error::AuctionAlreadyStarted));
+            assert_eq!(foobar.get_auction(auction_id).unwrap().min_increment, 3);
+        }
+
+        /// We test that an auction without bids never meets its reserve.
+        #[ink::test]
+        fn reserve_without_bids_works() {
+            let mut foobar = Foobar::new(false);
+            assert!(!foobar.reserve_met(0));
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 20, 60_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            foobar.begin_ending_period(auction_id).unwrap();
+            foobar.end_auction(auction_id).unwrap();
+
+            assert!(!foobar.reserve_met(auction_id));
+            assert_eq!(foobar.finalize_auction(auction_id), Ok(()));
+            assert!(foobar.get_auction(auction_id).unwrap().finalized);
+        }
+
+        /// We test that a highest bid below the reserve is refunded instead of paid out.
+        #[ink::test]
+        fn reserve_not_met_refunds_bidder() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 20, 60_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(19);
+            assert_eq!(foobar.place_bid(auction_id), Ok(()));
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 19);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            assert!(!foobar.reserve_met(auction_id));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.begin_ending_period(auction_id).unwrap();
+            foobar.end_auction(auction_id).unwrap();
+            let before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.alice,
+            )
+            .unwrap();
+            assert_eq!(foobar.finalize_auction(auction_id), Ok(()));
+            let after = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.alice,
+            )
+            .unwrap();
+            assert_eq!(after, before);
+            assert!(foobar.get_auction(auction_id).unwrap().finalized);
+            assert_eq!(foobar.pending_refund(accounts.bob), 19);
+
+            let event = ink::env::test::recorded_events().last().unwrap();
+            let decoded = <Event as scale::Decode>::decode(&mut &event.data[..])
+                .expect("encountered invalid contract event data buffer");
+            match decoded {
+                Event::ReserveNotMet(ReserveNotMet {
+                    id,
+                    bidder,
+                    highest_bid,
+                    reserve_price,
+                }) => {
+                    assert_eq!(id, auction_id);
+                    assert_eq!(bidder, accounts.bob);
+                    assert_eq!(highest_bid, 19);
+                    assert_eq!(reserve_price, 20);
+                }
+                _ => panic!("expected a ReserveNotMet event"),
+            }
+        }
+
+        /// We test that a highest bid exactly at the reserve sells the auction.
+        #[ink::test]
+        fn reserve_met_pays_seller() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 20, 60_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(20);
+            assert_eq!(foobar.place_bid(auction_id), Ok(()));
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 20);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            assert!(foobar.reserve_met(auction_id));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.begin_ending_period(auction_id).unwrap();
+            foobar.end_auction(auction_id).unwrap();
+            let before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.alice,
+            )
+            .unwrap();
+            assert_eq!(foobar.finalize_auction(auction_id), Ok(()));
+            let after = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.alice,
+            )
+            .unwrap();
+            assert_eq!(after - before, 20);
+            assert_eq!(foobar.pending_refund(accounts.bob), 0);
+        }
+
+        /// We test that bids stop at the deadline and anyone can finalize after it.
+        #[ink::test]
+        fn deadline_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.time_remaining(0), 0);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 500, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            assert_eq!(foobar.get_auction(auction_id).unwrap().end_timestamp, 1_500);
+            assert_eq!(foobar.time_remaining(auction_id), 500);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_499);
+            assert_eq!(foobar.time_remaining(auction_id), 1);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10);
+            assert_eq!(foobar.place_bid(auction_id), Ok(()));
+            assert_eq!(foobar.finalize_auction(auction_id), Err(Error::NotOwner));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_500);
+            assert_eq!(foobar.time_remaining(auction_id), 0);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(20);
+            assert_eq!(foobar.place_bid(auction_id), Err(Error::AuctionClosed));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(2_000);
+            assert_eq!(foobar.time_remaining(auction_id), 0);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                ink::env::test::callee::<ink::env::DefaultEnvironment>(),
+                10,
+            );
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            assert_eq!(foobar.finalize_auction(auction_id), Ok(()));
+            let auction = foobar.get_auction(auction_id).unwrap();
+            assert_eq!(auction.status, Status::Ended);
+            assert!(auction.finalized);
+        }
+
+        /// We test that only bids inside the window extend the deadline, up to the cap.
+        #[ink::test]
+        fn anti_sniping_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 500, None)
+                .unwrap();
+            assert_eq!(foobar.set_extension_policy(auction_id, 100, 200, 2), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.set_extension_policy(auction_id, 100, 200, 2), Err(Error::NotOwner));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.start_auction(auction_id).unwrap();
+            assert_eq!(
+                foobar.set_extension_policy(auction_id, 100, 200, 2),
+                Err(Error::AuctionAlreadyStarted)
+            );
+
+            // Exactly `window_ms` before the deadline is just outside the window.
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_400);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10);
+            foobar.place_bid(auction_id).unwrap();
+            assert_eq!(foobar.get_auction(auction_id).unwrap().end_timestamp, 1_500);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_401);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(11);
+            foobar.place_bid(auction_id).unwrap();
+            assert_eq!(foobar.get_auction(auction_id).unwrap().end_timestamp, 1_700);
+            let event = ink::env::test::recorded_events().last().unwrap();
+            let decoded = <Event as scale::Decode>::decode(&mut &event.data[..])
+                .expect("encountered invalid contract event data buffer");
+            match decoded {
+                Event::DeadlineExtended(DeadlineExtended { id, new_end }) => {
+                    assert_eq!(id, auction_id);
+                    assert_eq!(new_end, 1_700);
+                }
+                _ => panic!("expected a DeadlineExtended event"),
+            }
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_650);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(12);
+            foobar.place_bid(auction_id).unwrap();
+            assert_eq!(foobar.get_auction(auction_id).unwrap().end_timestamp, 1_900);
+
+            // The cap of two extensions is reached.
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_899);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(13);
+            foobar.place_bid(auction_id).unwrap();
+            let auction = foobar.get_auction(auction_id).unwrap();
+            assert_eq!(auction.end_timestamp, 1_900);
+            assert_eq!(auction.extensions, 2);
+        }
+
+        /// We test that interleaved bids on two auctions don't leak into each other.
+        #[ink::test]
+        fn concurrent_auctions_are_isolated() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            let first = foobar
+                .create_auction(String::from("first"), Hash::from([0x01; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            let second = foobar
+                .create_auction(String::from("second"), Hash::from([0x02; 32]), 5, 0, 60_000, None)
+                .unwrap();
+            assert_ne!(first, second);
+            foobar.start_auction(first).unwrap();
+            foobar.start_auction(second).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10);
+            assert_eq!(foobar.place_bid(first), Ok(()));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(3);
+            assert_eq!(foobar.place_bid(second), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(7);
+            assert_eq!(foobar.place_bid(second), Err(Error::IncrementTooSmall));
+            assert_eq!(foobar.place_bid(first), Err(Error::BidTooLow));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(8);
+            assert_eq!(foobar.place_bid(second), Ok(()));
+
+            let first_auction = foobar.get_auction(first).unwrap();
+            assert_eq!(first_auction.highest_bid, 10);
+            assert_eq!(first_auction.highest_bidder, Some(accounts.bob));
+            let second_auction = foobar.get_auction(second).unwrap();
+            assert_eq!(second_auction.highest_bid, 8);
+            assert_eq!(second_auction.highest_bidder, Some(accounts.charlie));
+            assert_eq!(foobar.pending_refund(accounts.bob), 3);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.begin_ending_period(first).unwrap();
+            assert_eq!(foobar.get_auction(first).unwrap().status, Status::EndingPeriod);
+            assert_eq!(foobar.get_auction(second).unwrap().status, Status::OpeningPeriod);
+        }
+
+        /// We test that every auction message rejects unknown ids.
+        #[ink::test]
+        fn unknown_auction_id_fails() {
+            let mut foobar = Foobar::new(false);
+            foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            assert_eq!(foobar.get_auction(1), None);
+            assert_eq!(foobar.set_min_increment(1, 2), Err(Error::NoSuchAuction));
+            assert_eq!(foobar.set_extension_policy(1, 1, 1, 1), Err(Error::NoSuchAuction));
+            assert_eq!(foobar.start_auction(1), Err(Error::NoSuchAuction));
+            assert_eq!(foobar.begin_ending_period(1), Err(Error::NoSuchAuction));
+            assert_eq!(foobar.end_auction(1), Err(Error::NoSuchAuction));
+            assert_eq!(foobar.place_bid(1), Err(Error::NoSuchAuction));
+            assert_eq!(foobar.finalize_auction(1), Err(Error::NoSuchAuction));
+        }
+
+        /// We test the topics and data of the auction lifecycle events.
+        #[ink::test]
+        fn auction_events_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            let subject = Hash::from([0x07; 32]);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), subject, 1, 0, 60_000, None)
+                .unwrap();
+            let event = ink::env::test::recorded_events().last().unwrap();
+            assert_eq!(
+                event.topics,
+                vec![
+                    topic_of(b"", b"Foobar::AuctionCreated"),
+                    topic_of(b"Foobar::AuctionCreated::id", &auction_id),
+                    topic_of(b"Foobar::AuctionCreated::seller", &accounts.alice),
+                    topic_of(b"Foobar::AuctionCreated::subject", &subject),
+                ]
+            );
+            match decode_event(&event) {
+                Event::AuctionCreated(AuctionCreated { id, seller, subject: hash }) => {
+                    assert_eq!(id, auction_id);
+                    assert_eq!(seller, accounts.alice);
+                    assert_eq!(hash, subject);
+                }
+                _ => panic!("expected an AuctionCreated event"),
+            }
+
+            foobar.start_auction(auction_id).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10);
+            foobar.place_bid(auction_id).unwrap();
+            let event = ink::env::test::recorded_events().last().unwrap();
+            assert_eq!(
+                event.topics,
+                vec![
+                    topic_of(b"", b"Foobar::BidPlaced"),
+                    topic_of(b"Foobar::BidPlaced::id", &auction_id),
+                    topic_of(b"Foobar::BidPlaced::bidder", &accounts.bob),
+                ]
+            );
+            match decode_event(&event) {
+                Event::BidPlaced(BidPlaced { id, bidder, amount }) => {
+                    assert_eq!(id, auction_id);
+                    assert_eq!(bidder, accounts.bob);
+                    assert_eq!(amount, 10);
+                }
+                _ => panic!("expected a BidPlaced event"),
+            }
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 10);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            foobar.begin_ending_period(auction_id).unwrap();
+            foobar.end_auction(auction_id).unwrap();
+            let event = ink::env::test::recorded_events().last().unwrap();
+            assert_eq!(
+                event.topics,
+                vec![
+                    topic_of(b"", b"Foobar::AuctionEnded"),
+                    topic_of(b"Foobar::AuctionEnded::id", &auction_id),
+                ]
+            );
+            match decode_event(&event) {
+                Event::AuctionEnded(AuctionEnded { id }) => assert_eq!(id, auction_id),
+                _ => panic!("expected an AuctionEnded event"),
+            }
+
+            foobar.finalize_auction(auction_id).unwrap();
+            let event = ink::env::test::recorded_events().last().unwrap();
+            assert_eq!(
+                event.topics,
+                vec![
+                    topic_of(b"", b"Foobar::AuctionFinalized"),
+                    topic_of(b"Foobar::AuctionFinalized::id", &auction_id),
+                    topic_of(b"Foobar::AuctionFinalized::winner", &Some(accounts.bob)),
+                ]
+            );
+            match decode_event(&event) {
+                Event::AuctionFinalized(AuctionFinalized { id, winner, amount }) => {
+                    assert_eq!(id, auction_id);
+                    assert_eq!(winner, Some(accounts.bob));
+                    assert_eq!(amount, 10);
+                }
+                _ => panic!("expected an AuctionFinalized event"),
+            }
+        }
+
+        /// We test that the seller can cancel an auction before the first bid.
+        #[ink::test]
+        fn cancel_auction_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+
+            assert_eq!(foobar.cancel_auction(auction_id), Ok(()));
+            assert_eq!(foobar.get_auction(auction_id).unwrap().status, Status::Cancelled);
+            let event = ink::env::test::recorded_events().last().unwrap();
+            assert_eq!(
+                event.topics,
+                vec![
+                    topic_of(b"", b"Foobar::AuctionCancelled"),
+                    topic_of(b"Foobar::AuctionCancelled::id", &auction_id),
+                ]
+            );
+            match decode_event(&event) {
+                Event::AuctionCancelled(AuctionCancelled { id }) => assert_eq!(id, auction_id),
+                _ => panic!("expected an AuctionCancelled event"),
+            }
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10);
+            assert_eq!(foobar.place_bid(auction_id), Err(Error::AuctionNotOpen));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(60_000);
+            assert_eq!(foobar.finalize_auction(auction_id), Err(Error::Cancelled));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.cancel_auction(auction_id), Err(Error::CannotCancel));
+        }
+
+        /// We test that an auction with a bid can no longer be cancelled.
+        #[ink::test]
+        fn cancel_auction_rejects_after_bid() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10);
+            foobar.place_bid(auction_id).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.cancel_auction(auction_id), Err(Error::CannotCancel));
+            assert_eq!(foobar.get_auction(auction_id).unwrap().status, Status::OpeningPeriod);
+        }
+
+        /// We test that only the seller can cancel an auction.
+        #[ink::test]
+        fn cancel_auction_rejects_other_callers() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+
+            // Not even the contract owner may cancel someone else's auction.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.cancel_auction(auction_id), Err(Error::NotSeller));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.cancel_auction(auction_id), Ok(()));
+        }
+
+        /// We test that the highest revealed sealed bid wins and deposits are settled.
+        #[ink::test]
+        fn sealed_bid_auction_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_sealed_auction(String::from("lot"), Hash::from([0x07; 32]), 0, 1_000, 1_000)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+
+            for (bidder, amount, deposit) in [
+                (accounts.bob, 10, 15),
+                (accounts.charlie, 20, 20),
+                (accounts.django, 30, 30),
+            ] {
+                ink::env::test::set_caller::<ink::env::DefaultEnvironment>(bidder);
+                ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(deposit);
+                let commitment = Foobar::bid_commitment(amount, b"salt", &bidder);
+                assert_eq!(foobar.commit_bid(auction_id, commitment), Ok(()));
+            }
+            assert_eq!(foobar.place_bid(auction_id), Err(Error::WrongAuctionKind));
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 65);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+
+            // Bids can't be revealed while bidding is still open.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                foobar.reveal_bid(auction_id, 10, b"salt".to_vec()),
+                Err(Error::NotRevealPhase)
+            );
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            assert_eq!(foobar.reveal_bid(auction_id, 10, b"salt".to_vec()), Ok(()));
+            assert_eq!(foobar.pending_refund(accounts.bob), 5);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(foobar.reveal_bid(auction_id, 20, b"salt".to_vec()), Ok(()));
+            assert_eq!(foobar.pending_refund(accounts.bob), 15);
+            assert_eq!(foobar.pending_refund(accounts.charlie), 0);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.finalize_auction(auction_id), Err(Error::RevealNotOver));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(2_000);
+            let before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.alice,
+            )
+            .unwrap();
+            assert_eq!(foobar.finalize_auction(auction_id), Ok(()));
+            let after = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.alice,
+            )
+            .unwrap();
+            assert_eq!(after - before, 20);
+            let auction = foobar.get_auction(auction_id).unwrap();
+            assert_eq!(auction.highest_bidder, Some(accounts.charlie));
+            assert_eq!(auction.highest_bid, 20);
+
+            // Django's larger bid was never revealed, so it doesn't count.
+            assert_eq!(foobar.pending_refund(accounts.django), 0);
+        }
+
+        /// We test that sealed bids can't be committed or revealed while the
+        /// contract is paused.
+        #[ink::test]
+        fn sealed_bids_reject_when_paused() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_sealed_auction(String::from("lot"), Hash::from([0x07; 32]), 0, 1_000, 1_000)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            foobar.pause().unwrap();
+            assert_eq!(
+                foobar.create_sealed_auction(String::from("lot"), Hash::from([0x07; 32]), 0, 1_000, 1_000),
+                Err(Error::Paused)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10);
+            let commitment = Foobar::bid_commitment(10, b"salt", &accounts.bob);
+            assert_eq!(foobar.commit_bid(auction_id, commitment), Err(Error::Paused));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.unpause().unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.commit_bid(auction_id, commitment), Ok(()));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.pause().unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.reveal_bid(auction_id, 10, b"salt".to_vec()), Err(Error::Paused));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.unpause().unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.reveal_bid(auction_id, 10, b"salt".to_vec()), Ok(()));
+        }
+
+        /// We test that reveals must match the commitment and the deposit, once.
+        #[ink::test]
+        fn reveal_bid_rejects_invalid_reveals() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            let english = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            let auction_id = foobar
+                .create_sealed_auction(String::from("lot"), Hash::from([0x07; 32]), 0, 1_000, 1_000)
+                .unwrap();
+            foobar.start_auction(english).unwrap();
+            foobar.start_auction(auction_id).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10);
+            let commitment = Foobar::bid_commitment(10, b"salt", &accounts.bob);
+            assert_eq!(foobar.commit_bid(english, commitment), Err(Error::WrongAuctionKind));
+            assert_eq!(foobar.commit_bid(auction_id, commitment), Ok(()));
+            assert_eq!(foobar.commit_bid(auction_id, commitment), Err(Error::AlreadyCommitted));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            let commitment = Foobar::bid_commitment(50, b"salt", &accounts.charlie);
+            assert_eq!(foobar.commit_bid(auction_id, commitment), Ok(()));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                foobar.reveal_bid(auction_id, 10, b"pepper".to_vec()),
+                Err(Error::CommitmentMismatch)
+            );
+            assert_eq!(
+                foobar.reveal_bid(auction_id, 9, b"salt".to_vec()),
+                Err(Error::CommitmentMismatch)
+            );
+            assert_eq!(foobar.reveal_bid(auction_id, 10, b"salt".to_vec()), Ok(()));
+            assert_eq!(
+                foobar.reveal_bid(auction_id, 10, b"salt".to_vec()),
+                Err(Error::AlreadyRevealed)
+            );
+
+            // Charlie committed to more than they deposited.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(
+                foobar.reveal_bid(auction_id, 50, b"salt".to_vec()),
+                Err(Error::DepositTooLow)
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(
+                foobar.reveal_bid(auction_id, 10, b"salt".to_vec()),
+                Err(Error::NoCommitment)
+            );
+
+            let auction = foobar.get_auction(auction_id).unwrap();
+            assert_eq!(auction.highest_bidder, Some(accounts.bob));
+            assert_eq!(auction.highest_bid, 10);
+        }
+
+        /// We test that unrevealed deposits can be reclaimed after the reveal phase.
+        #[ink::test]
+        fn reclaim_deposit_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_sealed_auction(String::from("lot"), Hash::from([0x07; 32]), 0, 1_000, 1_000)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(25);
+            let commitment = Foobar::bid_commitment(20, b"salt", &accounts.bob);
+            foobar.commit_bid(auction_id, commitment).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_999);
+            assert_eq!(foobar.reclaim_deposit(auction_id), Err(Error::RevealNotOver));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(2_000);
+            assert_eq!(
+                foobar.reveal_bid(auction_id, 20, b"salt".to_vec()),
+                Err(Error::NotRevealPhase)
+            );
+            assert_eq!(foobar.reclaim_deposit(auction_id), Ok(25));
+            assert_eq!(foobar.pending_refund(accounts.bob), 25);
+            assert_eq!(foobar.reclaim_deposit(auction_id), Err(Error::NoCommitment));
+            assert_eq!(foobar.get_auction(auction_id).unwrap().highest_bidder, None);
+        }
+
+        /// We test that pausing freezes auction and ownership changes while
+        /// withdrawals and refunds keep working.
+        #[ink::test]
+        fn pause_freezes_auctions_but_not_withdrawals() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            let subject = Hash::from([0x07; 32]);
+            let open = foobar.create_auction(String::from("open"), subject, 1, 0, 60_000, None).unwrap();
+            foobar.start_auction(open).unwrap();
+            let idle = foobar.create_auction(String::from("idle"), subject, 1, 0, 60_000, None).unwrap();
+            let sealed = foobar.create_sealed_auction(String::from("sealed"), subject, 0, 1_000, 1_000).unwrap();
+            foobar.start_auction(sealed).unwrap();
+            bid_as(&mut foobar, open, accounts.bob, 10);
+            bid_as(&mut foobar, open, accounts.charlie, 20);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(25);
+            foobar.commit_bid(sealed, Foobar::bid_commitment(20, b"salt", &accounts.bob)).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 100);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.propose_owner(accounts.django).unwrap();
+            let action = foobar.queue_action(AdminAction::SetFeeBps(100)).unwrap();
+            let unpause = foobar.queue_action(AdminAction::Unpause).unwrap();
+            foobar.pause().unwrap();
+
+            assert_eq!(foobar.start_auction(idle), Err(Error::Paused));
+            assert_eq!(foobar.begin_ending_period(open), Err(Error::Paused));
+            assert_eq!(foobar.end_auction(open), Err(Error::Paused));
+            assert_eq!(foobar.cancel_auction(idle), Err(Error::Paused));
+            assert_eq!(foobar.transfer_auction(idle, accounts.eve), Err(Error::Paused));
+            assert_eq!(foobar.set_subject(idle, Hash::from([0x08; 32])), Err(Error::Paused));
+            assert_eq!(foobar.set_settlement(idle, SettlementKind::SecondPrice), Err(Error::Paused));
+            assert_eq!(foobar.add_allowed_bidder(idle, accounts.eve), Err(Error::Paused));
+            assert_eq!(foobar.remove_allowed_bidder(idle, accounts.eve), Err(Error::Paused));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(60_000);
+            assert_eq!(foobar.finalize_auction(open), Err(Error::Paused));
+            assert_eq!(foobar.finalize_expired(5), Err(Error::Paused));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(foobar.accept_ownership(), Err(Error::Paused));
+            assert_eq!(foobar.get_auction(idle).unwrap().status, Status::NotStarted);
+            assert_eq!(foobar.get_auction(open).unwrap().status, Status::OpeningPeriod);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.withdraw_refund(), Ok(10));
+            assert_eq!(foobar.reclaim_deposit(sealed), Ok(25));
+            assert_eq!(foobar.withdraw_all(), Ok(25));
+
+            assert_eq!(foobar.execute_action(action), Ok(()));
+            assert_eq!(foobar.fee_bps(), 100);
+            assert_eq!(foobar.execute_action(unpause), Ok(()));
+            assert!(!foobar.is_paused());
+        }
+
+        /// We test the Dutch price at the start, midpoint and end of its schedule.
+        #[ink::test]
+        fn dutch_price_schedule_works() {
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            let mut foobar = Foobar::new(false);
+            assert_eq!(
+                foobar.create_dutch_auction(String::from("lot"), Hash::from([0x07; 32]), 10, 20, 1_000),
+                Err(Error::InvalidPriceSchedule)
+            );
+            let auction_id = foobar
+                .create_dutch_auction(String::from("lot"), Hash::from([0x07; 32]), 100, 20, 1_000)
+                .unwrap();
+
+            assert_eq!(foobar.current_price(auction_id), 100);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_500);
+            assert_eq!(foobar.current_price(auction_id), 60);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_999);
+            assert_eq!(foobar.current_price(auction_id), 21);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(2_000);
+            assert_eq!(foobar.current_price(auction_id), 20);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(5_000);
+            assert_eq!(foobar.current_price(auction_id), 20);
+        }
+
+        /// We test that buying a Dutch auction settles it and refunds the overpayment.
+        #[ink::test]
+        fn buy_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_dutch_auction(String::from("lot"), Hash::from([0x07; 32]), 100, 20, 1_000)
+                .unwrap();
+            let english = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 1_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            foobar.start_auction(english).unwrap();
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_500);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(70);
+            assert_eq!(foobar.buy(english), Err(Error::WrongAuctionKind));
+            assert_eq!(foobar.place_bid(auction_id), Err(Error::WrongAuctionKind));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(59);
+            assert_eq!(foobar.buy(auction_id), Err(Error::BidTooLow));
+
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(70);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 70);
+            let before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.alice,
+            )
+            .unwrap();
+            assert_eq!(foobar.buy(auction_id), Ok(()));
+            let after = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.alice,
+            )
+            .unwrap();
+            assert_eq!(after - before, 60);
+            assert_eq!(foobar.pending_refund(accounts.bob), 10);
+
+            let auction = foobar.get_auction(auction_id).unwrap();
+            assert_eq!(auction.status, Status::Ended);
+            assert!(auction.finalized);
+            assert_eq!(auction.highest_bidder, Some(accounts.bob));
+            assert_eq!(auction.highest_bid, 60);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(foobar.buy(auction_id), Err(Error::AuctionNotOpen));
+        }
+
+        /// We test that Dutch auctions can't be created or bought while the
+        /// contract is paused.
+        #[ink::test]
+        fn buy_rejects_when_paused() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_dutch_auction(String::from("lot"), Hash::from([0x07; 32]), 100, 20, 1_000)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            foobar.pause().unwrap();
+            assert_eq!(
+                foobar.create_dutch_auction(String::from("lot"), Hash::from([0x07; 32]), 100, 20, 1_000),
+                Err(Error::Paused)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(100);
+            assert_eq!(foobar.buy(auction_id), Err(Error::Paused));
+            assert_eq!(foobar.get_auction(auction_id).unwrap().status, Status::OpeningPeriod);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.unpause().unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 100);
+            assert_eq!(foobar.buy(auction_id), Ok(()));
+        }
+
+        /// We test that auction listings page through all auctions and filter by status.
+        #[ink::test]
+        fn list_auctions_works() {
+            let mut foobar = Foobar::new(false);
+            assert!(foobar.list_auctions(0, 10).is_empty());
+            for i in 0..10u8 {
+                foobar
+                    .create_auction(String::from("lot"), Hash::from([i; 32]), 1, 0, 60_000, None)
+                    .unwrap();
+            }
+            for auction_id in [1, 4, 5, 8] {
+                foobar.start_auction(auction_id).unwrap();
+            }
+            foobar.begin_ending_period(5).unwrap();
+
+            let ids = |page: Vec<(u32, AuctionSummary)>| {
+                page.into_iter().map(|(id, _)| id).collect::<Vec<_>>()
+            };
+            assert_eq!(ids(foobar.list_auctions(0, 4)), vec![0, 1, 2, 3]);
+            assert_eq!(ids(foobar.list_auctions(8, 4)), vec![8, 9]);
+            assert_eq!(ids(foobar.list_auctions(10, 4)), Vec::<u32>::new());
+            assert_eq!(ids(foobar.list_auctions(u32::MAX, u32::MAX)), Vec::<u32>::new());
+            assert_eq!(foobar.list_auctions(0, 0), Vec::new());
+
+            let (id, summary) = foobar.list_auctions(5, 1).remove(0);
+            assert_eq!(id, 5);
+            assert_eq!(
+                summary,
+                AuctionSummary {
+                    id: 5,
+                    name: String::from("lot"),
+                    status: Status::EndingPeriod,
+                    highest_bid: 0,
+                    end_timestamp: 60_000,
+                }
+            );
+
+            let sorted_ids = |page: Vec<(u32, AuctionSummary)>| {
+                let mut ids = ids(page);
+                ids.sort();
+                ids
+            };
+            assert_eq!(sorted_ids(foobar.list_by_status(Status::NotStarted, 0, 50)), vec![0, 2, 3, 6, 7, 9]);
+            assert_eq!(foobar.count_by_status(Status::NotStarted), 6);
+            let mut pages = ids(foobar.list_by_status(Status::NotStarted, 0, 2));
+            pages.extend(ids(foobar.list_by_status(Status::NotStarted, 2, 3)));
+            pages.extend(ids(foobar.list_by_status(Status::NotStarted, 5, 3)));
+            pages.sort();
+            assert_eq!(pages, vec![0, 2, 3, 6, 7, 9]);
+            assert_eq!(sorted_ids(foobar.list_by_status(Status::OpeningPeriod, 0, 50)), vec![1, 4, 8]);
+            assert_eq!(ids(foobar.list_by_status(Status::OpeningPeriod, 3, 50)), Vec::<u32>::new());
+            assert_eq!(ids(foobar.list_by_status(Status::EndingPeriod, 0, 50)), vec![5]);
+            assert_eq!(ids(foobar.list_by_status(Status::Ended, 0, 50)), Vec::<u32>::new());
+            assert_eq!(foobar.count_by_status(Status::Ended), 0);
+
+            // Moving the last auction of a status out needs no reordering.
+            foobar.begin_ending_period(8).unwrap();
+            assert_eq!(sorted_ids(foobar.list_by_status(Status::OpeningPeriod, 0, 50)), vec![1, 4]);
+            assert_eq!(sorted_ids(foobar.list_by_status(Status::EndingPeriod, 0, 50)), vec![5, 8]);
+            foobar.begin_ending_period(1).unwrap();
+            assert_eq!(ids(foobar.list_by_status(Status::OpeningPeriod, 0, 50)), vec![4]);
+            assert_eq!(foobar.count_by_status(Status::EndingPeriod), 3);
+        }
+
+        /// We test that listing pages are capped at `MAX_PAGE_SIZE` entries.
+        #[ink::test]
+        fn list_auctions_caps_limit() {
+            let mut foobar = Foobar::new(false);
+            for _ in 0..MAX_PAGE_SIZE + 5 {
+                foobar
+                    .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                    .unwrap();
+            }
+            assert_eq!(foobar.list_auctions(0, u32::MAX).len(), MAX_PAGE_SIZE as usize);
+            assert_eq!(foobar.list_auctions(MAX_PAGE_SIZE, u32::MAX).len(), 5);
+            assert_eq!(
+                foobar.list_by_status(Status::NotStarted, 0, u32::MAX).len(),
+                MAX_PAGE_SIZE as usize
+            );
+        }
+
+        /// We test that bid histories are kept per auction and bidder and evict the oldest.
+        #[ink::test]
+        fn bids_of_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            let first = foobar
+                .create_auction(String::from("first"), Hash::from([0x01; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            let second = foobar
+                .create_auction(String::from("second"), Hash::from([0x02; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            foobar.start_auction(first).unwrap();
+            foobar.start_auction(second).unwrap();
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(100);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10);
+            foobar.place_bid(first).unwrap();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(200);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(20);
+            foobar.place_bid(first).unwrap();
+            foobar.place_bid(second).unwrap();
+            // A rejected bid is not recorded.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(15);
+            assert_eq!(foobar.place_bid(first), Err(Error::BidTooLow));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(300);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(30);
+            foobar.place_bid(first).unwrap();
+
+            assert_eq!(foobar.bids_of(first, accounts.bob), vec![(10, 100), (30, 300)]);
+            assert_eq!(foobar.bids_of(first, accounts.charlie), vec![(20, 200)]);
+            assert_eq!(foobar.bids_of(second, accounts.charlie), vec![(20, 200)]);
+            assert_eq!(foobar.bids_of(second, accounts.bob), Vec::new());
+
+            // Charlie and Bob outbid each other until Bob's history overflows.
+            for round in 1..=MAX_BIDS_PER_BIDDER as u128 {
+                ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+                ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(20 + 20 * round);
+                foobar.place_bid(second).unwrap();
+                ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+                ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(30 + 20 * round);
+                foobar.place_bid(second).unwrap();
+            }
+            let bids = foobar.bids_of(second, accounts.charlie);
+            assert_eq!(bids.len(), MAX_BIDS_PER_BIDDER);
+            assert_eq!(bids[0], (40, 300));
+            assert_eq!(bids[MAX_BIDS_PER_BIDDER - 1], (420, 300));
+            assert_eq!(foobar.bids_of(second, accounts.bob).len(), MAX_BIDS_PER_BIDDER);
+            assert_eq!(foobar.bids_of(first, accounts.bob), vec![(10, 100), (30, 300)]);
+        }
+
+        /// We test that the subject can be verified against its preimage.
+        #[ink::test]
+        fn verify_subject_works() {
+            let mut foobar = Foobar::new(false);
+            let metadata = b"{\"name\":\"lot\"}".to_vec();
+            let mut subject = [0u8; 32];
+            ink::env::hash_bytes::<Blake2x256>(&metadata, &mut subject);
+            let subject = Hash::from(subject);
+            assert!(!foobar.verify_subject(0, metadata.clone()));
+            let auction_id = foobar
+                .create_auction(String::from("lot"), subject, 1, 0, 60_000, None)
+                .unwrap();
+
+            assert!(foobar.verify_subject(auction_id, metadata));
+            assert!(!foobar.verify_subject(auction_id, b"{\"name\":\"other\"}".to_vec()));
+            assert!(!foobar.verify_subject(auction_id, Vec::new()));
+        }
+
+        /// We test that only the seller can change the subject, and only before the start.
+        #[ink::test]
+        fn set_subject_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            let mut subject = [0u8; 32];
+            ink::env::hash_bytes::<Blake2x256>(b"fixed", &mut subject);
+            let subject = Hash::from(subject);
+            assert_eq!(foobar.set_subject(auction_id, subject), Ok(()));
+            assert!(foobar.verify_subject(auction_id, b"fixed".to_vec()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                foobar.set_subject(auction_id, Hash::from([0x08; 32])),
+                Err(Error::NotSeller)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.start_auction(auction_id).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10);
+            foobar.place_bid(auction_id).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(
+                foobar.set_subject(auction_id, Hash::from([0x08; 32])),
+                Err(Error::AuctionAlreadyStarted)
+            );
+            assert_eq!(foobar.get_auction(auction_id).unwrap().subject, subject);
+        }
+
+        /// We test that only allowed accounts can bid on restricted auctions.
+        #[ink::test]
+        fn allowlist_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            let open_id = foobar
+                .create_auction(String::from("open"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            let restricted_id = foobar
+                .create_auction(String::from("private"), Hash::from([0x08; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            assert_eq!(foobar.add_allowed_bidder(restricted_id, accounts.bob), Ok(()));
+            foobar.start_auction(open_id).unwrap();
+            foobar.start_auction(restricted_id).unwrap();
+            assert!(foobar.is_allowed_bidder(open_id, accounts.charlie));
+            assert!(foobar.is_allowed_bidder(restricted_id, accounts.bob));
+            assert!(!foobar.is_allowed_bidder(restricted_id, accounts.charlie));
+
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.place_bid(open_id), Ok(()));
+            assert_eq!(foobar.place_bid(restricted_id), Ok(()));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(20);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(foobar.place_bid(open_id), Ok(()));
+            assert_eq!(foobar.place_bid(restricted_id), Err(Error::NotAllowed));
+            assert_eq!(
+                foobar.add_allowed_bidder(restricted_id, accounts.charlie),
+                Err(Error::NotSeller)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.add_allowed_bidder(restricted_id, accounts.charlie), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(foobar.place_bid(restricted_id), Ok(()));
+            assert_eq!(
+                foobar.get_auction(restricted_id).unwrap().highest_bidder,
+                Some(accounts.charlie)
+            );
+        }
+
+        /// We test that removing the highest bidder from the allowlist keeps
+        /// their bid but stops them from bidding again.
+        #[ink::test]
+        fn remove_allowed_bidder_keeps_bid() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_auction(String::from("private"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            foobar.add_allowed_bidder(auction_id, accounts.bob).unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10);
+            foobar.place_bid(auction_id).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.remove_allowed_bidder(auction_id, accounts.bob), Ok(()));
+            assert!(!foobar.is_allowed_bidder(auction_id, accounts.bob));
+            let auction = foobar.get_auction(auction_id).unwrap();
+            assert_eq!(auction.highest_bidder, Some(accounts.bob));
+            assert_eq!(auction.highest_bid, 10);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(20);
+            assert_eq!(foobar.place_bid(auction_id), Err(Error::NotAllowed));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.begin_ending_period(auction_id).unwrap();
+            foobar.end_auction(auction_id).unwrap();
+            assert_eq!(
+                foobar.add_allowed_bidder(auction_id, accounts.bob),
+                Err(Error::AuctionNotOpen)
+            );
+        }
+
+        /// We test that finalizing splits the winning bid between the seller
+        /// and the protocol fee, rounding the fee down.
+        #[ink::test]
+        fn protocol_fee_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new_with_fee(false, 250).unwrap();
+            assert_eq!(foobar.fee_bps(), 250);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            let even = foobar
+                .create_auction(String::from("even"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            let uneven = foobar
+                .create_auction(String::from("uneven"), Hash::from([0x08; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.start_auction(even).unwrap();
+            foobar.start_auction(uneven).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10_000);
+            foobar.place_bid(even).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(999);
+            foobar.place_bid(uneven).unwrap();
+
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 10_999);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(60_000);
+            let before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.django,
+            )
+            .unwrap();
+            foobar.finalize_auction(even).unwrap();
+            assert_eq!(foobar.collected_fees(), 250);
+            // 999 * 2.5% = 24.975, so the fee is 24 and the seller gets 975.
+            foobar.finalize_auction(uneven).unwrap();
+            assert_eq!(foobar.collected_fees(), 274);
+            let after = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.django,
+            )
+            .unwrap();
+            assert_eq!(after - before, 9_750 + 975);
+        }
+
+        /// We test that an auction keeps the fee in effect at its creation.
+        #[ink::test]
+        fn protocol_fee_is_snapshotted() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new_with_fee(false, 1_000).unwrap();
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            assert_eq!(foobar.set_fee_bps(MAX_FEE_BPS + 1), Err(Error::FeeTooHigh));
+            assert_eq!(foobar.set_fee_bps(0), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.set_fee_bps(500), Err(Error::NotOwner));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(105);
+            foobar.place_bid(auction_id).unwrap();
+
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 105);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(60_000);
+            foobar.finalize_auction(auction_id).unwrap();
+            assert_eq!(foobar.collected_fees(), 10);
+            assert!(matches!(
+                Foobar::new_with_fee(false, MAX_FEE_BPS + 1),
+                Err(Error::FeeTooHigh)
+            ));
+        }
+
+        /// We test that only the owner can withdraw the accrued fees.
+        #[ink::test]
+        fn withdraw_fees_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new_with_fee(false, 500).unwrap();
+            assert_eq!(foobar.withdraw_fees(Some(accounts.eve), 0), Err(Error::NothingToWithdraw));
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(200);
+            foobar.place_bid(auction_id).unwrap();
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 200);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(60_000);
+            foobar.finalize_auction(auction_id).unwrap();
+            assert_eq!(foobar.withdraw_fees(Some(accounts.bob), 0), Err(Error::NotOwner));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.eve,
+            )
+            .unwrap();
+            assert_eq!(foobar.withdraw_fees(Some(accounts.eve), 0), Ok(()));
+            let after = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.eve,
+            )
+            .unwrap();
+            assert_eq!(after - before, 10);
+            assert_eq!(foobar.collected_fees(), 0);
+            let event = ink::env::test::recorded_events().last().unwrap();
+            match decode_event(&event) {
+                Event::FeesWithdrawn(FeesWithdrawn { to, amount }) => {
+                    assert_eq!(to, accounts.eve);
+                    assert_eq!(amount, 10);
+                }
+                _ => panic!("expected a FeesWithdrawn event"),
+            }
+            assert_eq!(foobar.withdraw_fees(Some(accounts.eve), 0), Err(Error::NothingToWithdraw));
+        }
+
+        /// We test that `status` follows every lifecycle transition.
+        #[ink::test]
+        fn status_works() {
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.status(0), None);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            let cancelled_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x08; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            assert_eq!(foobar.status(auction_id), Some(Status::NotStarted));
+
+            foobar.start_auction(auction_id).unwrap();
+            assert_eq!(foobar.status(auction_id), Some(Status::OpeningPeriod));
+            foobar.begin_ending_period(auction_id).unwrap();
+            assert_eq!(foobar.status(auction_id), Some(Status::EndingPeriod));
+            foobar.end_auction(auction_id).unwrap();
+            assert_eq!(foobar.status(auction_id), Some(Status::Ended));
+            foobar.finalize_auction(auction_id).unwrap();
+            assert_eq!(foobar.status(auction_id), Some(Status::Ended));
+
+            foobar.cancel_auction(cancelled_id).unwrap();
+            assert_eq!(foobar.status(cancelled_id), Some(Status::Cancelled));
+        }
+
+        /// We test that a bid at the buy-now price ends the auction at once and
+        /// anyone can finalize it.
+        #[ink::test]
+        fn buy_now_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            assert_eq!(
+                foobar.create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 50, 60_000, Some(49)),
+                Err(Error::InvalidBuyNowPrice)
+            );
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 50, 60_000, Some(100))
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(100);
+            assert_eq!(foobar.place_bid(auction_id), Ok(()));
+            let auction = foobar.get_auction(auction_id).unwrap();
+            assert_eq!(auction.status, Status::Ended);
+            assert_eq!(auction.highest_bidder, Some(accounts.bob));
+            assert_eq!(auction.highest_bid, 100);
+            assert_eq!(foobar.pending_refund(accounts.bob), 0);
+            let event = ink::env::test::recorded_events().last().unwrap();
+            match decode_event(&event) {
+                Event::BoughtNow(BoughtNow { id, buyer, price }) => {
+                    assert_eq!(id, auction_id);
+                    assert_eq!(buyer, accounts.bob);
+                    assert_eq!(price, 100);
+                }
+                _ => panic!("expected a BoughtNow event"),
+            }
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(200);
+            assert_eq!(foobar.place_bid(auction_id), Err(Error::AuctionNotOpen));
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 100);
+            assert_eq!(foobar.finalize_auction(auction_id), Ok(()));
+            assert!(foobar.get_auction(auction_id).unwrap().finalized);
+        }
+
+        /// We test that buying now refunds the outbid bidder and credits the
+        /// buyer's overpayment.
+        #[ink::test]
+        fn buy_now_refunds_overpayment() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 10, 0, 60_000, Some(100))
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(95);
+            foobar.place_bid(auction_id).unwrap();
+            assert_eq!(foobar.status(auction_id), Some(Status::OpeningPeriod));
+
+            // Buying now skips the minimum increment.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(130);
+            assert_eq!(foobar.place_bid(auction_id), Ok(()));
+            assert_eq!(foobar.pending_refund(accounts.bob), 95);
+            assert_eq!(foobar.pending_refund(accounts.charlie), 30);
+            let auction = foobar.get_auction(auction_id).unwrap();
+            assert_eq!(auction.status, Status::Ended);
+            assert_eq!(auction.highest_bid, 100);
+            assert_eq!(auction.highest_bidder, Some(accounts.charlie));
+            assert_eq!(foobar.bids_of(auction_id, accounts.charlie)[0].0, 100);
+        }
+
+        /// We test that refunds from several auctions outbid in the same block
+        /// add up and are withdrawn at once.
+        #[ink::test]
+        fn withdraw_all_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            let first = foobar
+                .create_auction(String::from("first"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            let second = foobar
+                .create_auction(String::from("second"), Hash::from([0x08; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            foobar.start_auction(first).unwrap();
+            foobar.start_auction(second).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.withdraw_all(), Err(Error::NothingToWithdraw));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(30);
+            foobar.place_bid(first).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(45);
+            foobar.place_bid(second).unwrap();
+
+            // Charlie outbids Bob on both auctions without the block advancing.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(40);
+            foobar.place_bid(first).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(50);
+            foobar.place_bid(second).unwrap();
+            assert_eq!(foobar.refundable_of(accounts.bob), 75);
+            assert_eq!(foobar.refundable_of(accounts.charlie), 0);
+
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 165);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            let before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.bob,
+            )
+            .unwrap();
+            assert_eq!(foobar.withdraw_all(), Ok(75));
+            let after = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.bob,
+            )
+            .unwrap();
+            assert_eq!(after - before, 75);
+            assert_eq!(foobar.refundable_of(accounts.bob), 0);
+            assert_eq!(foobar.withdraw_all(), Err(Error::NothingToWithdraw));
+        }
+
+        /// We test that only expired, unsettled auctions are finalized in a
+        /// batch and that the batch can be resumed.
+        #[ink::test]
+        fn finalize_expired_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            let create = |foobar: &mut Foobar, duration_ms| {
+                foobar
+                    .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, duration_ms, None)
+                    .unwrap()
+            };
+            let expired_with_bid = create(&mut foobar, 1_000);
+            let already_finalized = create(&mut foobar, 1_000);
+            let live = create(&mut foobar, 60_000);
+            let cancelled = create(&mut foobar, 1_000);
+            let expired_unstarted = create(&mut foobar, 1_000);
+            let expired_started = create(&mut foobar, 1_000);
+            for auction_id in [expired_with_bid, already_finalized, live, expired_started] {
+                foobar.start_auction(auction_id).unwrap();
+            }
+            foobar.cancel_auction(cancelled).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10);
+            foobar.place_bid(expired_with_bid).unwrap();
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 10);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            foobar.finalize_auction(already_finalized).unwrap();
+            assert_eq!(foobar.expired_cursor(), 0);
+            // Every id looked at counts against `max`, settled or not.
+            assert_eq!(foobar.finalize_expired(2), Ok(1));
+            assert!(foobar.get_auction(expired_with_bid).unwrap().finalized);
+            assert!(!foobar.get_auction(expired_unstarted).unwrap().finalized);
+            assert_eq!(foobar.expired_cursor(), live);
+
+            // The live auction holds the cursor back, so a window that ends
+            // before the next expired auction settles nothing.
+            assert_eq!(foobar.finalize_expired(2), Ok(0));
+            assert_eq!(foobar.expired_cursor(), live);
+            assert_eq!(foobar.finalize_expired(4), Ok(2));
+            assert!(foobar.get_auction(expired_unstarted).unwrap().finalized);
+            assert!(foobar.get_auction(expired_started).unwrap().finalized);
+            assert!(!foobar.get_auction(live).unwrap().finalized);
+            assert_eq!(foobar.get_auction(cancelled).unwrap().status, Status::Cancelled);
+            assert_eq!(foobar.finalize_expired(4), Ok(0));
+            let event = ink::env::test::recorded_events().last().unwrap();
+            match decode_event(&event) {
+                Event::ExpiredFinalized(ExpiredFinalized { settled, failed }) => {
+                    assert_eq!(settled, 0);
+                    assert_eq!(failed, 0);
+                }
+                _ => panic!("expected an ExpiredFinalized event"),
+            }
+
+            // Once the live auction is settled, the cursor passes every
+            // auction and later calls scan nothing.
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(60_000);
+            assert_eq!(foobar.finalize_expired(1), Ok(1));
+            assert_eq!(foobar.expired_cursor(), cancelled);
+            assert_eq!(foobar.finalize_expired(3), Ok(0));
+            assert_eq!(foobar.expired_cursor(), foobar.auction_count());
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let late = create(&mut foobar, 1_000);
+            foobar.start_auction(late).unwrap();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(61_000);
+            assert_eq!(foobar.finalize_expired(2), Ok(1));
+            assert_eq!(foobar.expired_cursor(), late + 1);
+        }
+
+        /// Creates and starts a second-price English auction with the given
+        /// reserve price, sold by the default caller.
+        fn start_second_price_auction(foobar: &mut Foobar, reserve_price: Balance) -> u32 {
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, reserve_price, 60_000, None)
+                .unwrap();
+            foobar.set_settlement(auction_id, SettlementKind::SecondPrice).unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            auction_id
+        }
+
+        /// Places a bid of `amount` on `auction_id` as `bidder`.
+        fn bid_as(foobar: &mut Foobar, auction_id: u32, bidder: AccountId, amount: Balance) {
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(bidder);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(amount);
+            foobar.place_bid(auction_id).unwrap();
+        }
+
+        /// We test that the winner of a second-price auction pays the
+        /// runner-up's bid and gets the difference credited back.
+        #[ink::test]
+        fn second_price_settlement_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            let auction_id = start_second_price_auction(&mut foobar, 0);
+            bid_as(&mut foobar, auction_id, accounts.bob, 10);
+            bid_as(&mut foobar, auction_id, accounts.charlie, 25);
+            bid_as(&mut foobar, auction_id, accounts.bob, 30);
+            bid_as(&mut foobar, auction_id, accounts.charlie, 40);
+            assert_eq!(foobar.get_auction(auction_id).unwrap().second_bid, 30);
+
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 105);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(60_000);
+            let before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.alice,
+            )
+            .unwrap();
+            foobar.finalize_auction(auction_id).unwrap();
+            let after = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.alice,
+            )
+            .unwrap();
+            assert_eq!(after - before, 30);
+            assert_eq!(foobar.refundable_of(accounts.bob), 10 + 30);
+            assert_eq!(foobar.refundable_of(accounts.charlie), 25 + 10);
+            let finalized = ink::env::test::recorded_events()
+                .find_map(|event| match decode_event(&event) {
+                    Event::AuctionFinalized(finalized) => Some(finalized),
+                    _ => None,
+                })
+                .expect("expected an AuctionFinalized event");
+            assert_eq!(finalized.winner, Some(accounts.charlie));
+            assert_eq!(finalized.amount, 30);
+        }
+
+        /// We test that raising one's own bid keeps the runner-up's bid as
+        /// the second price.
+        #[ink::test]
+        fn second_price_ignores_own_raises() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            let auction_id = start_second_price_auction(&mut foobar, 0);
+            bid_as(&mut foobar, auction_id, accounts.bob, 10);
+            bid_as(&mut foobar, auction_id, accounts.charlie, 20);
+            bid_as(&mut foobar, auction_id, accounts.charlie, 35);
+            let auction = foobar.get_auction(auction_id).unwrap();
+            assert_eq!(auction.highest_bid, 35);
+            assert_eq!(auction.second_bid, 10);
+
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 65);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(60_000);
+            foobar.finalize_auction(auction_id).unwrap();
+            assert_eq!(foobar.refundable_of(accounts.bob), 10);
+            assert_eq!(foobar.refundable_of(accounts.charlie), 20 + 25);
+        }
+
+        /// We test that a single bid settles at the reserve price, or at the
+        /// bid itself without a reserve.
+        #[ink::test]
+        fn second_price_single_bid_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            let with_reserve = start_second_price_auction(&mut foobar, 15);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let without_reserve = start_second_price_auction(&mut foobar, 0);
+            bid_as(&mut foobar, with_reserve, accounts.bob, 40);
+            bid_as(&mut foobar, with_reserve, accounts.bob, 50);
+            bid_as(&mut foobar, without_reserve, accounts.charlie, 40);
+
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 90);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(60_000);
+            foobar.finalize_auction(with_reserve).unwrap();
+            assert_eq!(foobar.refundable_of(accounts.bob), 40 + 35);
+            foobar.finalize_auction(without_reserve).unwrap();
+            assert_eq!(foobar.refundable_of(accounts.charlie), 0);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let dutch = foobar
+                .create_dutch_auction(String::from("lot"), Hash::from([0x07; 32]), 100, 20, 1_000)
+                .unwrap();
+            assert_eq!(
+                foobar.set_settlement(dutch, SettlementKind::SecondPrice),
+                Err(Error::WrongAuctionKind)
+            );
+        }
+
+        /// We test that a second-price auction without bids ends without a
+        /// winner instead of clearing at the reserve price.
+        #[ink::test]
+        fn second_price_without_bids_has_no_winner() {
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            let auction_id = start_second_price_auction(&mut foobar, 15);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(60_000);
+            assert_eq!(foobar.finalize_auction(auction_id), Ok(()));
+
+            let finalized = ink::env::test::recorded_events()
+                .filter_map(|event| match decode_event(&event) {
+                    Event::AuctionFinalized(AuctionFinalized { id, winner, amount }) => {
+                        Some((id, winner, amount))
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(finalized, vec![(auction_id, None, 0)]);
+            assert_eq!(foobar.collected_fees(), 0);
+        }
+
+        /// We test that a transferred auction pays out to the new seller.
+        #[ink::test]
+        fn transfer_auction_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.start_auction(auction_id).unwrap();
+            bid_as(&mut foobar, auction_id, accounts.bob, 10);
+
+            // Only the seller may hand the auction over, not even the owner.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.transfer_auction(auction_id, accounts.eve), Err(Error::NotSeller));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(foobar.transfer_auction(auction_id, accounts.eve), Ok(()));
+            assert_eq!(foobar.get_auction(auction_id).unwrap().seller, accounts.eve);
+            let event = ink::env::test::recorded_events().last().unwrap();
+            assert_eq!(
+                event.topics,
+                vec![
+                    topic_of(b"", b"Foobar::AuctionTransferred"),
+                    topic_of(b"Foobar::AuctionTransferred::id", &auction_id),
+                    topic_of(b"Foobar::AuctionTransferred::from", &accounts.django),
+                    topic_of(b"Foobar::AuctionTransferred::to", &accounts.eve),
+                ]
+            );
+            assert_eq!(foobar.transfer_auction(auction_id, accounts.django), Err(Error::NotSeller));
+
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 10);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(60_000);
+            let before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.eve,
+            )
+            .unwrap();
+            foobar.finalize_auction(auction_id).unwrap();
+            let after = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.eve,
+            )
+            .unwrap();
+            assert_eq!(after - before, 10);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(
+                foobar.transfer_auction(auction_id, accounts.django),
+                Err(Error::AlreadyFinalized)
+            );
+        }
+
+        /// We test that the message length is limited in bytes, not characters.
+        #[ink::test]
+        fn set_message_checks_length() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.get_message(), "");
+            assert_eq!(foobar.set_message("x".repeat(MAX_MESSAGE_LEN)), Ok(()));
+            assert_eq!(foobar.get_message(), "x".repeat(MAX_MESSAGE_LEN));
+            assert_eq!(
+                foobar.set_message("x".repeat(MAX_MESSAGE_LEN + 1)),
+                Err(Error::MessageTooLong)
+            );
+
+            // "é" takes two bytes, so 129 of them exceed the limit.
+            let accented = "é".repeat(MAX_MESSAGE_LEN / 2 + 1);
+            assert_eq!(accented.chars().count(), 129);
+            assert_eq!(foobar.set_message(accented), Err(Error::MessageTooLong));
+            assert_eq!(foobar.set_message("é".repeat(MAX_MESSAGE_LEN / 2)), Ok(()));
+            assert_eq!(foobar.get_message(), "é".repeat(MAX_MESSAGE_LEN / 2));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.set_message(String::from("hi")), Err(Error::NotOwner));
+        }
+
+        /// We test that `MessageChanged` carries the new message.
+        #[ink::test]
+        fn set_message_emits_event() {
+            let mut foobar = Foobar::new(false);
+            foobar.set_message(String::from("gm")).unwrap();
+            let event = ink::env::test::recorded_events().last().unwrap();
+            match decode_event(&event) {
+                Event::MessageChanged(MessageChanged { message }) => assert_eq!(message, "gm"),
+                _ => panic!("expected a MessageChanged event"),
+            }
+        }
+
+        /// We test pushing, reading and popping numbers.
+        #[ink::test]
+        fn numbers_work() {
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.pop_number(), Ok(None));
+            assert_eq!(foobar.number_at(0), None);
+            assert_eq!(foobar.push_number(7), Ok(()));
+            assert_eq!(foobar.push_number(9), Ok(()));
+            assert_eq!(foobar.numbers_len(), 2);
+            assert_eq!(foobar.number_at(1), Some(9));
+            assert_eq!(foobar.number_at(2), None);
+            assert_eq!(foobar.number_at(u32::MAX), None);
+
+            assert_eq!(foobar.pop_number(), Ok(Some(9)));
+            assert_eq!(foobar.pop_number(), Ok(Some(7)));
+            assert_eq!(foobar.pop_number(), Ok(None));
+            assert_eq!(foobar.numbers_len(), 0);
+        }
+
+        /// We test that the numbers can't change while the contract is paused.
+        #[ink::test]
+        fn numbers_reject_when_paused() {
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.push_number(7), Ok(()));
+            foobar.pause().unwrap();
+            assert_eq!(foobar.push_number(9), Err(Error::Paused));
+            assert_eq!(foobar.pop_number(), Err(Error::Paused));
+            assert_eq!(foobar.numbers_len(), 1);
+
+            foobar.unpause().unwrap();
+            assert_eq!(foobar.pop_number(), Ok(Some(7)));
+        }
+
+        /// We test that the number vector is capped at `MAX_NUMBERS`.
+        #[ink::test]
+        fn push_number_checks_capacity() {
+            let mut foobar = Foobar::new(false);
+            for number in 0..MAX_NUMBERS as u32 {
+                assert_eq!(foobar.push_number(number), Ok(()));
+            }
+            assert_eq!(foobar.push_number(0), Err(Error::VectorFull));
+            assert_eq!(foobar.numbers_len(), MAX_NUMBERS as u32);
+            assert_eq!(foobar.number_at(MAX_NUMBERS as u32 - 1), Some(MAX_NUMBERS as u32 - 1));
+
+            assert_eq!(foobar.pop_number(), Ok(Some(MAX_NUMBERS as u32 - 1)));
+            assert_eq!(foobar.push_number(0), Ok(()));
+        }
+
+        /// We test the statistics of an empty and a single-entry vector.
+        #[ink::test]
+        fn stats_works() {
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.stats(), None);
+            foobar.push_number(42).unwrap();
+            assert_eq!(
+                foobar.stats(),
+                Some(NumberStats {
+                    min: 42,
+                    max: 42,
+                    sum: 42,
+                    count: 1,
+                })
+            );
+            foobar.push_number(3).unwrap();
+            foobar.push_number(100).unwrap();
+            assert_eq!(
+                foobar.stats(),
+                Some(NumberStats {
+                    min: 3,
+                    max: 100,
+                    sum: 145,
+                    count: 3,
+                })
+            );
+        }
+
+        /// We test that summing a full vector of `u32::MAX` doesn't overflow.
+        #[ink::test]
+        fn stats_does_not_overflow() {
+            let mut foobar = Foobar::new(false);
+            foobar.push_number(u32::MAX - 1).unwrap();
+            for _ in 1..MAX_NUMBERS {
+                foobar.push_number(u32::MAX).unwrap();
+            }
+            assert_eq!(
+                foobar.stats(),
+                Some(NumberStats {
+                    min: u32::MAX - 1,
+                    max: u32::MAX,
+                    sum: u64::from(u32::MAX) * MAX_NUMBERS as u64 - 1,
+                    count: MAX_NUMBERS as u32,
+                })
+            );
+        }
+
+        /// We test that the numbers and the flip history are kept out of the
+        /// root storage cell.
+        #[ink::test]
+        fn collections_are_not_in_root_cell() {
+            use ink::storage::traits::Storable;
+
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.flip(), Ok(()));
+            let mut root = Vec::new();
+            foobar.encode(&mut root);
+            for number in 0..500 {
+                foobar.push_number(number).unwrap();
+            }
+            assert_eq!(foobar.flip(), Ok(()));
+            let mut grown = Vec::new();
+            foobar.encode(&mut grown);
+
+            // The second flip only updates fixed-size fields of the root cell.
+            assert_eq!(grown.len(), root.len());
+            assert_eq!(foobar.numbers_len(), 500);
+            assert_eq!(foobar.history().len(), 2);
+        }
+
+        /// We test that only the owner can change the beneficiary.
+        #[ink::test]
+        fn set_beneficiary_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.beneficiary(), accounts.alice);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.set_beneficiary(accounts.bob), Err(Error::NotOwner));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.set_beneficiary(accounts.charlie), Ok(()));
+            assert_eq!(foobar.beneficiary(), accounts.charlie);
+            let event = ink::env::test::recorded_events().last().unwrap();
+            assert_eq!(
+                event.topics,
+                vec![
+                    topic_of(b"", b"Foobar::BeneficiaryChanged"),
+                    topic_of(b"Foobar::BeneficiaryChanged::old", &accounts.alice),
+                    topic_of(b"Foobar::BeneficiaryChanged::new", &accounts.charlie),
+                ]
+            );
+        }
+
+        /// We test that fees are split with the treasury, which gets the
+        /// rounding remainder.
+        #[ink::test]
+        fn treasury_split_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new_with_treasury(false, accounts.django, 2_500).unwrap();
+            assert_eq!(foobar.treasury(), accounts.django);
+            assert_eq!(foobar.treasury_share_bps(), 2_500);
+
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(100);
+            foobar.paid_flip().unwrap();
+            assert_eq!((foobar.owner_fees(), foobar.treasury_fees()), (75, 25));
+            // 75% of 7 is 5.25, the 0.25 goes to the treasury.
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(7);
+            foobar.paid_flip().unwrap();
+            assert_eq!((foobar.owner_fees(), foobar.treasury_fees()), (80, 27));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(1);
+            foobar.paid_flip().unwrap();
+            assert_eq!((foobar.owner_fees(), foobar.treasury_fees()), (80, 28));
+            assert_eq!(foobar.collected_fees(), 108);
+
+            // Auction fees are split the same way.
+            let mut foobar = Foobar::new_with_treasury(false, accounts.django, 3_333).unwrap();
+            foobar.set_fee_bps(1_000).unwrap();
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            bid_as(&mut foobar, auction_id, accounts.bob, 1_010);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 1_010);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(60_000);
+            foobar.finalize_auction(auction_id).unwrap();
+            // A fee of 101, of which the owner gets 66.67% rounded down.
+            assert_eq!((foobar.owner_fees(), foobar.treasury_fees()), (67, 34));
+        }
+
+        /// We test who may withdraw which share of the fees.
+        #[ink::test]
+        fn withdraw_split_fees_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new_with_treasury(false, accounts.django, 5_000).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(9);
+            foobar.paid_flip().unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 9);
+
+            assert_eq!(foobar.withdraw_treasury_fees(), Err(Error::NotTreasury));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(foobar.withdraw_owner_fees(), Err(Error::NotOwner));
+            assert_eq!(foobar.set_treasury(accounts.django), Err(Error::NotOwner));
+            assert_eq!(foobar.withdraw_treasury_fees(), Ok(5));
+            assert_eq!(foobar.withdraw_treasury_fees(), Err(Error::NothingToWithdraw));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let alice_before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.alice,
+            )
+            .unwrap();
+            assert_eq!(foobar.withdraw_owner_fees(), Ok(4));
+            let alice_after = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.alice,
+            )
+            .unwrap();
+            assert_eq!(alice_after - alice_before, 4);
+            assert_eq!(foobar.collected_fees(), 0);
+
+            // A new treasury takes over the share accrued so far.
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(2);
+            foobar.paid_flip().unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 2);
+            assert_eq!(foobar.set_treasury(accounts.eve), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(foobar.withdraw_treasury_fees(), Err(Error::NotTreasury));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(foobar.withdraw_treasury_fees(), Ok(1));
+        }
+
+        /// We test that the owner's share goes to the beneficiary.
+        #[ink::test]
+        fn withdraw_owner_fees_pays_beneficiary() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new_with_treasury(false, accounts.django, 5_000).unwrap();
+            foobar.set_beneficiary(accounts.frank).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10);
+            foobar.paid_flip().unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 10);
+
+            let frank_before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.frank,
+            )
+            .unwrap();
+            assert_eq!(foobar.withdraw_owner_fees(), Ok(5));
+            let frank_after = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.frank,
+            )
+            .unwrap();
+            assert_eq!(frank_after - frank_before, 5);
+            assert_eq!(foobar.owner_fees(), 0);
+            assert_eq!(foobar.treasury_fees(), 5);
+        }
+
+        /// We test partial withdrawals and that a failed transfer keeps the fees.
+        #[ink::test]
+        fn withdraw_fees_partially_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // A fresh contract account without any balance can't pay out.
+            let contract = AccountId::from([0xfe; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new_with_flip_fee(false, 100, false);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(100);
+            foobar.paid_flip().unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            assert_eq!(foobar.withdraw_fees(Some(accounts.frank), 101), Err(Error::InsufficientFees));
+            assert_eq!(foobar.withdraw_fees(Some(accounts.frank), 40), Err(Error::TransferFailed));
+            assert_eq!(foobar.collected_fees(), 100);
+
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 100);
+            let frank_before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.frank,
+            )
+            .unwrap();
+            assert_eq!(foobar.withdraw_fees(Some(accounts.frank), 25), Ok(()));
+            assert_eq!(foobar.collected_fees(), 75);
+            let frank_after = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.frank,
+            )
+            .unwrap();
+            assert_eq!(frank_after - frank_before, 25);
+        }
+
+        /// We test that fees go to whoever is the beneficiary at withdrawal.
+        #[ink::test]
+        fn withdraw_fees_pays_current_beneficiary() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new_with_fee(false, 1_000).unwrap();
+            foobar.set_beneficiary(accounts.django).unwrap();
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            bid_as(&mut foobar, auction_id, accounts.bob, 100);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 100);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(60_000);
+            foobar.finalize_auction(auction_id).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.set_beneficiary(accounts.frank).unwrap();
+            let django_before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.django,
+            )
+            .unwrap();
+            let frank_before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.frank,
+            )
+            .unwrap();
+            assert_eq!(foobar.withdraw_fees(None, 0), Ok(()));
+            let django_after = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.django,
+            )
+            .unwrap();
+            let frank_after = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.frank,
+            )
+            .unwrap();
+            assert_eq!(django_after, django_before);
+            assert_eq!(frank_after - frank_before, 10);
+        }
+
+        /// We test that deposits and bids add up in `total_received`, which
+        /// keeps its value when funds are paid out.
+        #[ink::test]
+        fn total_received_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.total_received(), 0);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(25);
+            foobar.deposit().unwrap();
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            bid_as(&mut foobar, auction_id, accounts.bob, 10);
+            bid_as(&mut foobar, auction_id, accounts.charlie, 20);
+            assert_eq!(foobar.total_received(), 55);
+
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 55);
+            assert_eq!(foobar.contract_free_balance(), 55);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            foobar.withdraw_all().unwrap();
+            assert_eq!(foobar.contract_free_balance(), 45);
+            assert_eq!(foobar.total_received(), 55);
+        }
+
+        /// We test that deposits are credited to the depositing account.
+        #[ink::test]
+        fn deposit_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.deposit(), Err(Error::ZeroDeposit));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(30);
+            assert_eq!(foobar.deposit(), Ok(()));
+            assert_eq!(foobar.deposit(), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(15);
+            assert_eq!(foobar.deposit(), Ok(()));
+
+            assert_eq!(foobar.deposit_of(accounts.bob), 60);
+            assert_eq!(foobar.deposit_of(accounts.charlie), 15);
+            assert_eq!(foobar.deposit_of(accounts.alice), 0);
+            assert_eq!(foobar.total_deposits(), 75);
+
+            let deposited = ink::env::test::recorded_events()
+                .filter_map(|event| match decode_event(&event) {
+                    Event::Deposited(Deposited { from, amount }) => Some((from, amount)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                deposited,
+                vec![(accounts.bob, 30), (accounts.bob, 30), (accounts.charlie, 15)]
+            );
+        }
+
+        /// We test that deposits are rejected while the contract is paused.
+        #[ink::test]
+        fn deposit_rejects_when_paused() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            foobar.pause().unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(30);
+            assert_eq!(foobar.deposit(), Err(Error::Paused));
+            assert_eq!(foobar.deposit_of(accounts.bob), 0);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.unpause().unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.deposit(), Ok(()));
+            assert_eq!(foobar.deposit_of(accounts.bob), 30);
+        }
+
+        /// We test that accounts can withdraw up to their own deposit.
+        #[ink::test]
+        fn withdraw_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            let minimum = foobar.minimum_balance();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(100);
+            foobar.deposit().unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(50);
+            foobar.deposit().unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, minimum + 150);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.withdraw(101), Err(Error::InsufficientDeposit));
+            assert_eq!(foobar.withdraw(0), Err(Error::NothingToWithdraw));
+            let bob_before = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.bob,
+            )
+            .unwrap();
+            assert_eq!(foobar.withdraw(40), Ok(()));
+            assert_eq!(foobar.deposit_of(accounts.bob), 60);
+            assert_eq!(foobar.withdraw_entire_deposit(), Ok(60));
+            let bob_after = ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(
+                accounts.bob,
+            )
+            .unwrap();
+            assert_eq!(bob_after - bob_before, 100);
+            assert_eq!(foobar.deposit_of(accounts.bob), 0);
+            assert_eq!(foobar.withdraw_entire_deposit(), Err(Error::NothingToWithdraw));
+            assert_eq!(foobar.deposit_of(accounts.charlie), 50);
+            assert_eq!(foobar.total_deposits(), 50);
+            assert_eq!(foobar.contract_free_balance(), minimum + 50);
+        }
+
+        /// We test the balance views against the contract's own account.
+        #[ink::test]
+        fn contract_balance_works() {
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let foobar = Foobar::new(false);
+            let minimum = foobar.minimum_balance();
+            assert!(minimum > 0);
+
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, minimum + 25);
+            assert_eq!(foobar.contract_balance(), minimum + 25);
+            assert_eq!(foobar.contract_free_balance(), minimum + 25);
+            assert_eq!(foobar.spendable_balance(), 25);
+
+            // Nothing is spendable below the minimum balance.
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, minimum - 1);
+            assert_eq!(foobar.contract_balance(), minimum - 1);
+            assert_eq!(foobar.spendable_balance(), 0);
+        }
+
+        /// We test that bids stay in escrow until they are paid out.
+        #[ink::test]
+        fn escrowed_bids_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new_with_fee(false, 1_000).unwrap();
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            bid_as(&mut foobar, auction_id, accounts.bob, 40);
+            bid_as(&mut foobar, auction_id, accounts.charlie, 100);
+            assert_eq!(foobar.escrowed_bids(), 140);
+
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 140);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(60_000);
+            foobar.finalize_auction(auction_id).unwrap();
+            // The seller got 90 and 10 went to the fees, bob's bid is still held.
+            assert_eq!(foobar.escrowed_bids(), 40);
+            assert_eq!(foobar.collected_fees(), 10);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            foobar.withdraw_refund().unwrap();
+            assert_eq!(foobar.escrowed_bids(), 0);
+        }
+
+        /// We test that `runtime_transfer` encodes `Balances::transfer_keep_alive`
+        /// the way substrate-contracts-node expects.
+        #[cfg(feature = "call-runtime")]
+        #[ink::test]
+        fn runtime_transfer_call_encoding_is_pinned() {
+            let call = RuntimeCall::Balances(BalancesCall::TransferKeepAlive {
+                dest: MultiAddress::Id(AccountId::from([0x01; 32])),
+                value: 1_000,
+            });
+            let mut expected = vec![4, 3, 0];
+            expected.extend_from_slice(&[0x01; 32]);
+            // 1_000 as a compact integer in two-byte mode.
+            expected.extend_from_slice(&[0xa1, 0x0f]);
+            assert_eq!(call.encode(), expected);
+        }
+
+        /// We test that `runtime_transfer` is owner-only and limited to the
+        /// sweepable balance. The off-chain environment can't dispatch runtime
+        /// calls, so the transfer itself is covered by the e2e test.
+        #[cfg(feature = "call-runtime")]
+        #[ink::test]
+        fn runtime_transfer_checks() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            let minimum = foobar.minimum_balance();
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, minimum + 7);
+
+            assert_eq!(foobar.runtime_transfer(accounts.eve, 8), Err(Error::ExceedsSweepable));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.runtime_transfer(accounts.eve, 7), Err(Error::NotOwner));
+        }
+
+        /// We test that only value nobody has a claim on can be swept out.
+        #[ink::test]
+        fn transfer_out_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new_with_flip_fee(false, 5, true);
+            let minimum = foobar.minimum_balance();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(5);
+            foobar.paid_flip().unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(20);
+            foobar.deposit().unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            bid_as(&mut foobar, auction_id, accounts.charlie, 30);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+
+            // Fees, the deposit and the bid are all backed, nothing more.
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, minimum + 55);
+            assert_eq!(foobar.sweepable_balance(), 0);
+            assert_eq!(foobar.transfer_out(accounts.eve, 1), Err(Error::ExceedsSweepable));
+
+            // An unsolicited transfer of 7 can be swept.
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, minimum + 62);
+            assert_eq!(foobar.sweepable_balance(), 7);
+            assert_eq!(foobar.transfer_out(accounts.eve, 8), Err(Error::ExceedsSweepable));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.transfer_out(accounts.eve, 7), Err(Error::NotOwner));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.transfer_out(accounts.eve, 7), Ok(()));
+            assert_eq!(foobar.contract_balance(), minimum + 55);
+            assert_eq!(foobar.sweepable_balance(), 0);
+            let event = ink::env::test::recorded_events().last().unwrap();
+            match decode_event(&event) {
+                Event::SweptOut(SweptOut { to, amount }) => {
+                    assert_eq!(to, accounts.eve);
+                    assert_eq!(amount, 7);
+                }
+                _ => panic!("expected a SweptOut event"),
+            }
+        }
+
+        /// We test that value sent to an unknown selector is rejected unless
+        /// donations are accepted.
+        #[ink::test]
+        fn receive_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            assert!(!foobar.accept_donations());
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(30);
+            assert_eq!(foobar.receive(), Err(Error::DirectTransfersNotAccepted));
+            assert_eq!(foobar.set_accept_donations(true), Err(Error::NotOwner));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.set_accept_donations(true), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.receive(), Ok(()));
+            assert_eq!(foobar.donations(), 30);
+            let event = ink::env::test::recorded_events().last().unwrap();
+            match decode_event(&event) {
+                Event::DonationReceived(DonationReceived { donor, amount, total }) => {
+                    assert_eq!(donor, accounts.bob);
+                    assert_eq!(amount, 30);
+                    assert_eq!(total, 30);
+                }
+                _ => panic!("expected a DonationReceived event"),
+            }
+
+            // Donations can't be swept out, only withdrawn.
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                contract,
+                foobar.minimum_balance() + 30,
+            );
+            assert_eq!(foobar.sweepable_balance(), 0);
+            assert_eq!(foobar.withdraw_donations(Some(accounts.bob)), Err(Error::NotOwner));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.withdraw_donations(Some(accounts.eve)), Ok(30));
+            assert_eq!(foobar.donations(), 0);
+            match decode_event(&ink::env::test::recorded_events().last().unwrap()) {
+                Event::DonationsWithdrawn(DonationsWithdrawn { to, amount }) => {
+                    assert_eq!(to, accounts.eve);
+                    assert_eq!(amount, 30);
+                }
+                _ => panic!("expected a DonationsWithdrawn event"),
+            }
+            assert_eq!(foobar.withdraw_donations(None), Err(Error::NothingToWithdraw));
+
+            // Without a recipient, donations go to the beneficiary.
+            foobar.set_beneficiary(accounts.charlie).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(20);
+            foobar.receive().unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                contract,
+                foobar.minimum_balance() + 20,
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.withdraw_donations(None), Ok(20));
+            match decode_event(&ink::env::test::recorded_events().last().unwrap()) {
+                Event::DonationsWithdrawn(DonationsWithdrawn { to, .. }) => {
+                    assert_eq!(to, accounts.charlie)
+                }
+                _ => panic!("expected a DonationsWithdrawn event"),
+            }
+        }
+
+        /// We test that donations are rejected while the contract is paused.
+        #[ink::test]
+        fn receive_rejects_when_paused() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            foobar.set_accept_donations(true).unwrap();
+            foobar.pause().unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(30);
+            assert_eq!(foobar.receive(), Err(Error::Paused));
+            assert_eq!(foobar.donations(), 0);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.unpause().unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.receive(), Ok(()));
+            assert_eq!(foobar.donations(), 30);
+        }
+
+        /// We test that strict value checks reject value sent to `receive`,
+        /// even while donations are accepted.
+        #[ink::test]
+        fn strict_value_checks_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new_with_strict_value_checks(false, true);
+            assert!(foobar.strict_value_checks());
+            assert!(!Foobar::new(false).strict_value_checks());
+            assert_eq!(foobar.set_accept_donations(true), Ok(()));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(30);
+            assert_eq!(foobar.receive(), Err(Error::UnexpectedPayment));
+            assert_eq!(foobar.donations(), 0);
+            assert_eq!(foobar.donated_by(accounts.bob), 0);
+
+            // Calls without value still reach the fallback.
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            assert_eq!(foobar.receive(), Ok(()));
+
+            // Messages that expect value are not affected.
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(30);
+            assert_eq!(foobar.deposit(), Ok(()));
+            assert_eq!(foobar.deposit_of(accounts.bob), 30);
+        }
+
+        /// We test that `expected_value_of` tells which messages take value.
+        #[ink::test]
+        fn expected_value_of_works() {
+            let mut foobar = Foobar::new_with_flip_fee(false, 5, true);
+            assert_eq!(foobar.expected_value_of(ink::selector_bytes!("paid_flip")), 5);
+            assert_eq!(foobar.set_flip_fee(7), Ok(()));
+            assert_eq!(foobar.expected_value_of(ink::selector_bytes!("paid_flip")), 7);
+            for selector in [
+                ink::selector_bytes!("deposit"),
+                ink::selector_bytes!("place_bid"),
+                ink::selector_bytes!("commit_bid"),
+                ink::selector_bytes!("buy"),
+            ] {
+                assert_eq!(foobar.expected_value_of(selector), VALUE_DEPENDS_ON_CALL);
+            }
+            assert_eq!(foobar.expected_value_of(ink::selector_bytes!("flip")), 0);
+            assert_eq!(foobar.expected_value_of(ink::selector_bytes!("withdraw")), 0);
+            assert_eq!(foobar.expected_value_of([0xde, 0xad, 0xbe, 0xef]), 0);
+        }
+
+        /// We test that `top_donors` keeps the largest donors in order as
+        /// repeat donations move them up and newcomers push others out.
+        #[ink::test]
+        fn top_donors_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            foobar.set_accept_donations(true).unwrap();
+            let donate = |foobar: &mut Foobar, donor: AccountId, amount: Balance| {
+                ink::env::test::set_caller::<ink::env::DefaultEnvironment>(donor);
+                ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(amount);
+                foobar.receive().unwrap();
+            };
+            let frank = accounts.frank;
+            let ferdie = AccountId::from([0x07; 32]);
+            donate(&mut foobar, accounts.alice, 10);
+            donate(&mut foobar, accounts.bob, 20);
+            donate(&mut foobar, accounts.charlie, 30);
+            donate(&mut foobar, accounts.django, 40);
+            donate(&mut foobar, accounts.eve, 50);
+            // Alice moves from last to second place.
+            donate(&mut foobar, accounts.alice, 35);
+            assert_eq!(
+                foobar.top_donors(),
+                vec![
+                    (accounts.eve, 50),
+                    (accounts.alice, 45),
+                    (accounts.django, 40),
+                    (accounts.charlie, 30),
+                    (accounts.bob, 20),
+                ]
+            );
+            // Matching the smallest entry isn't enough to get on the list.
+            donate(&mut foobar, frank, 20);
+            assert_eq!(foobar.top_donors().len(), TOP_DONORS_SIZE);
+            assert!(!foobar.top_donors().iter().any(|(donor, _)| *donor == frank));
+            // Frank displaces Bob, then climbs to the top with a repeat donation.
+            donate(&mut foobar, frank, 5);
+            donate(&mut foobar, ferdie, 1);
+            donate(&mut foobar, frank, 30);
+            donate(&mut foobar, accounts.bob, 100);
+            assert_eq!(
+                foobar.top_donors(),
+                vec![
+                    (accounts.bob, 120),
+                    (frank, 55),
+                    (accounts.eve, 50),
+                    (accounts.alice, 45),
+                    (accounts.django, 40),
+                ]
+            );
+            assert_eq!(foobar.donated_by(accounts.charlie), 30);
+            assert_eq!(foobar.donated_by(ferdie), 1);
+            assert_eq!(foobar.donations(), 341);
+
+            let totals = ink::env::test::recorded_events()
+                .filter_map(|event| match decode_event(&event) {
+                    Event::DonationReceived(DonationReceived { donor, total, .. }) if donor == frank => Some(total),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(totals, vec![20, 25, 55]);
+        }
+
+        /// We test that the owner can terminate the contract and that its
+        /// balance goes to the beneficiary.
+        #[ink::test]
+        fn terminate_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 100);
+
+            let should_terminate = move || foobar.terminate(accounts.eve).unwrap();
+            ink::env::test::assert_contract_termination::<ink::env::DefaultEnvironment, _>(
+                should_terminate,
+                accounts.eve,
+                100,
+            );
+            let event = ink::env::test::recorded_events().last().unwrap();
+            match decode_event(&event) {
+                Event::Terminated(Terminated { beneficiary, balance }) => {
+                    assert_eq!(beneficiary, accounts.eve);
+                    assert_eq!(balance, 100);
+                }
+                _ => panic!("expected a Terminated event"),
+            }
+        }
+
+        /// We test that the contract can't be terminated by anyone but the
+        /// owner, nor while it holds deposits or a live auction.
+        #[ink::test]
+        fn terminate_checks_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.terminate(accounts.bob), Err(Error::NotOwner));
+
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10);
+            foobar.deposit().unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.terminate(accounts.alice), Err(Error::CannotTerminate));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                contract,
+                foobar.minimum_balance() + 10,
+            );
+            foobar.withdraw_entire_deposit().unwrap();
+
+            // An auction that hasn't started holds no bids yet.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            assert!(!foobar.has_live_auction());
+            foobar.start_auction(auction_id).unwrap();
+            assert_eq!(foobar.terminate(accounts.alice), Err(Error::CannotTerminate));
+            bid_as(&mut foobar, auction_id, accounts.bob, 10);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(60_000);
+            // Ended, but the bid is held until the auction is finalized.
+            assert_eq!(foobar.terminate(accounts.alice), Err(Error::CannotTerminate));
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 10);
+            foobar.finalize_auction(auction_id).unwrap();
+            assert!(!foobar.has_live_auction());
+        }
+
+        /// We test that a payout failing inside `finalize_expired` keeps the
+        /// auction counted as live.
+        #[ink::test]
+        fn failed_settlement_keeps_auction_live() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            // A fresh contract account without any balance can't pay out.
+            let contract = AccountId::from([0xfe; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 1_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            bid_as(&mut foobar, auction_id, accounts.bob, 10);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            assert_eq!(foobar.live_auctions, 1);
+
+            for _ in 0..3 {
+                assert_eq!(foobar.finalize_expired(5), Ok(0));
+                assert_eq!(foobar.live_auctions, 1);
+            }
+            assert!(!foobar.get_auction(auction_id).unwrap().finalized);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.terminate(accounts.alice), Err(Error::CannotTerminate));
+
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 10);
+            assert_eq!(foobar.finalize_expired(5), Ok(1));
+            assert_eq!(foobar.live_auctions, 0);
+        }
+
+        /// We test that the contract can't be terminated while it owes
+        /// refunds, holds sealed-bid deposits or treasury fees.
+        #[ink::test]
+        fn terminate_waits_for_escrow_and_treasury_fees() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new_with_treasury(false, accounts.django, 5_000).unwrap();
+
+            // An outbid bidder's refund outlives the auction.
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            bid_as(&mut foobar, auction_id, accounts.bob, 10);
+            bid_as(&mut foobar, auction_id, accounts.charlie, 20);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(60_000);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 30);
+            foobar.finalize_auction(auction_id).unwrap();
+            assert!(!foobar.has_live_auction());
+            assert_eq!(foobar.escrowed_bids(), 10);
+            assert_eq!(foobar.terminate(accounts.alice), Err(Error::CannotTerminate));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.withdraw_refund(), Ok(10));
+
+            // Treasury fees have to be withdrawn first.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(2);
+            foobar.paid_flip().unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 2);
+            assert_eq!(foobar.terminate(accounts.alice), Err(Error::CannotTerminate));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(foobar.withdraw_treasury_fees(), Ok(1));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.escrowed_bids(), 0);
+            assert_eq!(foobar.treasury_fees(), 0);
+            let should_terminate = move || foobar.terminate(accounts.alice).unwrap();
+            ink::env::test::assert_contract_termination::<ink::env::DefaultEnvironment, _>(
+                should_terminate,
+                accounts.alice,
+                1,
+            );
+        }
+
+        /// We test that a withdrawal leaving the contract below its minimum
+        /// balance fails without touching the deposit.
+        #[ink::test]
+        fn withdraw_keeps_minimum_balance() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            let minimum = foobar.minimum_balance();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(100);
+            foobar.deposit().unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, minimum + 60);
+
+            assert_eq!(foobar.withdraw(61), Err(Error::TransferFailed));
+            assert_eq!(foobar.withdraw_entire_deposit(), Err(Error::TransferFailed));
+            assert_eq!(foobar.deposit_of(accounts.bob), 100);
+            assert_eq!(foobar.total_deposits(), 100);
+            assert_eq!(foobar.withdraw(60), Ok(()));
+            assert_eq!(foobar.contract_free_balance(), minimum);
+        }
+
+        /// We test that `total_received` saturates instead of overflowing.
+        #[ink::test]
+        fn total_received_saturates() {
+            let mut foobar = Foobar::new(false);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(Balance::MAX);
+            foobar.deposit().unwrap();
+            foobar.deposit().unwrap();
+            assert_eq!(foobar.total_received(), Balance::MAX);
+        }
+
+        /// We test verifying documents of various sizes against the reference hash.
+        #[ink::test]
+        fn verify_document_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            let document = vec![0xab; 8 * 1024];
+            let mut expected = [0u8; 32];
+            ink::env::hash_bytes::<Blake2x256>(&document, &mut expected);
+            assert_eq!(foobar.hash_of(document.clone()), Hash::from(expected));
+            assert!(!foobar.verify_document(document.clone()));
+
+            assert_eq!(foobar.set_document_hash(Hash::from(expected)), Ok(()));
+            assert!(foobar.verify_document(document.clone()));
+            let mut tampered = document;
+            tampered[4096] ^= 1;
+            assert!(!foobar.verify_document(tampered));
+            assert!(!foobar.verify_document(Vec::new()));
+            let event = ink::env::test::recorded_events().last().unwrap();
+            assert_eq!(
+                event.topics,
+                vec![
+                    topic_of(b"", b"Foobar::DocumentHashSet"),
+                    topic_of(b"Foobar::DocumentHashSet::hash", &Hash::from(expected)),
+                ]
+            );
+
+            let empty = foobar.hash_of(Vec::new());
+            assert_eq!(foobar.set_document_hash(empty), Ok(()));
+            assert!(foobar.verify_document(Vec::new()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.set_document_hash(Hash::from(expected)), Err(Error::NotOwner));
+        }
+
+        /// We test `hash_blake2` against known blake2b-256 vectors.
+        #[ink::test]
+        fn hash_blake2_works() {
+            let foobar = Foobar::new(false);
+            let empty = Hash::from([
+                0x0e, 0x57, 0x51, 0xc0, 0x26, 0xe5, 0x43, 0xb2,
+                0xe8, 0xab, 0x2e, 0xb0, 0x60, 0x99, 0xda, 0xa1,
+                0xd1, 0xe5, 0xdf, 0x47, 0x77, 0x8f, 0x77, 0x87,
+                0xfa, 0xab, 0x45, 0xcd, 0xf1, 0x2f, 0xe3, 0xa8,
+            ]);
+            let abc = Hash::from([
+                0xbd, 0xdd, 0x81, 0x3c, 0x63, 0x42, 0x39, 0x72,
+                0x31, 0x71, 0xef, 0x3f, 0xee, 0x98, 0x57, 0x9b,
+                0x94, 0x96, 0x4e, 0x3b, 0xb1, 0xcb, 0x3e, 0x42,
+                0x72, 0x62, 0xc8, 0xc0, 0x68, 0xd5, 0x23, 0x19,
+            ]);
+            let buffer: Vec<u8> = (0..4096).map(|i| (i % 251) as u8).collect();
+            let large = Hash::from([
+                0x11, 0xc2, 0x94, 0xa1, 0x1d, 0xc6, 0x7e, 0x3d,
+                0xdb, 0x25, 0xf8, 0xc0, 0x6c, 0xca, 0x27, 0x21,
+                0xe5, 0x8d, 0x2a, 0x04, 0x42, 0x43, 0xab, 0xea,
+                0x6c, 0x70, 0x63, 0xfd, 0x17, 0xd5, 0x89, 0xe5,
+            ]);
+            assert_eq!(foobar.hash_blake2(Vec::new()), Ok(empty));
+            assert_eq!(foobar.hash_blake2(b"abc".to_vec()), Ok(abc));
+            assert_eq!(foobar.hash_blake2(buffer.clone()), Ok(large));
+            assert_eq!(foobar.hash_of(buffer), large);
+        }
+
+        /// We test `hash_keccak` against known keccak-256 vectors.
+        #[ink::test]
+        fn hash_keccak_works() {
+            let foobar = Foobar::new(false);
+            let empty = [
+                0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c,
+                0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7, 0x03, 0xc0,
+                0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b,
+                0x7b, 0xfa, 0xd8, 0x04, 0x5d, 0x85, 0xa4, 0x70,
+            ];
+            let abc = [
+                0x4e, 0x03, 0x65, 0x7a, 0xea, 0x45, 0xa9, 0x4f,
+                0xc7, 0xd4, 0x7b, 0xa8, 0x26, 0xc8, 0xd6, 0x67,
+                0xc0, 0xd1, 0xe6, 0xe3, 0x3a, 0x64, 0xa0, 0x36,
+                0xec, 0x44, 0xf5, 0x8f, 0xa1, 0x2d, 0x6c, 0x45,
+            ];
+            let buffer: Vec<u8> = (0..4096).map(|i| (i % 251) as u8).collect();
+            let large = [
+                0x15, 0x4a, 0x93, 0x2b, 0xb0, 0x8a, 0x71, 0x88,
+                0x80, 0x80, 0x79, 0x03, 0xc8, 0x72, 0x4a, 0x2e,
+                0x29, 0x32, 0x71, 0xf2, 0x49, 0xbd, 0x8d, 0x70,
+                0x17, 0xeb, 0x0e, 0x9b, 0x45, 0x6a, 0x21, 0x8d,
+            ];
+            assert_eq!(foobar.hash_keccak(Vec::new()), Ok(empty));
+            assert_eq!(foobar.hash_keccak(b"abc".to_vec()), Ok(abc));
+            assert_eq!(foobar.hash_keccak(buffer), Ok(large));
+        }
+
+        /// We test that hashing more than `MAX_HASH_INPUT_LEN` bytes fails.
+        #[ink::test]
+        fn hash_rejects_large_input() {
+            let foobar = Foobar::new(false);
+            let limit = vec![0u8; MAX_HASH_INPUT_LEN];
+            assert!(foobar.hash_blake2(limit.clone()).is_ok());
+            assert!(foobar.hash_keccak(limit).is_ok());
+            let too_large = vec![0u8; MAX_HASH_INPUT_LEN + 1];
+            assert_eq!(foobar.hash_blake2(too_large.clone()), Err(Error::InputTooLarge));
+            assert_eq!(foobar.hash_keccak(too_large), Err(Error::InputTooLarge));
+        }
+
+        /// We test that a populated `MyStruct` round-trips through storage.
+        #[ink::test]
+        fn my_struct_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            let initial = foobar.get_my_struct();
+            assert_eq!(initial.my_account, accounts.alice);
+            assert!(initial.my_string.is_empty());
+            assert!(initial.my_vector.is_empty());
+            let my_struct = MyStruct {
+                value: true,
+                my_string: String::from("hello"),
+                my_vector: vec![1, 2, 3],
+                my_account: accounts.bob,
+                my_balance: 1_000,
+                my_hash: Hash::from([0x07; 32]),
+            };
+            assert_eq!(foobar.set_my_struct(my_struct.clone()), Ok(()));
+            assert_eq!(foobar.get_my_struct(), my_struct);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.set_my_struct(initial), Err(Error::NotOwner));
+        }
+
+        /// We test the length bounds of `set_my_struct`.
+        #[ink::test]
+        fn set_my_struct_checks_lengths() {
+            let mut foobar = Foobar::new(false);
+            let initial = foobar.get_my_struct();
+            let too_long = MyStruct {
+                my_string: "x".repeat(MAX_MESSAGE_LEN + 1),
+                ..initial.clone()
+            };
+            assert_eq!(foobar.set_my_struct(too_long), Err(Error::MessageTooLong));
+            let too_many = MyStruct {
+                my_vector: vec![0; MAX_NUMBERS + 1],
+                ..initial.clone()
+            };
+            assert_eq!(foobar.set_my_struct(too_many), Err(Error::VectorFull));
+            let at_bounds = MyStruct {
+                my_string: "x".repeat(MAX_MESSAGE_LEN),
+                my_vector: vec![0; MAX_NUMBERS],
+                ..initial
+            };
+            assert_eq!(foobar.set_my_struct(at_bounds.clone()), Ok(()));
+            assert_eq!(foobar.get_my_struct(), at_bounds);
+        }
+
+        /// We test that notes are kept per account and can be cleared.
+        #[ink::test]
+        fn notes_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.note_of(accounts.alice), None);
+            assert_eq!(foobar.set_my_note(String::from("alice here")), Ok(()));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.set_my_note(String::from("bob here")), Ok(()));
+            let event = ink::env::test::recorded_events().last().unwrap();
+            assert_eq!(
+                event.topics,
+                vec![
+                    topic_of(b"", b"Foobar::NoteChanged"),
+                    topic_of(b"Foobar::NoteChanged::account", &accounts.bob),
+                ]
+            );
+            assert_eq!(foobar.note_of(accounts.alice), Some(String::from("alice here")));
+            assert_eq!(foobar.note_of(accounts.bob), Some(String::from("bob here")));
+
+            foobar.clear_my_note().unwrap();
+            assert_eq!(foobar.note_of(accounts.bob), None);
+            assert_eq!(foobar.note_of(accounts.alice), Some(String::from("alice here")));
+            match decode_event(&ink::env::test::recorded_events().last().unwrap()) {
+                Event::NoteChanged(NoteChanged { account, note }) => {
+                    assert_eq!(account, accounts.bob);
+                    assert_eq!(note, None);
+                }
+                _ => panic!("expected a NoteChanged event"),
+            }
+        }
+
+        /// We test that notes can't be changed while the contract is paused.
+        #[ink::test]
+        fn notes_reject_when_paused() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.set_my_note(String::from("alice here")), Ok(()));
+            foobar.pause().unwrap();
+            assert_eq!(foobar.set_my_note(String::from("changed")), Err(Error::Paused));
+            assert_eq!(foobar.clear_my_note(), Err(Error::Paused));
+            assert_eq!(foobar.note_of(accounts.alice), Some(String::from("alice here")));
+
+            foobar.unpause().unwrap();
+            assert_eq!(foobar.set_my_note(String::from("changed")), Ok(()));
+            assert_eq!(foobar.note_of(accounts.alice), Some(String::from("changed")));
+        }
+
+        /// We test that notes are limited to `MAX_NOTE_LEN` bytes.
+        #[ink::test]
+        fn set_my_note_checks_length() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.set_my_note("x".repeat(MAX_NOTE_LEN)), Ok(()));
+            assert_eq!(
+                foobar.set_my_note("x".repeat(MAX_NOTE_LEN + 1)),
+                Err(Error::NoteTooLong)
+            );
+            assert_eq!(foobar.note_of(accounts.alice), Some("x".repeat(MAX_NOTE_LEN)));
+        }
+
+        /// We test that the deployer owns the contract and that admin messages
+        /// are gated on it while `flip` stays open.
+        #[ink::test]
+        fn owner_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.owner(), accounts.bob);
+            assert_eq!(Foobar::new_with_cooldown(false, 1).owner(), accounts.bob);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.pause(), Err(Error::NotOwner));
+            assert_eq!(foobar.set_fee_bps(10), Err(Error::NotOwner));
+            assert_eq!(foobar.reset_value(true), Err(Error::NotOwner));
+            assert_eq!(foobar.flip(), Ok(()));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.set_fee_bps(10), Ok(()));
+            assert_eq!(foobar.pause(), Ok(()));
+        }
+
+        /// We test that ownership moves to the new owner at once.
+        #[ink::test]
+        fn transfer_ownership_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.transfer_ownership(accounts.bob), Err(Error::NotOwner));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(
+                foobar.transfer_ownership(AccountId::from([0x00; 32])),
+                Err(Error::InvalidOwner)
+            );
+            assert_eq!(foobar.transfer_ownership(accounts.bob), Ok(()));
+            assert_eq!(foobar.owner(), accounts.bob);
+            let event = ink::env::test::recorded_events().last().unwrap();
+            assert_eq!(
+                event.topics,
+                vec![
+                    topic_of(b"", b"Foobar::OwnershipTransferred"),
+                    topic_of(b"Foobar::OwnershipTransferred::previous", &accounts.alice),
+                    topic_of(b"Foobar::OwnershipTransferred::new", &accounts.bob),
+                ]
+            );
+
+            // The previous owner loses its privileges right away.
+            assert_eq!(foobar.pause(), Err(Error::NotOwner));
+            assert_eq!(foobar.transfer_ownership(accounts.alice), Err(Error::NotOwner));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.pause(), Ok(()));
+        }
+
+        /// We test the propose/accept ownership transfer.
+        #[ink::test]
+        fn two_step_ownership_transfer_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.accept_ownership(), Err(Error::NoOwnerProposal));
+            assert_eq!(foobar.propose_owner(accounts.bob), Err(Error::NotOwner));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.propose_owner(accounts.charlie), Ok(()));
+            // A second proposal replaces the first.
+            assert_eq!(foobar.propose_owner(accounts.bob), Ok(()));
+            assert_eq!(foobar.pending_owner(), Some(accounts.bob));
+            let event = ink::env::test::recorded_events().last().unwrap();
+            assert_eq!(
+                event.topics,
+                vec![
+                    topic_of(b"", b"Foobar::OwnerProposed"),
+                    topic_of(b"Foobar::OwnerProposed::owner", &accounts.alice),
+                    topic_of(b"Foobar::OwnerProposed::proposed", &accounts.bob),
+                ]
+            );
+            assert_eq!(foobar.owner(), accounts.alice);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(foobar.accept_ownership(), Err(Error::NotPendingOwner));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.accept_ownership(), Ok(()));
+            assert_eq!(foobar.owner(), accounts.bob);
+            assert_eq!(foobar.pending_owner(), None);
+            match decode_event(&ink::env::test::recorded_events().last().unwrap()) {
+                Event::OwnershipTransferred(OwnershipTransferred { previous, new }) => {
+                    assert_eq!(previous, accounts.alice);
+                    assert_eq!(new, accounts.bob);
+                }
+                _ => panic!("expected an OwnershipTransferred event"),
+            }
+            assert_eq!(foobar.accept_ownership(), Err(Error::NoOwnerProposal));
+        }
+
+        /// We test that the owner can withdraw an ownership proposal.
+        #[ink::test]
+        fn cancel_ownership_proposal_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.cancel_ownership_proposal(), Err(Error::NoOwnerProposal));
+            foobar.propose_owner(accounts.bob).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.cancel_ownership_proposal(), Err(Error::NotOwner));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.cancel_ownership_proposal(), Ok(()));
+            assert_eq!(foobar.pending_owner(), None);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.accept_ownership(), Err(Error::NoOwnerProposal));
+            assert_eq!(foobar.owner(), accounts.alice);
+        }
+
+        /// We test that no privileged message works after renouncing ownership.
+        #[ink::test]
+        fn renounce_ownership_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new_with_fee(false, 1_000).unwrap();
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(100);
+            foobar.place_bid(auction_id).unwrap();
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, 100);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(60_000);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.finalize_auction(auction_id).unwrap();
+            assert_eq!(foobar.collected_fees(), 10);
+
+            assert_eq!(foobar.renounce_ownership(false), Err(Error::NotConfirmed));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.renounce_ownership(true), Err(Error::NotOwner));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.grant_role(Role::Admin, accounts.bob).unwrap();
+            foobar.propose_owner(accounts.bob).unwrap();
+            assert_eq!(foobar.renounce_ownership(true), Ok(()));
+            assert!(foobar.is_owner_renounced());
+            assert_eq!(foobar.owner(), AccountId::from([0x00; 32]));
+            assert_eq!(foobar.pending_owner(), None);
+            let event = ink::env::test::recorded_events().last().unwrap();
+            assert_eq!(
+                event.topics,
+                vec![
+                    topic_of(b"", b"Foobar::OwnershipRenounced"),
+                    topic_of(b"Foobar::OwnershipRenounced::previous", &accounts.alice),
+                ]
+            );
+
+            assert_eq!(foobar.pause(), Err(Error::NotOwner));
+            assert_eq!(foobar.withdraw_fees(Some(accounts.alice), 0), Err(Error::NotOwner));
+            assert_eq!(foobar.set_fee_bps(0), Err(Error::NotOwner));
+            assert_eq!(foobar.transfer_ownership(accounts.alice), Err(Error::NotOwner));
+            assert_eq!(foobar.propose_owner(accounts.alice), Err(Error::NotOwner));
+            assert_eq!(foobar.renounce_ownership(true), Err(Error::NotOwner));
+            assert!(!foobar.has_role(Role::Admin, accounts.alice));
+            assert_eq!(foobar.grant_role(Role::Flipper, accounts.bob), Err(Error::MissingRole));
+            assert_eq!(foobar.set_restricted_mode(true), Err(Error::MissingRole));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.accept_ownership(), Err(Error::NoOwnerProposal));
+            // Admins other than the owner lose their rights as well.
+            assert_eq!(foobar.set_restricted_mode(true), Err(Error::MissingRole));
+            // Not even the all-zero account inherits the owner's rights.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(AccountId::from([0x00; 32]));
+            assert_eq!(foobar.pause(), Err(Error::NotOwner));
+            assert_eq!(foobar.withdraw_fees(Some(accounts.alice), 0), Err(Error::NotOwner));
+        }
+
+        /// We test that `Role::Admin` moves with ownership, however it is
+        /// transferred.
+        #[ink::test]
+        fn admin_role_follows_ownership() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            foobar.transfer_ownership(accounts.bob).unwrap();
+            assert!(!foobar.has_role(Role::Admin, accounts.alice));
+            assert!(foobar.has_role(Role::Admin, accounts.bob));
+            assert_eq!(foobar.grant_role(Role::Flipper, accounts.alice), Err(Error::MissingRole));
+            assert_eq!(foobar.set_restricted_mode(true), Err(Error::MissingRole));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            foobar.propose_owner(accounts.charlie).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            foobar.accept_ownership().unwrap();
+            assert!(!foobar.has_role(Role::Admin, accounts.bob));
+            assert!(foobar.has_role(Role::Admin, accounts.charlie));
+
+            let id = foobar.queue_action(AdminAction::TransferOwnership(accounts.django)).unwrap();
+            foobar.execute_action(id).unwrap();
+            assert!(!foobar.has_role(Role::Admin, accounts.charlie));
+            assert!(foobar.has_role(Role::Admin, accounts.django));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(foobar.set_restricted_mode(true), Ok(()));
+            assert_eq!(foobar.revoke_role(Role::Admin, accounts.django), Err(Error::LastAdmin));
+        }
+
+        /// We test granting and revoking roles.
+        #[ink::test]
+        fn grant_and_revoke_role_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            assert!(foobar.has_role(Role::Admin, accounts.alice));
+            assert!(!foobar.has_role(Role::Flipper, accounts.alice));
+
+            assert_eq!(foobar.grant_role(Role::Flipper, accounts.bob), Ok(()));
+            assert!(foobar.has_role(Role::Flipper, accounts.bob));
+            assert!(!foobar.has_role(Role::Admin, accounts.bob));
+            let event = ink::env::test::recorded_events().last().unwrap();
+            assert_eq!(
+                event.topics,
+                vec![
+                    topic_of(b"", b"Foobar::RoleGranted"),
+                    topic_of(b"Foobar::RoleGranted::role", &Role::Flipper),
+                    topic_of(b"Foobar::RoleGranted::account", &accounts.bob),
+                ]
+            );
+            // Granting a held role again changes nothing.
+            let events_before = ink::env::test::recorded_events().count();
+            assert_eq!(foobar.grant_role(Role::Flipper, accounts.bob), Ok(()));
+            assert_eq!(ink::env::test::recorded_events().count(), events_before);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.grant_role(Role::Admin, accounts.bob), Err(Error::MissingRole));
+            assert_eq!(foobar.revoke_role(Role::Admin, accounts.alice), Err(Error::MissingRole));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.revoke_role(Role::Flipper, accounts.bob), Ok(()));
+            assert!(!foobar.has_role(Role::Flipper, accounts.bob));
+            let event = ink::env::test::recorded_events().last().unwrap();
+            assert_eq!(
+                event.topics,
+                vec![
+                    topic_of(b"", b"Foobar::RoleRevoked"),
+                    topic_of(b"Foobar::RoleRevoked::role", &Role::Flipper),
+                    topic_of(b"Foobar::RoleRevoked::account", &accounts.bob),
+                ]
+            );
+        }
+
+        /// We test that the last admin cannot revoke its own admin role.
+        #[ink::test]
+        fn last_admin_cannot_revoke_itself() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.revoke_role(Role::Admin, accounts.alice), Err(Error::LastAdmin));
+            assert!(foobar.has_role(Role::Admin, accounts.alice));
+
+            foobar.grant_role(Role::Admin, accounts.bob).unwrap();
+            assert_eq!(foobar.revoke_role(Role::Admin, accounts.alice), Ok(()));
+            assert!(!foobar.has_role(Role::Admin, accounts.alice));
+            assert_eq!(foobar.grant_role(Role::Flipper, accounts.alice), Err(Error::MissingRole));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.revoke_role(Role::Admin, accounts.bob), Err(Error::LastAdmin));
+            assert!(foobar.has_role(Role::Admin, accounts.bob));
+        }
+
+        /// We test that restricted mode limits flipping to flippers.
+        #[ink::test]
+        fn restricted_mode_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.flip(), Ok(()));
+            assert_eq!(foobar.set_restricted_mode(true), Err(Error::MissingRole));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.set_restricted_mode(true), Ok(()));
+            assert!(foobar.restricted_mode());
+            // Being an admin does not make the caller a flipper.
+            assert_eq!(foobar.flip(), Err(Error::MissingRole));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.flip(), Err(Error::MissingRole));
+            assert_eq!(
+                foobar.flip_with_reason(String::from("because")),
+                Err(Error::MissingRole)
+            );
+            assert!(foobar.get());
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.grant_role(Role::Flipper, accounts.bob).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.flip(), Ok(()));
+            assert!(!foobar.get());
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.set_restricted_mode(false).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(foobar.flip(), Ok(()));
+            assert!(foobar.get());
+        }
+
+        /// We test toggling the flip allowlist between flips.
+        #[ink::test]
+        fn flip_allowlist_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.add_to_allowlist(accounts.bob), Ok(()));
+            assert!(foobar.is_allowed(accounts.bob));
+            assert!(!foobar.allowlist_enabled());
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(foobar.flip(), Ok(()));
+            assert_eq!(foobar.set_allowlist_enabled(true), Err(Error::NotOwner));
+            assert_eq!(foobar.add_to_allowlist(accounts.charlie), Err(Error::NotOwner));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.set_allowlist_enabled(true), Ok(()));
+            assert_eq!(foobar.flip(), Err(Error::NotAllowed));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(foobar.flip(), Err(Error::NotAllowed));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.flip(), Ok(()));
+            assert!(!foobar.get());
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.remove_from_allowlist(accounts.bob).unwrap();
+            assert!(!foobar.is_allowed(accounts.bob));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.flip(), Err(Error::NotAllowed));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.set_allowlist_enabled(false).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(foobar.flip(), Ok(()));
+            assert!(foobar.get());
+        }
+
+        /// We test adding a batch with duplicates to the flip allowlist.
+        #[ink::test]
+        fn add_many_to_allowlist_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            assert_eq!(
+                foobar.add_many_to_allowlist(vec![accounts.bob; MAX_ALLOWLIST_BATCH + 1]),
+                Err(Error::BatchTooLarge)
+            );
+            assert!(!foobar.is_allowed(accounts.bob));
+            assert_eq!(
+                foobar.add_many_to_allowlist(vec![accounts.bob, accounts.charlie, accounts.bob]),
+                Ok(())
+            );
+            assert!(foobar.is_allowed(accounts.bob));
+            assert!(foobar.is_allowed(accounts.charlie));
+            assert!(!foobar.is_allowed(accounts.django));
+
+            // A duplicate entry is removed with a single call.
+            foobar.remove_from_allowlist(accounts.bob).unwrap();
+            assert!(!foobar.is_allowed(accounts.bob));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.add_many_to_allowlist(vec![accounts.bob]), Err(Error::NotOwner));
+        }
+
+        /// We test banning and unbanning an account.
+        #[ink::test]
+        fn ban_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            assert_eq!(foobar.ban(accounts.alice), Err(Error::CannotBanOwner));
+            assert_eq!(foobar.ban(accounts.bob), Ok(()));
+            assert!(foobar.is_banned(accounts.bob));
+            let event = ink::env::test::recorded_events().last().unwrap();
+            assert_eq!(
+                event.topics,
+                vec![
+                    topic_of(b"", b"Foobar::AccountBanned"),
+                    topic_of(b"Foobar::AccountBanned::account", &accounts.bob),
+                ]
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.flip(), Err(Error::Banned));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(10);
+            assert_eq!(foobar.place_bid(auction_id), Err(Error::Banned));
+            assert_eq!(foobar.set_my_note(String::from("hi")), Err(Error::Banned));
+            assert_eq!(foobar.deposit(), Err(Error::Banned));
+            assert_eq!(foobar.unban(accounts.bob), Err(Error::NotOwner));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(foobar.ban(accounts.django), Err(Error::NotOwner));
+            assert_eq!(foobar.flip(), Ok(()));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.unban(accounts.bob), Ok(()));
+            assert!(!foobar.is_banned(accounts.bob));
+            let event = ink::env::test::recorded_events().last().unwrap();
+            assert_eq!(
+                event.topics,
+                vec![
+                    topic_of(b"", b"Foobar::AccountUnbanned"),
+                    topic_of(b"Foobar::AccountUnbanned::account", &accounts.bob),
+                ]
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.flip(), Ok(()));
+            assert_eq!(foobar.place_bid(auction_id), Ok(()));
+        }
+
+        /// We test that a ban wins over allowlist entries and roles.
+        #[ink::test]
+        fn ban_overrides_allowlist_and_roles() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            foobar.add_to_allowlist(accounts.bob).unwrap();
+            foobar.set_allowlist_enabled(true).unwrap();
+            foobar.grant_role(Role::Admin, accounts.bob).unwrap();
+            foobar.ban(accounts.bob).unwrap();
+            assert!(foobar.is_allowed(accounts.bob));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.flip(), Err(Error::Banned));
+            assert_eq!(foobar.grant_role(Role::Flipper, accounts.bob), Err(Error::Banned));
+            assert_eq!(foobar.set_restricted_mode(true), Err(Error::Banned));
+            assert!(!foobar.get());
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.unban(accounts.bob).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.flip(), Ok(()));
+            assert!(foobar.get());
+        }
+
+        /// We test flipping a personal flag through an approved operator.
+        #[ink::test]
+        fn operator_approval_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.flip_for(accounts.alice), Err(Error::NotOperator));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.approve_operator(accounts.bob, true), Ok(()));
+            assert!(foobar.is_operator(accounts.alice, accounts.bob));
+            assert!(!foobar.is_operator(accounts.bob, accounts.alice));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.flip_for(accounts.alice), Ok(()));
+            assert!(foobar.get_of(accounts.alice));
+            assert!(!foobar.get_of(accounts.bob));
+            let event = ink::env::test::recorded_events().last().unwrap();
+            assert_eq!(
+                event.topics,
+                vec![
+                    topic_of(b"", b"Foobar::PersonalFlipped"),
+                    topic_of(b"Foobar::PersonalFlipped::account", &accounts.alice),
+                    topic_of(b"Foobar::PersonalFlipped::operator", &accounts.bob),
+                ]
+            );
+            assert_eq!(foobar.set_mine_for(accounts.alice, false), Ok(()));
+            assert!(!foobar.get_of(accounts.alice));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(foobar.set_mine_for(accounts.alice, true), Err(Error::NotOperator));
+
+            // Acting after the approval was revoked fails.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.approve_operator(accounts.bob, false), Ok(()));
+            assert!(!foobar.is_operator(accounts.alice, accounts.bob));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.flip_for(accounts.alice), Err(Error::NotOperator));
+            assert_eq!(foobar.set_mine_for(accounts.alice, true), Err(Error::NotOperator));
+            assert!(!foobar.get_of(accounts.alice));
+        }
+
+        /// We test that operators can't be approved or revoked while the
+        /// contract is paused.
+        #[ink::test]
+        fn approve_operator_rejects_when_paused() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            foobar.pause().unwrap();
+            assert_eq!(foobar.approve_operator(accounts.bob, true), Err(Error::Paused));
+            assert!(!foobar.is_operator(accounts.alice, accounts.bob));
+
+            foobar.unpause().unwrap();
+            assert_eq!(foobar.approve_operator(accounts.bob, true), Ok(()));
+            foobar.pause().unwrap();
+            assert_eq!(foobar.approve_operator(accounts.bob, false), Err(Error::Paused));
+            assert!(foobar.is_operator(accounts.alice, accounts.bob));
+        }
+
+        /// We test that queued admin actions only apply after the delay.
+        #[ink::test]
+        fn timelocked_actions_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new_with_timelock(false, 2);
+            assert_eq!(foobar.delay_blocks(), 2);
+            let fee_id = foobar.queue_action(AdminAction::SetFeeBps(250)).unwrap();
+            let pause_id = foobar.queue_action(AdminAction::Pause).unwrap();
+            let transfer_id = foobar
+                .queue_action(AdminAction::TransferOwnership(accounts.bob))
+                .unwrap();
+            assert_eq!(
+                foobar.queued_action(pause_id),
+                Some(QueuedAction {
+                    action: AdminAction::Pause,
+                    queued_at: 0,
+                })
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(foobar.queue_action(AdminAction::Unpause), Err(Error::NotOwner));
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert_eq!(foobar.execute_action(fee_id), Err(Error::TooEarly));
+            assert_eq!(foobar.fee_bps(), 0);
+
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert_eq!(foobar.execute_action(fee_id), Ok(()));
+            assert_eq!(foobar.fee_bps(), 250);
+            assert_eq!(foobar.execute_action(fee_id), Err(Error::ActionNotFound));
+            assert_eq!(foobar.execute_action(pause_id), Ok(()));
+            assert!(foobar.is_paused());
+            assert_eq!(foobar.execute_action(transfer_id), Ok(()));
+            assert_eq!(foobar.owner(), accounts.bob);
+            match decode_event(&ink::env::test::recorded_events().last().unwrap()) {
+                Event::ActionExecuted(ActionExecuted { id }) => assert_eq!(id, transfer_id),
+                _ => panic!("expected an ActionExecuted event"),
+            }
+        }
+
+        /// We test that cancelled or failing actions are not applied.
+        #[ink::test]
+        fn cancel_action_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new_with_timelock(false, 1);
+            let pause_id = foobar.queue_action(AdminAction::Pause).unwrap();
+            let unpause_id = foobar.queue_action(AdminAction::Unpause).unwrap();
+            let fee_id = foobar.queue_action(AdminAction::SetFeeBps(MAX_FEE_BPS + 1)).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.cancel_action(pause_id), Err(Error::NotOwner));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.cancel_action(pause_id), Ok(()));
+            assert_eq!(foobar.queued_action(pause_id), None);
+            assert_eq!(foobar.cancel_action(pause_id), Err(Error::ActionNotFound));
+
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.execute_action(pause_id), Err(Error::ActionNotFound));
+            assert!(!foobar.is_paused());
+            // A failing action keeps its slot in the queue.
+            assert_eq!(foobar.execute_action(unpause_id), Err(Error::NotPaused));
+            assert!(foobar.queued_action(unpause_id).is_some());
+            assert_eq!(foobar.execute_action(fee_id), Err(Error::FeeTooHigh));
+            assert_eq!(foobar.fee_bps(), 0);
+
+            // Renouncing ownership disables everything still queued.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let transfer_id = foobar
+                .queue_action(AdminAction::TransferOwnership(accounts.bob))
+                .unwrap();
+            foobar.renounce_ownership(true).unwrap();
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert_eq!(foobar.execute_action(transfer_id), Err(Error::NotOwner));
+        }
+
+        /// We test that `Flipped` carries the caller, both values and the block.
+        #[ink::test]
+        fn flipped_event_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            foobar.flip().unwrap();
+
+            let event = ink::env::test::recorded_events().last().unwrap();
+            assert_eq!(event.topics.len(), 2);
+            assert_eq!(
+                event.topics[1],
+                topic_of(b"Foobar::Flipped::caller", &accounts.bob)
+            );
+            match decode_event(&event) {
+                Event::Flipped(Flipped {
+                    caller,
+                    previous,
+                    current,
+                    block,
+                }) => {
+                    assert_eq!(caller, accounts.bob);
+                    assert!(!previous);
+                    assert!(current);
+                    assert_eq!(block, 2);
+                }
+                _ => panic!("expected a Flipped event"),
+            }
+        }
+
+        /// We test that every instantiation emits one `Created` event.
+        #[ink::test]
+        fn created_event_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let created_events = || {
+                ink::env::test::recorded_events()
+                    .filter_map(|event| match decode_event(&event) {
+                        Event::Created(created) => Some((event.topics, created)),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+            };
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            let _ = Foobar::new(true);
+            let events = created_events();
+            assert_eq!(events.len(), 1);
+            let (topics, created) = &events[0];
+            assert_eq!(
+                topics,
+                &vec![
+                    topic_of(b"", b"Foobar::Created"),
+                    topic_of(b"Foobar::Created::creator", &accounts.bob),
+                ]
+            );
+            assert_eq!(created.creator, accounts.bob);
+            assert_eq!(created.message, "Foobar created");
+            assert_eq!(created.constructor, CONSTRUCTOR_NEW);
+            assert!(created.init_value);
+            assert_eq!(created.code_hash, Hash::default());
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            let _ = Foobar::default();
+            let events = created_events();
+            assert_eq!(events.len(), 2);
+            let (topics, created) = &events[1];
+            assert_eq!(topics[1], topic_of(b"Foobar::Created::creator", &accounts.charlie));
+            assert_eq!(created.creator, accounts.charlie);
+            assert_eq!(created.constructor, CONSTRUCTOR_DEFAULT);
+            assert!(!created.init_value);
+        }
+
+        /// We test that constructors building on each other's state still emit
+        /// a single `Created` with their own tag.
+        #[ink::test]
+        fn created_event_tags_every_constructor() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let last_created = || {
+                let created = ink::env::test::recorded_events()
+                    .filter_map(|event| match decode_event(&event) {
+                        Event::Created(created) => Some(created),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                (created.len(), created.last().map(|created| created.constructor))
+            };
+            let _ = Foobar::new_with_cooldown(true, 5);
+            assert_eq!(last_created(), (1, Some(CONSTRUCTOR_WITH_COOLDOWN)));
+            let _ = Foobar::new_with_expiry(true, 5);
+            assert_eq!(last_created(), (2, Some(CONSTRUCTOR_WITH_EXPIRY)));
+            let _ = Foobar::new_with_rate_limit(true, 5);
+            assert_eq!(last_created(), (3, Some(CONSTRUCTOR_WITH_RATE_LIMIT)));
+            let _ = Foobar::new_with_timelock(true, 5);
+            assert_eq!(last_created(), (4, Some(CONSTRUCTOR_WITH_TIMELOCK)));
+            let _ = Foobar::new_with_fee(true, 5).unwrap();
+            assert_eq!(last_created(), (5, Some(CONSTRUCTOR_WITH_FEE)));
+            let _ = Foobar::new_multisig(vec![accounts.alice, accounts.bob], 2).unwrap();
+            assert_eq!(last_created(), (6, Some(CONSTRUCTOR_MULTISIG)));
+            let _ = Foobar::try_new(true, 5, 5).unwrap();
+            assert_eq!(last_created(), (7, Some(CONSTRUCTOR_TRY_NEW)));
+            let _ = Foobar::new_with_flip_fee(true, 5, false);
+            assert_eq!(last_created(), (8, Some(CONSTRUCTOR_WITH_FLIP_FEE)));
+            let _ = Foobar::new_funded(true, 0).unwrap();
+            assert_eq!(last_created(), (9, Some(CONSTRUCTOR_FUNDED)));
+            let _ = Foobar::new_with_treasury(true, accounts.bob, 5).unwrap();
+            assert_eq!(last_created(), (10, Some(CONSTRUCTOR_WITH_TREASURY)));
+            let _ = Foobar::new_with_strict_value_checks(true, true);
+            assert_eq!(last_created(), (11, Some(CONSTRUCTOR_WITH_STRICT_VALUE_CHECKS)));
+            // A failing constructor emits nothing.
+            assert!(Foobar::new_with_fee(true, MAX_FEE_BPS + 1).is_err());
+            assert!(Foobar::try_new(true, 5, MAX_FEE_BPS + 1).is_err());
+            assert!(Foobar::new_funded(true, 1).is_err());
+            assert!(Foobar::new_with_treasury(true, accounts.bob, 10_001).is_err());
+            assert_eq!(last_created().0, 11);
+        }
+
+        /// We test that `try_new` applies a configuration within bounds.
+        #[ink::test]
+        fn try_new_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::try_new(true, MAX_COOLDOWN_BLOCKS, MAX_FEE_BPS).unwrap();
+            assert!(foobar.get());
+            assert_eq!(foobar.fee_bps(), MAX_FEE_BPS);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.flip(), Ok(()));
+            assert_eq!(foobar.cooldown_remaining(accounts.bob), MAX_COOLDOWN_BLOCKS);
+        }
+
+        /// We test that `try_new` rejects a fee above `MAX_FEE_BPS`.
+        #[ink::test]
+        fn try_new_rejects_high_fee() {
+            assert!(matches!(
+                Foobar::try_new(false, 0, MAX_FEE_BPS + 1),
+                Err(Error::InvalidConfig)
+            ));
+            assert!(matches!(Foobar::try_new(false, 0, u16::MAX), Err(Error::InvalidConfig)));
+        }
+
+        /// We test that `try_new` rejects a cooldown above `MAX_COOLDOWN_BLOCKS`.
+        #[ink::test]
+        fn try_new_rejects_long_cooldown() {
+            assert!(matches!(
+                Foobar::try_new(false, MAX_COOLDOWN_BLOCKS + 1, 0),
+                Err(Error::InvalidConfig)
+            ));
+            assert!(matches!(Foobar::try_new(false, u32::MAX, 0), Err(Error::InvalidConfig)));
+        }
+
+        /// We test that `new_funded` records the endowment, also in `Created`.
+        #[ink::test]
+        fn new_funded_works() {
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(500);
+            let foobar = Foobar::new_funded(true, 500).unwrap();
+            assert_eq!(foobar.initial_endowment(), 500);
+            let event = ink::env::test::recorded_events().last().unwrap();
+            match decode_event(&event) {
+                Event::Created(Created { constructor, endowment, .. }) => {
+                    assert_eq!(constructor, CONSTRUCTOR_FUNDED);
+                    assert_eq!(endowment, 500);
+                }
+                _ => panic!("expected a Created event"),
+            }
+
+            assert!(matches!(Foobar::new_funded(true, 501), Err(Error::EndowmentTooLow)));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            assert_eq!(Foobar::new(true).initial_endowment(), 0);
+        }
+
+        /// Returns the leading zero bits of the `pow_flip` hash, mirroring the
+        /// documented layout.
+        fn pow_bits(contract: AccountId, caller: AccountId, last_flip_block: BlockNumber, nonce: u64) -> u32 {
+            let mut input = Vec::new();
+            input.extend_from_slice(contract.as_ref());
+            input.extend_from_slice(caller.as_ref());
+            input.extend_from_slice(&last_flip_block.to_le_bytes());
+            input.extend_from_slice(&nonce.to_le_bytes());
+            let mut hash = [0u8; 32];
+            ink::env::hash_bytes::<Blake2x256>(&input, &mut hash);
+            Foobar::leading_zero_bits(&hash)
+        }
+
+        /// We test that `pow_flip` takes a valid proof once and rejects an
+        /// invalid one.
+        #[ink::test]
+        fn pow_flip_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.pow_difficulty(), DEFAULT_POW_DIFFICULTY);
+            assert_eq!(foobar.set_pow_difficulty(8), Ok(()));
+            let (start_block, _) = foobar.last_flip_at();
+
+            // Brute-force a valid nonce and pick one that falls short.
+            let nonce = (0u64..).find(|n| pow_bits(contract, accounts.alice, start_block, *n) >= 8).unwrap();
+            let invalid = (0u64..).find(|n| pow_bits(contract, accounts.alice, start_block, *n) < 8).unwrap();
+            assert_eq!(foobar.pow_flip(invalid), Err(Error::InvalidProof));
+            // The proof is bound to the caller.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            if pow_bits(contract, accounts.bob, start_block, nonce) < 8 {
+                assert_eq!(foobar.pow_flip(nonce), Err(Error::InvalidProof));
+            }
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert!(!foobar.get());
+
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert_eq!(foobar.pow_flip(nonce), Ok(()));
+            assert!(foobar.get());
+
+            // The flip moved the challenge on, so the same nonce stops working.
+            let (flip_block, _) = foobar.last_flip_at();
+            assert_ne!(flip_block, start_block);
+            assert!(pow_bits(contract, accounts.alice, flip_block, nonce) < 8);
+            assert_eq!(foobar.pow_flip(nonce), Err(Error::InvalidProof));
+            assert!(foobar.get());
+        }
+
+        /// We test that only the owner sets the difficulty, up to the cap.
+        #[ink::test]
+        fn set_pow_difficulty_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.set_pow_difficulty(MAX_POW_DIFFICULTY + 1), Err(Error::InvalidConfig));
+            assert_eq!(foobar.set_pow_difficulty(MAX_POW_DIFFICULTY), Ok(()));
+            assert_eq!(foobar.pow_difficulty(), MAX_POW_DIFFICULTY);
+
+            // Difficulty 0 accepts any nonce.
+            assert_eq!(foobar.set_pow_difficulty(0), Ok(()));
+            assert_eq!(foobar.pow_flip(12345), Ok(()));
+            assert!(foobar.get());
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.set_pow_difficulty(4), Err(Error::NotOwner));
+        }
+
+        /// We test that `paid_flip` requires `flip_fee` and collects the payment.
+        #[ink::test]
+        fn paid_flip_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new_with_flip_fee(false, 100, true);
+            assert_eq!(foobar.flip_fee(), 100);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(99);
+            assert_eq!(foobar.paid_flip(), Err(Error::FeeTooLow));
+            assert!(!foobar.get());
+            assert_eq!(foobar.collected_fees(), 0);
+
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(100);
+            assert_eq!(foobar.paid_flip(), Ok(()));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(150);
+            assert_eq!(foobar.paid_flip(), Ok(()));
+            assert!(!foobar.get());
+            assert_eq!(foobar.collected_fees(), 250);
+            assert_eq!(foobar.total_received(), 250);
+
+            // Free flips stay available.
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            assert_eq!(foobar.flip(), Ok(()));
+            assert!(foobar.get());
+        }
+
+        /// We test that `flip` is rejected unless free flips are allowed.
+        #[ink::test]
+        fn free_flips_can_be_disabled() {
+            let mut foobar = Foobar::new_with_flip_fee(false, 0, false);
+            assert!(!foobar.free_flips_allowed());
+            assert_eq!(foobar.flip(), Err(Error::FreeFlipsDisabled));
+            assert_eq!(foobar.attempt_flip(), Some(Error::FreeFlipsDisabled));
+            assert_eq!(foobar.paid_flip(), Ok(()));
+            assert!(foobar.get());
+            assert!(Foobar::new(false).free_flips_allowed());
+        }
+
+        /// We test that no other message flips for free once free flips are
+        /// disabled.
+        #[ink::test]
+        fn free_flips_disabled_covers_every_flip() {
+            let mut foobar = Foobar::new_with_flip_fee(false, 0, false);
+            assert_eq!(foobar.flip_n(1), Err(Error::FreeFlipsDisabled));
+            assert_eq!(foobar.flip_to(true), Err(Error::FreeFlipsDisabled));
+            assert_eq!(foobar.get_and_flip(), Err(Error::FreeFlipsDisabled));
+            assert_eq!(foobar.compare_and_flip(false), Err(Error::FreeFlipsDisabled));
+            assert_eq!(foobar.flip_with_reason(String::from("free")), Err(Error::FreeFlipsDisabled));
+            assert_eq!(foobar.random_flip(Vec::new()), Err(Error::FreeFlipsDisabled));
+            assert_eq!(foobar.vote_flip(), Err(Error::FreeFlipsDisabled));
+            assert_eq!(foobar.execute_flip(), Err(Error::FreeFlipsDisabled));
+
+            assert_eq!(foobar.commit(Foobar::commitment_of(b"salt", true)), Ok(()));
+            assert_eq!(foobar.reveal(b"salt".to_vec(), true), Err(Error::FreeFlipsDisabled));
+
+            assert_eq!(foobar.flip_with_signature([0; 33], 0, [0; 65]), Err(Error::FreeFlipsDisabled));
+
+            assert_eq!(foobar.schedule_flip(0), Ok(()));
+            assert_eq!(foobar.execute_scheduled_flip(), Err(Error::FreeFlipsDisabled));
+
+            assert_eq!(foobar.set_pow_difficulty(0), Ok(()));
+            assert_eq!(foobar.pow_flip(0), Err(Error::FreeFlipsDisabled));
+
+            assert!(!foobar.get());
+            assert_eq!(foobar.flip_count(), 0);
+        }
+
+        /// We test that only the owner can change the flip fee.
+        #[ink::test]
+        fn set_flip_fee_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.flip_fee(), 0);
+            assert_eq!(foobar.set_flip_fee(10), Ok(()));
+            assert_eq!(foobar.flip_fee(), 10);
+            assert_eq!(foobar.paid_flip(), Err(Error::FeeTooLow));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.set_flip_fee(0), Err(Error::NotOwner));
+            assert_eq!(foobar.flip_fee(), 10);
+        }
+
+        /// We test that the admin history can be replayed from events alone.
+        #[ink::test]
+        fn admin_events_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            foobar.pause().unwrap();
+            foobar.unpause().unwrap();
+            foobar.set_fee_bps(250).unwrap();
+            foobar.add_many_to_allowlist(vec![accounts.bob, accounts.bob]).unwrap();
+            foobar.set_allowlist_enabled(true).unwrap();
+            foobar.remove_from_allowlist(accounts.bob).unwrap();
+            foobar.set_restricted_mode(true).unwrap();
+            foobar.transfer_ownership(accounts.bob).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            foobar.set_quorum(3).unwrap();
+
+            let events = ink::env::test::recorded_events()
+                .skip(1)
+                .map(|event| decode_event(&event))
+                .collect::<Vec<_>>();
+            let alice = accounts.alice;
+            let config = |event: &Event| match event {
+                Event::ConfigChanged(ConfigChanged { admin, key, old, new }) => {
+                    Some((*admin, key.clone(), old.clone(), new.clone()))
+                }
+                _ => None,
+            };
+            let expected = |admin, key: &str, old: Vec<u8>, new: Vec<u8>| {
+                Some((admin, String::from(key), old, new))
+            };
+            assert_eq!(events.len(), 11);
+            assert!(matches!(&events[0], Event::Paused(Paused { by }) if *by == alice));
+            assert!(matches!(&events[1], Event::Unpaused(Unpaused { by }) if *by == alice));
+            assert_eq!(
+                config(&events[2]),
+                expected(alice, "fee_bps", 0u16.encode(), 250u16.encode())
+            );
+            assert!(matches!(
+                &events[3],
+                Event::AllowlistUpdated(AllowlistUpdated { admin, account, allowed: true })
+                    if *admin == alice && *account == accounts.bob
+            ));
+            assert_eq!(
+                config(&events[4]),
+                expected(alice, "allowlist_enabled", false.encode(), true.encode())
+            );
+            assert!(matches!(
+                &events[5],
+                Event::AllowlistUpdated(AllowlistUpdated { admin, account, allowed: false })
+                    if *admin == alice && *account == accounts.bob
+            ));
+            assert_eq!(
+                config(&events[6]),
+                expected(alice, "restricted_mode", false.encode(), true.encode())
+            );
+            assert!(matches!(
+                &events[7],
+                Event::RoleRevoked(RoleRevoked { role: Role::Admin, account }) if *account == alice
+            ));
+            assert!(matches!(
+                &events[8],
+                Event::RoleGranted(RoleGranted { role: Role::Admin, account }) if *account == accounts.bob
+            ));
+            assert!(matches!(
+                &events[9],
+                Event::OwnershipTransferred(OwnershipTransferred { previous, new })
+                    if *previous == alice && *new == accounts.bob
+            ));
+            assert_eq!(
+                config(&events[10]),
+                expected(accounts.bob, "quorum", 0u32.encode(), 3u32.encode())
+            );
+
+            let contract = AccountId::from([0xff; 32]);
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(contract);
+            let bob = accounts.bob;
+            foobar.register_observer(accounts.charlie).unwrap();
+            foobar.unregister_observer(accounts.charlie).unwrap();
+            foobar.schedule_flip(5).unwrap();
+            foobar.cancel_scheduled_flip().unwrap();
+            foobar.propose_owner(accounts.django).unwrap();
+            foobar.cancel_ownership_proposal().unwrap();
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            foobar.set_min_increment(auction_id, 5).unwrap();
+            foobar.set_extension_policy(auction_id, 10, 20, 3).unwrap();
+            foobar.set_accept_donations(true).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(30);
+            foobar.receive().unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                contract,
+                foobar.minimum_balance() + 30,
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(bob);
+            foobar.withdraw_donations(Some(accounts.eve)).unwrap();
+
+            let changes = ink::env::test::recorded_events()
+                .skip(1 + events.len())
+                .filter_map(|event| config(&decode_event(&event)))
+                .map(Some)
+                .collect::<Vec<_>>();
+            let none = Option::<BlockNumber>::None;
+            assert_eq!(
+                changes,
+                vec![
+                    expected(bob, "observers", Vec::<AccountId>::new().encode(), vec![accounts.charlie].encode()),
+                    expected(bob, "observers", vec![accounts.charlie].encode(), Vec::<AccountId>::new().encode()),
+                    expected(bob, "pending_flip", none.encode(), Some(5u32).encode()),
+                    expected(bob, "pending_flip", Some(5u32).encode(), none.encode()),
+                    expected(bob, "pending_owner", Some(accounts.django).encode(), Option::<AccountId>::None.encode()),
+                    expected(bob, "min_increment", (auction_id, 1u128).encode(), (auction_id, 5u128).encode()),
+                    expected(
+                        bob,
+                        "extension_policy",
+                        (auction_id, 0u64, 0u64, 0u32).encode(),
+                        (auction_id, 10u64, 20u64, 3u32).encode()
+                    ),
+                    expected(bob, "accept_donations", false.encode(), true.encode()),
+                ]
+            );
+        }
+
+        /// We test that `verbose_events` selects the flip event.
+        #[ink::test]
+        fn verbose_events_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            assert!(foobar.verbose_events());
+            foobar.flip().unwrap();
+            let event = ink::env::test::recorded_events().last().unwrap();
+            assert_eq!(event.topics.len(), 2);
+            assert!(matches!(decode_event(&event), Event::Flipped(_)));
+
+            assert_eq!(foobar.set_verbose_events(false), Ok(()));
+            assert!(!foobar.verbose_events());
+            foobar.flip().unwrap();
+            let event = ink::env::test::recorded_events().last().unwrap();
+            assert!(event.topics.is_empty());
+            match decode_event(&event) {
+                Event::FlippedLite(FlippedLite { current }) => assert!(!current),
+                _ => panic!("expected a FlippedLite event"),
+            }
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.set_verbose_events(true), Err(Error::NotOwner));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.set_verbose_events(true), Ok(()));
+            foobar.flip().unwrap();
+            let event = ink::env::test::recorded_events().last().unwrap();
+            assert!(matches!(decode_event(&event), Event::Flipped(_)));
+        }
+
+        /// We test that `attempt_flip` logs rejected attempts instead of reverting.
+        #[ink::test]
+        fn attempt_flip_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            let rejections = || {
+                ink::env::test::recorded_events()
+                    .filter_map(|event| match decode_event(&event) {
+                        Event::OperationRejected(rejected) => Some((event.topics, rejected)),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(foobar.attempt_flip(), None);
+            assert!(foobar.get());
+            assert!(rejections().is_empty());
+
+            foobar.ban(accounts.bob).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.attempt_flip(), Some(Error::Banned));
+            assert!(foobar.get());
+            let events = rejections();
+            assert_eq!(events.len(), 1);
+            let (topics, rejected) = &events[0];
+            assert_eq!(topics[1], topic_of(b"Foobar::OperationRejected::caller", &accounts.bob));
+            assert_eq!(rejected.caller, accounts.bob);
+            assert_eq!(rejected.reason, Error::Banned);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.pause().unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_eq!(foobar.attempt_flip(), Some(Error::Paused));
+            let events = rejections();
+            assert_eq!(events.len(), 2);
+            assert_eq!(events[1].1.caller, accounts.charlie);
+            assert_eq!(events[1].1.reason, Error::Paused);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.unpause().unwrap();
+            assert_eq!(foobar.attempt_flip(), None);
+            assert!(!foobar.get());
+            assert_eq!(rejections().len(), 2);
+        }
+
+        /// Returns the code assigned to `error`.
+        ///
+        /// The match is exhaustive, so a new variant doesn't compile until it
+        /// is assigned a code here.
+        fn assigned_code(error: Error) -> u32 {
+            match error {
+                Error::UnexpectedValue => 0,
+                Error::NotOwner => 1,
+                Error::TooEarly => 2,
+                Error::AlreadyScheduled => 3,
+                Error::NothingScheduled => 4,
+                Error::CooldownActive => 5,
+                Error::AlreadyVoted => 6,
+                Error::InvalidThreshold => 7,
+                Error::NotSigner => 8,
+                Error::NotEnoughApprovals => 9,
+                Error::AlreadyApproved => 10,
+                Error::NotApproved => 11,
+                Error::InvalidSigners => 12,
+                Error::CommitmentMismatch => 13,
+                Error::NoCommitment => 14,
+                Error::InvalidSignature => 15,
+                Error::NonceTooLow => 16,
+                Error::Paused => 17,
+                Error::NotPaused => 18,
+                Error::Expired => 19,
+                Error::QuorumNotMet => 20,
+                Error::ReasonTooLong => 21,
+                Error::TooManyObservers => 22,
+                Error::AlreadyObserver => 23,
+                Error::NotObserver => 24,
+                Error::RateLimited => 25,
+                Error::NoSuchAuction => 26,
+                Error::TooManyAuctions => 27,
+                Error::InvalidStatusTransition => 28,
+                Error::AuctionNotOpen => 29,
+                Error::BidTooLow => 30,
+                Error::NothingToWithdraw => 31,
+                Error::TransferFailed => 32,
+                Error::AuctionNotEnded => 33,
+                Error::AlreadyFinalized => 34,
+                Error::IncrementTooSmall => 35,
+                Error::AuctionAlreadyStarted => 36,
+                Error::AuctionClosed => 37,
+                Error::NotSeller => 38,
+                Error::CannotCancel => 39,
+                Error::Cancelled => 40,
+                Error::WrongAuctionKind => 41,
+                Error::AlreadyCommitted => 42,
+                Error::AlreadyRevealed => 43,
+                Error::DepositTooLow => 44,
+                Error::NotRevealPhase => 45,
+                Error::RevealNotOver => 46,
+                Error::InvalidPriceSchedule => 47,
+                Error::NotAllowed => 48,
+                Error::FeeTooHigh => 49,
+                Error::InvalidBuyNowPrice => 50,
+                Error::MessageTooLong => 51,
+                Error::VectorFull => 52,
+                Error::NoteTooLong => 53,
+                Error::InvalidOwner => 54,
+                Error::NoOwnerProposal => 55,
+                Error::NotPendingOwner => 56,
+                Error::NotConfirmed => 57,
+                Error::MissingRole => 58,
+                Error::LastAdmin => 59,
+                Error::BatchTooLarge => 60,
+                Error::Banned => 61,
+                Error::CannotBanOwner => 62,
+                Error::NotOperator => 63,
+                Error::ActionNotFound => 64,
+                Error::TooManyActions => 65,
+                Error::InvalidConfig => 66,
+                Error::FeeTooLow => 67,
+                Error::FreeFlipsDisabled => 68,
+                Error::ZeroDeposit => 69,
+                Error::InsufficientFees => 70,
+                Error::InsufficientDeposit => 71,
+                Error::CannotTerminate => 72,
+                Error::ExceedsSweepable => 73,
+                Error::EndowmentTooLow => 74,
+                Error::NotTreasury => 75,
+                Error::DirectTransfersNotAccepted => 76,
+                Error::InputTooLarge => 77,
+                Error::RandomnessUnavailable => 78,
+                Error::RuntimeCallFailed => 79,
+                Error::UnexpectedPayment => 80,
+                Error::InvalidProof => 81,
+            }
+        }
+
+        /// We test that every error keeps its assigned, unique code.
+        #[ink::test]
+        fn error_codes_are_stable() {
+            let errors = [
+                Error::UnexpectedValue,
+                Error::NotOwner,
+                Error::TooEarly,
+                Error::AlreadyScheduled,
+                Error::NothingScheduled,
+                Error::CooldownActive,
+                Error::AlreadyVoted,
+                Error::InvalidThreshold,
+                Error::NotSigner,
+                Error::NotEnoughApprovals,
+                Error::AlreadyApproved,
+                Error::NotApproved,
+                Error::InvalidSigners,
+                Error::CommitmentMismatch,
+                Error::NoCommitment,
+                Error::InvalidSignature,
+                Error::NonceTooLow,
+                Error::Paused,
+                Error::NotPaused,
+                Error::Expired,
+                Error::QuorumNotMet,
+                Error::ReasonTooLong,
+                Error::TooManyObservers,
+                Error::AlreadyObserver,
+                Error::NotObserver,
+                Error::RateLimited,
+                Error::NoSuchAuction,
+                Error::TooManyAuctions,
+                Error::InvalidStatusTransition,
+                Error::AuctionNotOpen,
+                Error::BidTooLow,
+                Error::NothingToWithdraw,
+                Error::TransferFailed,
+                Error::AuctionNotEnded,
+                Error::AlreadyFinalized,
+                Error::IncrementTooSmall,
+                Error::AuctionAlreadyStarted,
+                Error::AuctionClosed,
+                Error::NotSeller,
+                Error::CannotCancel,
+                Error::Cancelled,
+                Error::WrongAuctionKind,
+                Error::AlreadyCommitted,
+                Error::AlreadyRevealed,
+                Error::DepositTooLow,
+                Error::NotRevealPhase,
+                Error::RevealNotOver,
+                Error::InvalidPriceSchedule,
+                Error::NotAllowed,
+                Error::FeeTooHigh,
+                Error::InvalidBuyNowPrice,
+                Error::MessageTooLong,
+                Error::VectorFull,
+                Error::NoteTooLong,
+                Error::InvalidOwner,
+                Error::NoOwnerProposal,
+                Error::NotPendingOwner,
+                Error::NotConfirmed,
+                Error::MissingRole,
+                Error::LastAdmin,
+                Error::BatchTooLarge,
+                Error::Banned,
+                Error::CannotBanOwner,
+                Error::NotOperator,
+                Error::ActionNotFound,
+                Error::TooManyActions,
+                Error::InvalidConfig,
+                Error::FeeTooLow,
+                Error::FreeFlipsDisabled,
+                Error::ZeroDeposit,
+                Error::InsufficientFees,
+                Error::InsufficientDeposit,
+                Error::CannotTerminate,
+                Error::ExceedsSweepable,
+                Error::EndowmentTooLow,
+                Error::NotTreasury,
+                Error::DirectTransfersNotAccepted,
+                Error::InputTooLarge,
+                Error::RandomnessUnavailable,
+                Error::RuntimeCallFailed,
+                Error::UnexpectedPayment,
+                Error::InvalidProof,
+            ];
+            let mut codes = Vec::new();
+            for error in errors {
+                assert_eq!(error.code(), assigned_code(error));
+                // The code doubles as the SCALE index.
+                assert_eq!(error.encode(), vec![error.code() as u8]);
+                codes.push(error.code());
+            }
+            codes.sort_unstable();
+            codes.dedup();
+            assert_eq!(codes.len(), errors.len());
+        }
+
+        /// We test that `last_error_code` reports rejected `attempt_flip` calls.
+        #[ink::test]
+        fn last_error_code_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            assert_eq!(foobar.last_error_code(), None);
+            assert_eq!(foobar.attempt_flip(), None);
+            assert_eq!(foobar.last_error_code(), None);
+
+            foobar.pause().unwrap();
+            assert_eq!(foobar.attempt_flip(), Some(Error::Paused));
+            assert_eq!(foobar.last_error_code(), Some(Error::Paused.code()));
+            foobar.unpause().unwrap();
+            foobar.ban(accounts.bob).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(foobar.attempt_flip(), Some(Error::Banned));
+            assert_eq!(foobar.last_error_code(), Some(Error::Banned.code()));
+            // A successful attempt leaves the latest rejection in place.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(foobar.attempt_flip(), None);
+            assert_eq!(foobar.last_error_code(), Some(Error::Banned.code()));
+        }
+
+        /// Returns the path of the event's signature topic, or `None` for
+        /// anonymous events.
+        ///
+        /// The match is exhaustive, so a new event doesn't compile until it is
+        /// added here and, with that, to `event_topics`.
+        fn topic_path_of(event: &Event) -> Option<&'static str> {
+            match event {
+                Event::Created(_) => Some(event_topics::CREATED),
+                Event::Flipped(_) => Some(event_topics::FLIPPED),
+                Event::OperationRejected(_) => Some(event_topics::OPERATION_REJECTED),
+                Event::MessageChanged(_) => Some(event_topics::MESSAGE_CHANGED),
+                Event::DocumentHashSet(_) => Some(event_topics::DOCUMENT_HASH_SET),
+                Event::NoteChanged(_) => Some(event_topics::NOTE_CHANGED),
+                Event::ConfigChanged(_) => Some(event_topics::CONFIG_CHANGED),
+                Event::AllowlistUpdated(_) => Some(event_topics::ALLOWLIST_UPDATED),
+                Event::ActionQueued(_) => Some(event_topics::ACTION_QUEUED),
+                Event::ActionExecuted(_) => Some(event_topics::ACTION_EXECUTED),
+                Event::ActionCancelled(_) => Some(event_topics::ACTION_CANCELLED),
+                Event::AccountBanned(_) => Some(event_topics::ACCOUNT_BANNED),
+                Event::AccountUnbanned(_) => Some(event_topics::ACCOUNT_UNBANNED),
+                Event::RoleGranted(_) => Some(event_topics::ROLE_GRANTED),
+                Event::RoleRevoked(_) => Some(event_topics::ROLE_REVOKED),
+                Event::OwnershipRenounced(_) => Some(event_topics::OWNERSHIP_RENOUNCED),
+                Event::OwnerProposed(_) => Some(event_topics::OWNER_PROPOSED),
+                Event::OwnershipTransferred(_) => Some(event_topics::OWNERSHIP_TRANSFERRED),
+                Event::VoteCast(_) => Some(event_topics::VOTE_CAST),
+                Event::Paused(_) => Some(event_topics::PAUSED),
+                Event::Unpaused(_) => Some(event_topics::UNPAUSED),
+                Event::PersonalFlipped(_) => Some(event_topics::PERSONAL_FLIPPED),
+                Event::OperatorApproval(_) => Some(event_topics::OPERATOR_APPROVAL),
+                Event::FlippedWithReason(_) => Some(event_topics::FLIPPED_WITH_REASON),
+                Event::StatusChanged(_) => Some(event_topics::STATUS_CHANGED),
+                Event::AuctionCreated(_) => Some(event_topics::AUCTION_CREATED),
+                Event::BidPlaced(_) => Some(event_topics::BID_PLACED),
+                Event::AuctionEnded(_) => Some(event_topics::AUCTION_ENDED),
+                Event::AuctionFinalized(_) => Some(event_topics::AUCTION_FINALIZED),
+                Event::AuctionCancelled(_) => Some(event_topics::AUCTION_CANCELLED),
+                Event::ReserveNotMet(_) => Some(event_topics::RESERVE_NOT_MET),
+                Event::DeadlineExtended(_) => Some(event_topics::DEADLINE_EXTENDED),
+                Event::AuctionTransferred(_) => Some(event_topics::AUCTION_TRANSFERRED),
+                Event::BoughtNow(_) => Some(event_topics::BOUGHT_NOW),
+                Event::ExpiredFinalized(_) => Some(event_topics::EXPIRED_FINALIZED),
+                Event::BeneficiaryChanged(_) => Some(event_topics::BENEFICIARY_CHANGED),
+                Event::FeesWithdrawn(_) => Some(event_topics::FEES_WITHDRAWN),
+                Event::Deposited(_) => Some(event_topics::DEPOSITED),
+                Event::Terminated(_) => Some(event_topics::TERMINATED),
+                Event::SweptOut(_) => Some(event_topics::SWEPT_OUT),
+                Event::DonationReceived(_) => Some(event_topics::DONATION_RECEIVED),
+                Event::DonationsWithdrawn(_) => Some(event_topics::DONATIONS_WITHDRAWN),
+                Event::FlippedLite(_) => None,
+            }
+        }
+
+        /// We test that `event_topics` matches the topics actually emitted.
+        #[ink::test]
+        fn event_topics_match_recorded_events() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut foobar = Foobar::new(false);
+            foobar.flip().unwrap();
+            foobar.set_verbose_events(false).unwrap();
+            foobar.flip().unwrap();
+            foobar.pause().unwrap();
+            foobar.unpause().unwrap();
+            foobar.set_message(String::from("hello")).unwrap();
+            foobar.set_document_hash(Hash::from([0x01; 32])).unwrap();
+            foobar.set_my_note(String::from("note")).unwrap();
+            foobar.add_to_allowlist(accounts.bob).unwrap();
+            foobar.grant_role(Role::Flipper, accounts.bob).unwrap();
+            foobar.revoke_role(Role::Flipper, accounts.bob).unwrap();
+            foobar.ban(accounts.django).unwrap();
+            foobar.unban(accounts.django).unwrap();
+            foobar.approve_operator(accounts.bob, true).unwrap();
+            foobar.set_mine(true).unwrap();
+            foobar.flip_with_reason(String::from("why not")).unwrap();
+            let action_id = foobar.queue_action(AdminAction::Pause).unwrap();
+            foobar.execute_action(action_id).unwrap();
+            foobar.unpause().unwrap();
+            let action_id = foobar.queue_action(AdminAction::Pause).unwrap();
+            foobar.cancel_action(action_id).unwrap();
+            foobar.set_beneficiary(accounts.charlie).unwrap();
+            let auction_id = foobar
+                .create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None)
+                .unwrap();
+            foobar.start_auction(auction_id).unwrap();
+            foobar.cancel_auction(auction_id).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            foobar.vote_flip().unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            foobar.propose_owner(accounts.bob).unwrap();
+            foobar.transfer_ownership(accounts.bob).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            foobar.renounce_ownership(true).unwrap();
+            foobar.attempt_flip();
+
+            let mut seen = Vec::new();
+            for event in ink::env::test::recorded_events() {
+                match topic_path_of(&decode_event(&event)) {
+                    Some(path) => {
+                        assert_eq!(event.topics[0], event_topics::signature_topic(path).to_vec());
+                        seen.push(path);
+                    }
+                    None => assert!(event.topics.is_empty()),
+                }
+            }
+            seen.sort_unstable();
+            seen.dedup();
+            assert_eq!(seen.len(), 27);
+
+            let on_chain = foobar.event_topics();
+            assert_eq!(on_chain.len(), event_topics::ALL.len());
+            for (path, topic) in on_chain {
+                assert!(event_topics::ALL.contains(&path.as_str()));
+                assert_eq!(topic, Hash::from(event_topics::signature_topic(&path)));
+            }
+        }
+    }
+
+
+    /// This is how you'd write end-to-end (E2E) or integration tests for ink! contracts.
+    ///
+    /// When running these you need to make sure that you:
+    /// - Compile the tests with the `e2e-tests` feature flag enabled (`--features e2e-tests`)
+    /// - Are running a Substrate node which contains `pallet-contracts` in the background
+    /// The End-to-End tests run against the default environment, so they are
+    /// left out with the `chain-ext` feature, see `chain_ext_e2e_tests`.
+    #[cfg(all(test, feature = "e2e-tests", not(feature = "chain-ext")))]
+    mod e2e_tests {
+        /// Imports all the definitions from the outer scope so we can use them here.
+        use super::*;
+
+        /// A helper function used for calling contract messages.
+        use ink_e2e::build_message;
+
+        /// The observer contract notified in the `OnFlip` tests.
+        use observer::ObserverRef;
+
+        /// The contract-wide event type, used to decode `ContractEmitted` data.
+        type Event = <Foobar as ::ink::reflect::ContractEventBase>::Type;
+
+        /// The End-to-End test `Result` type.
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        /// We test that we can upload and instantiate the contract using its default constructor.
+        #[ink_e2e::test]
+        async fn default_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::default();
+
+            // When
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // Then
+            let get = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get());
+            let get_result = client.call_dry_run(&ink_e2e::alice(), &get, 0, None).await;
+            assert!(matches!(get_result.return_value(), false));
+
+            Ok(())
+        }
+
+        /// We test that we can read and write a value from the on-chain contract contract.
+        #[ink_e2e::test]
+        async fn it_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::bob(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            let get = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get());
+            let get_result = client.call_dry_run(&ink_e2e::bob(), &get, 0, None).await;
+            assert!(matches!(get_result.return_value(), false));
+
+            // When
+            let flip = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.flip());
+            let _flip_result = client
+                .call(&ink_e2e::bob(), flip, 0, None)
+                .await
+                .expect("flip failed");
+
+            // Then
+            let get = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get());
+            let get_result = client.call_dry_run(&ink_e2e::bob(), &get, 0, None).await;
+            assert!(matches!(get_result.return_value(), true));
+
+            Ok(())
+        }
+
+        /// We test that the flip counter is updated by on-chain `flip` calls.
+        #[ink_e2e::test]
+        async fn flip_count_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::charlie(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // When
+            for _ in 0..2 {
+                let flip = build_message::<FoobarRef>(contract_account_id.clone())
+                    .call(|foobar| foobar.flip());
+                client
+                    .call(&ink_e2e::charlie(), flip, 0, None)
+                    .await
+                    .expect("flip failed");
+            }
+
+            // Then
+            let flip_count = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.flip_count());
+            let flip_count_result = client
+                .call_dry_run(&ink_e2e::charlie(), &flip_count, 0, None)
+                .await;
+            assert_eq!(flip_count_result.return_value(), 2);
+
+            Ok(())
+        }
+
+        /// We test that the last flipper follows the signer of the latest `flip`.
+        #[ink_e2e::test]
+        async fn last_flipper_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // When
+            let flip = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.flip());
+            client
+                .call(&ink_e2e::alice(), flip, 0, None)
+                .await
+                .expect("flip failed");
+
+            // Then
+            let last_flipper = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.last_flipper());
+            let last_flipper_result = client
+                .call_dry_run(&ink_e2e::alice(), &last_flipper, 0, None)
+                .await;
+            assert_eq!(
+                last_flipper_result.return_value(),
+                Some(ink_e2e::account_id(ink_e2e::AccountKeyring::Alice))
+            );
+
+            // When
+            let flip = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.flip());
+            client
+                .call(&ink_e2e::bob(), flip, 0, None)
+                .await
+                .expect("flip failed");
+
+            // Then
+            let last_flipper_result = client
+                .call_dry_run(&ink_e2e::alice(), &last_flipper, 0, None)
+                .await;
+            assert_eq!(
+                last_flipper_result.return_value(),
+                Some(ink_e2e::account_id(ink_e2e::AccountKeyring::Bob))
+            );
+
+            Ok(())
+        }
+
+        /// We test that a repeated `flip_to` reports that nothing changed.
+        #[ink_e2e::test]
+        async fn flip_to_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // When
+            let flip_to = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.flip_to(true));
+            let first_result = client
+                .call(&ink_e2e::alice(), flip_to, 0, None)
+                .await
+                .expect("flip_to failed");
+            assert_eq!(first_result.return_value(), Ok(true));
+
+            // Then
+            let flip_to = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.flip_to(true));
+            let second_result = client
+                .call(&ink_e2e::alice(), flip_to, 0, None)
+                .await
+                .expect("flip_to failed");
+            assert_eq!(second_result.return_value(), Ok(false));
+
+            let get = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get());
+            let get_result = client.call_dry_run(&ink_e2e::alice(), &get, 0, None).await;
+            assert!(matches!(get_result.return_value(), true));
+
+            Ok(())
+        }
+
+        /// We test that the second of two conflicting `compare_and_flip` calls fails.
+        #[ink_e2e::test]
+        async fn compare_and_flip_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // When
+            let compare_and_flip = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.compare_and_flip(false));
+            client
+                .call(&ink_e2e::alice(), compare_and_flip, 0, None)
+                .await
+                .expect("compare_and_flip failed");
+
+            // Then
+            let compare_and_flip = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.compare_and_flip(false));
+            let second_result = client
+                .call_dry_run(&ink_e2e::bob(), &compare_and_flip, 0, None)
+                .await;
+            assert_eq!(second_result.return_value(), Err(Error::UnexpectedValue));
+
+            Ok(())
+        }
+
+        /// We test that `flip_n(3)` ends with the value toggled.
+        #[ink_e2e::test]
+        async fn flip_n_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // When
+            let flip_n = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.flip_n(3));
+            client
+                .call(&ink_e2e::alice(), flip_n, 0, None)
+                .await
+                .expect("flip_n failed");
+
+            // Then
+            let get = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get());
+            let get_result = client.call_dry_run(&ink_e2e::alice(), &get, 0, None).await;
+            assert!(get_result.return_value());
+
+            let flip_count = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.flip_count());
+            let flip_count_result = client
+                .call_dry_run(&ink_e2e::alice(), &flip_count, 0, None)
+                .await;
+            assert_eq!(flip_count_result.return_value(), 3);
+
+            Ok(())
+        }
+
+
+        /// We test that `get_and_flip` returns the previous value on-chain.
+        #[ink_e2e::test]
+        async fn get_and_flip_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // When
+            let get_and_flip = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get_and_flip());
+            let dry_run_result = client
+                .call_dry_run(&ink_e2e::alice(), &get_and_flip, 0, None)
+                .await;
+            assert_eq!(dry_run_result.return_value(), Ok(false));
+            let get_and_flip_result = client
+                .call(&ink_e2e::alice(), get_and_flip, 0, None)
+                .await
+                .expect("get_and_flip failed");
+            assert_eq!(get_and_flip_result.return_value(), Ok(false));
+
+            // Then
+            let get = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get());
+            let get_result = client.call_dry_run(&ink_e2e::alice(), &get, 0, None).await;
+            assert!(get_result.return_value());
+
+            Ok(())
+        }
+
+
+        /// We test that only the deployer may reset the value.
+        #[ink_e2e::test]
+        async fn reset_value_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(true);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // When
+            let reset_value = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.reset_value(false));
+            let bob_result = client
+                .call_dry_run(&ink_e2e::bob(), &reset_value, 0, None)
+                .await;
+            assert_eq!(bob_result.return_value(), Err(Error::NotOwner));
+
+            client
+                .call(&ink_e2e::alice(), reset_value, 0, None)
+                .await
+                .expect("reset_value failed");
+
+            // Then
+            let get = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get());
+            let get_result = client.call_dry_run(&ink_e2e::alice(), &get, 0, None).await;
+            assert!(!get_result.return_value());
+
+            Ok(())
+        }
+
+
+        /// We test that an immediate second flip from the same signer is rejected.
+        #[ink_e2e::test]
+        async fn cooldown_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new_with_cooldown(false, 100);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // When
+            let flip = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.flip());
+            client
+                .call(&ink_e2e::bob(), flip, 0, None)
+                .await
+                .expect("flip failed");
+
+            // Then
+            let flip = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.flip());
+            let second_result = client.call_dry_run(&ink_e2e::bob(), &flip, 0, None).await;
+            assert_eq!(second_result.return_value(), Err(Error::CooldownActive));
+
+            let other_result = client.call_dry_run(&ink_e2e::charlie(), &flip, 0, None).await;
+            assert_eq!(other_result.return_value(), Ok(()));
+
+            Ok(())
+        }
+
+
+        /// We test that alice and bob approving lets charlie execute the flip.
+        #[ink_e2e::test]
+        async fn multisig_flip_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let signers = vec![
+                ink_e2e::account_id(ink_e2e::AccountKeyring::Alice),
+                ink_e2e::account_id(ink_e2e::AccountKeyring::Bob),
+                ink_e2e::account_id(ink_e2e::AccountKeyring::Charlie),
+            ];
+            let constructor = FoobarRef::new_multisig(signers, 2);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // When
+            let execute_flip = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.execute_flip());
+            let early_result = client
+                .call_dry_run(&ink_e2e::charlie(), &execute_flip, 0, None)
+                .await;
+            assert_eq!(early_result.return_value(), Err(Error::NotEnoughApprovals));
+
+            for signer in [ink_e2e::alice(), ink_e2e::bob()] {
+                let approve_flip = build_message::<FoobarRef>(contract_account_id.clone())
+                    .call(|foobar| foobar.approve_flip());
+                client
+                    .call(&signer, approve_flip, 0, None)
+                    .await
+                    .expect("approve_flip failed");
+            }
+
+            client
+                .call(&ink_e2e::charlie(), execute_flip, 0, None)
+                .await
+                .expect("execute_flip failed");
+
+            // Then
+            let get = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get());
+            let get_result = client.call_dry_run(&ink_e2e::alice(), &get, 0, None).await;
+            assert!(get_result.return_value());
+
+            Ok(())
+        }
+
+
+        /// We test that a paused contract reports `Error::Paused` to clients.
+        #[ink_e2e::test]
+        async fn pause_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // When
+            let pause = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.pause());
+            client
+                .call(&ink_e2e::alice(), pause, 0, None)
+                .await
+                .expect("pause failed");
+
+            // Then
+            let flip = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.flip());
+            let flip_result = client.call_dry_run(&ink_e2e::bob(), &flip, 0, None).await;
+            assert_eq!(flip_result.return_value(), Err(Error::Paused));
+
+            let is_paused = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.is_paused());
+            let is_paused_result = client.call_dry_run(&ink_e2e::bob(), &is_paused, 0, None).await;
+            assert!(is_paused_result.return_value());
+
+            let get = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get());
+            let get_result = client.call_dry_run(&ink_e2e::bob(), &get, 0, None).await;
+            assert!(!get_result.return_value());
+
+            Ok(())
+        }
+
+        /// We test that a rejected flip reverts with a decodable `Error`
+        /// instead of trapping.
+        #[ink_e2e::test]
+        async fn flip_by_banned_caller_fails(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let bob_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let ban = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.ban(bob_account));
+            client
+                .call(&ink_e2e::alice(), ban, 0, None)
+                .await
+                .expect("ban failed");
+
+            // When
+            let flip = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.flip());
+            let flip_result = client.call_dry_run(&ink_e2e::bob(), &flip, 0, None).await;
+
+            // Then
+            let exec_result = flip_result
+                .exec_result
+                .result
+                .as_ref()
+                .expect("flip trapped instead of returning an error");
+            assert!(exec_result.did_revert());
+            assert_eq!(flip_result.return_value(), Err(Error::Banned));
+            assert!(client.call(&ink_e2e::bob(), flip, 0, None).await.is_err());
+
+            let get = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get());
+            let get_result = client.call_dry_run(&ink_e2e::bob(), &get, 0, None).await;
+            assert!(!get_result.return_value());
+
+            Ok(())
+        }
+
+
+        /// We test that alice and bob each flip their own flag.
+        #[ink_e2e::test]
+        async fn personal_flags_work(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // When
+            let flip_mine = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.flip_mine());
+            client
+                .call(&ink_e2e::alice(), flip_mine, 0, None)
+                .await
+                .expect("flip_mine failed");
+            let set_mine = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.set_mine(false));
+            client
+                .call(&ink_e2e::bob(), set_mine, 0, None)
+                .await
+                .expect("set_mine failed");
+
+            // Then
+            let alice = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let bob = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let get_alice = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get_of(alice));
+            let alice_result = client.call_dry_run(&ink_e2e::bob(), &get_alice, 0, None).await;
+            assert!(alice_result.return_value());
+            let get_bob = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get_of(bob));
+            let bob_result = client.call_dry_run(&ink_e2e::alice(), &get_bob, 0, None).await;
+            assert!(!bob_result.return_value());
+
+            Ok(())
+        }
+
+
+        /// We test that the reason of an on-chain flip can be read back.
+        #[ink_e2e::test]
+        async fn flip_with_reason_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // When
+            let flip_with_reason = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.flip_with_reason(String::from("maintenance")));
+            client
+                .call(&ink_e2e::alice(), flip_with_reason, 0, None)
+                .await
+                .expect("flip_with_reason failed");
+
+            // Then
+            let last_reason = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.last_reason());
+            let last_reason_result = client
+                .call_dry_run(&ink_e2e::alice(), &last_reason, 0, None)
+                .await;
+            assert_eq!(last_reason_result.return_value(), "maintenance");
+
+            Ok(())
+        }
+
+
+        /// We test that the leaderboard orders signers by their number of flips.
+        #[ink_e2e::test]
+        async fn leaderboard_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // When
+            for signer in [ink_e2e::bob(), ink_e2e::alice(), ink_e2e::bob()] {
+                let flip = build_message::<FoobarRef>(contract_account_id.clone())
+                    .call(|foobar| foobar.flip());
+                client
+                    .call(&signer, flip, 0, None)
+                    .await
+                    .expect("flip failed");
+            }
+
+            // Then
+            let leaderboard = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.leaderboard());
+            let leaderboard_result = client
+                .call_dry_run(&ink_e2e::alice(), &leaderboard, 0, None)
+                .await;
+            assert_eq!(
+                leaderboard_result.return_value(),
+                vec![
+                    (ink_e2e::account_id(ink_e2e::AccountKeyring::Bob), 2),
+                    (ink_e2e::account_id(ink_e2e::AccountKeyring::Alice), 1),
+                ]
+            );
+
+            Ok(())
+        }
+
+
+        /// We test that `info` decodes through a dry-run.
+        #[ink_e2e::test]
+        async fn info_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // When
+            let flip = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.flip());
+            client
+                .call(&ink_e2e::bob(), flip, 0, None)
+                .await
+                .expect("flip failed");
+
+            // Then
+            let info = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.info());
+            let info = client
+                .call_dry_run(&ink_e2e::alice(), &info, 0, None)
+                .await
+                .return_value();
+            assert!(info.value);
+            assert_eq!(info.flip_count, 1);
+            assert_eq!(
+                info.last_flipper,
+                Some(ink_e2e::account_id(ink_e2e::AccountKeyring::Bob))
+            );
+            assert!(info.last_flip_block.is_some());
+            assert!(!info.paused);
+
+            Ok(())
+        }
+
+        /// We test that flips are stamped with increasing blocks and times.
+        #[ink_e2e::test]
+        async fn last_flip_at_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let last_flip_at = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.last_flip_at());
+
+            // When
+            let flip = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.flip());
+            client
+                .call(&ink_e2e::bob(), flip, 0, None)
+                .await
+                .expect("flip failed");
+            let (first_block, first_timestamp) = client
+                .call_dry_run(&ink_e2e::bob(), &last_flip_at, 0, None)
+                .await
+                .return_value();
+            let flip = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.flip());
+            client
+                .call(&ink_e2e::bob(), flip, 0, None)
+                .await
+                .expect("flip failed");
+            let (second_block, second_timestamp) = client
+                .call_dry_run(&ink_e2e::bob(), &last_flip_at, 0, None)
+                .await
+                .return_value();
+
+            // Then
+            assert!(first_block > 0);
+            assert!(first_timestamp > 0);
+            assert!(second_block > first_block);
+            assert!(second_timestamp >= first_timestamp);
+            let current_block = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.current_block());
+            let current_block = client
+                .call_dry_run(&ink_e2e::bob(), &current_block, 0, None)
+                .await
+                .return_value();
+            assert!(current_block >= second_block);
+
+            Ok(())
+        }
+
+        /// We test that the contract reports the code hash it was uploaded
+        /// with, also in `Created`, and its own account.
+        #[ink_e2e::test]
+        async fn identity_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let uploaded_code_hash = client
+                .upload("foobar", &ink_e2e::alice(), None)
+                .await
+                .expect("upload failed")
+                .code_hash;
+
+            // When
+            let instantiate_result = client
+                .instantiate("foobar", &ink_e2e::alice(), FoobarRef::new(false), 0, None)
+                .await
+                .expect("instantiate failed");
+            let contract_account_id = instantiate_result.account_id.clone();
+            let code_hash = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.code_hash());
+            let code_hash = client
+                .call_dry_run(&ink_e2e::bob(), &code_hash, 0, None)
+                .await
+                .return_value();
+            let account_id = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.account_id());
+            let account_id = client
+                .call_dry_run(&ink_e2e::bob(), &account_id, 0, None)
+                .await
+                .return_value();
+
+            // Then
+            assert_eq!(code_hash, uploaded_code_hash);
+            assert_eq!(account_id, contract_account_id);
+            let created = instantiate_result
+                .events
+                .iter()
+                .map(|event| event.expect("invalid event"))
+                .filter(|event| event.pallet_name() == "Contracts" && event.variant_name() == "ContractEmitted")
+                .find_map(|event| {
+                    let (_, data) = <(AccountId, Vec<u8>) as scale::Decode>::decode(&mut event.field_bytes())
+                        .expect("invalid ContractEmitted event");
+                    match <Event as scale::Decode>::decode(&mut &data[..]).expect("invalid contract event") {
+                        Event::Created(created) => Some(created),
+                        _ => None,
+                    }
+                })
+                .expect("no Created event");
+            assert_eq!(created.code_hash, uploaded_code_hash);
+
+            Ok(())
+        }
+
+        /// We test that `debug_meter` reports plausible values on chain.
+        ///
+        /// `ink_e2e` builds the contract with its default features, so this
+        /// test needs `debug` among them.
+        #[cfg(feature = "debug")]
+        #[ink_e2e::test]
+        async fn debug_meter_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // When
+            let debug_meter = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.debug_meter());
+            let meter = client
+                .call_dry_run(&ink_e2e::bob(), &debug_meter, 0, None)
+                .await
+                .return_value();
+
+            // Then
+            assert!(meter.gas_left > 0);
+            assert!(meter.balance > 0);
+            assert!(meter.minimum_balance > 0);
+            assert!(meter.balance >= meter.minimum_balance);
+            assert!(meter.storage_size > 0);
+
+            Ok(())
+        }
+
+        /// We test that a build without the `debug` feature has no
+        /// `debug_meter`, so its selector ends up in the `receive` fallback.
+        #[cfg(not(feature = "debug"))]
+        #[ink_e2e::test]
+        async fn debug_meter_is_not_exposed(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // When
+            let debug_meter = build_message::<FoobarRef>(contract_account_id.clone()).call(|_| {
+                build_call::<Environment>()
+                    .call(contract_account_id.clone())
+                    .exec_input(ExecutionInput::new(Selector::new(ink::selector_bytes!("debug_meter"))))
+                    .returns::<Result<(), Error>>()
+            });
+            let debug_meter_result = client
+                .call_dry_run(&ink_e2e::bob(), &debug_meter, 0, None)
+                .await;
+
+            // Then
+            assert_eq!(debug_meter_result.return_value(), Err(Error::DirectTransfersNotAccepted));
+
+            Ok(())
+        }
+
+        /// We test that `caller_context` describes the dry-run's caller.
+        #[ink_e2e::test]
+        async fn caller_context_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let deposit = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.deposit());
+            client
+                .call(&ink_e2e::bob(), deposit, 1_000, None)
+                .await
+                .expect("deposit failed");
+            let bob_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+
+            // When
+            let whoami = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.whoami());
+            let whoami = client
+                .call_dry_run(&ink_e2e::bob(), &whoami, 0, None)
+                .await
+                .return_value();
+            let caller_context = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.caller_context());
+            let caller_context = client
+                .call_dry_run(&ink_e2e::bob(), &caller_context, 0, None)
+                .await
+                .return_value();
+
+            // Then
+            assert_eq!(whoami, bob_account);
+            assert_eq!(
+                caller_context,
+                CallerContext {
+                    caller: bob_account,
+                    is_owner: false,
+                    is_allowed: false,
+                    is_banned: false,
+                    cooldown_remaining: 0,
+                    deposit: 1_000,
+                }
+            );
+
+            Ok(())
+        }
+
+
+        /// We test that a registered observer is notified when Foobar flips.
+        #[ink_e2e::test(additional_contracts = "../observer/Cargo.toml")]
+        async fn observer_is_notified(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let observer_account_id = client
+                .instantiate("observer", &ink_e2e::alice(), ObserverRef::new(), 0, None)
+                .await
+                .expect("instantiate observer failed")
+                .account_id;
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let register_observer = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.register_observer(observer_account_id.clone()));
+            client
+                .call(&ink_e2e::alice(), register_observer, 0, None)
+                .await
+                .expect("register_observer failed");
+
+            // When
+            let flip = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.flip());
+            client
+                .call(&ink_e2e::bob(), flip, 0, None)
+                .await
+                .expect("flip failed");
+
+            // Then
+            let last_value = build_message::<ObserverRef>(observer_account_id.clone())
+                .call(|observer| observer.last_value());
+            let last_value_result = client
+                .call_dry_run(&ink_e2e::alice(), &last_value, 0, None)
+                .await;
+            assert_eq!(last_value_result.return_value(), Some(true));
+
+            let failed_notifications = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.failed_notifications());
+            let failed_result = client
+                .call_dry_run(&ink_e2e::alice(), &failed_notifications, 0, None)
+                .await;
+            assert_eq!(failed_result.return_value(), 0);
+
+            Ok(())
+        }
+
+
+        /// We test that a created auction can be fetched.
+        #[ink_e2e::test]
+        async fn create_auction_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let subject = Hash::from([0x07; 32]);
+
+            // When
+            let create_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.create_auction(String::from("lot"), subject, 1, 0, 60_000, None));
+            let auction_id = client
+                .call(&ink_e2e::alice(), create_auction, 0, None)
+                .await
+                .expect("create_auction failed")
+                .return_value()
+                .expect("create_auction returned an error");
+
+            // Then
+            let get_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get_auction(auction_id));
+            let get_auction_result = client
+                .call_dry_run(&ink_e2e::alice(), &get_auction, 0, None)
+                .await;
+            let auction = get_auction_result.return_value().expect("auction exists");
+            assert_eq!(auction.name, "lot");
+            assert_eq!(auction.subject, subject);
+            assert_eq!(auction.status, Status::NotStarted);
+            assert!(!auction.finalized);
+
+            Ok(())
+        }
+
+
+        /// We test that a bid transfers funds and becomes the highest bid.
+        #[ink_e2e::test]
+        async fn place_bid_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let create_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None));
+            let auction_id = client
+                .call(&ink_e2e::alice(), create_auction, 0, None)
+                .await
+                .expect("create_auction failed")
+                .return_value()
+                .expect("create_auction returned an error");
+            let start_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.start_auction(auction_id));
+            client
+                .call(&ink_e2e::alice(), start_auction, 0, None)
+                .await
+                .expect("start_auction failed");
+
+            // When
+            let place_bid = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.place_bid(auction_id));
+            client
+                .call(&ink_e2e::bob(), place_bid, 1_000_000, None)
+                .await
+                .expect("place_bid failed");
+
+            // Then
+            let get_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get_auction(auction_id));
+            let get_auction_result = client
+                .call_dry_run(&ink_e2e::alice(), &get_auction, 0, None)
+                .await;
+            let auction = get_auction_result.return_value().expect("auction exists");
+            assert_eq!(auction.highest_bid, 1_000_000);
+            assert_eq!(
+                auction.highest_bidder,
+                Some(ink_e2e::account_id(ink_e2e::AccountKeyring::Bob))
+            );
+
+            let too_low = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.place_bid(auction_id));
+            let too_low_result = client
+                .call_dry_run(&ink_e2e::charlie(), &too_low, 1_000_000, None)
+                .await;
+            assert_eq!(too_low_result.return_value(), Err(Error::BidTooLow));
+
+            Ok(())
+        }
+
+        /// We test that an outbid bidder gets their funds back.
+        #[ink_e2e::test]
+        async fn withdraw_refund_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let create_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None));
+            let auction_id = client
+                .call(&ink_e2e::alice(), create_auction, 0, None)
+                .await
+                .expect("create_auction failed")
+                .return_value()
+                .expect("create_auction returned an error");
+            let start_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.start_auction(auction_id));
+            client
+                .call(&ink_e2e::alice(), start_auction, 0, None)
+                .await
+                .expect("start_auction failed");
+            let bob_bid = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.place_bid(auction_id));
+            client
+                .call(&ink_e2e::bob(), bob_bid, 1_000_000_000, None)
+                .await
+                .expect("bob's bid failed");
+            let charlie_bid = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.place_bid(auction_id));
+            client
+                .call(&ink_e2e::charlie(), charlie_bid, 2_000_000_000, None)
+                .await
+                .expect("charlie's bid failed");
+
+            // When
+            let bob_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let balance_before = client.balance(bob_account.clone()).await.expect("balance failed");
+            let withdraw_refund = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.withdraw_refund());
+            client
+                .call(&ink_e2e::bob(), withdraw_refund, 0, None)
+                .await
+                .expect("withdraw_refund failed");
+
+            // Then
+            let balance_after = client.balance(bob_account).await.expect("balance failed");
+            // Bob pays the call fees out of the refund, so allow for them.
+            assert!(balance_after > balance_before);
+            assert!(balance_after - balance_before <= 1_000_000_000);
+
+            Ok(())
+        }
+
+        /// We test the full auction lifecycle up to the seller's payout.
+        #[ink_e2e::test]
+        async fn finalize_auction_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let create_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None));
+            let auction_id = client
+                .call(&ink_e2e::alice(), create_auction, 0, None)
+                .await
+                .expect("create_auction failed")
+                .return_value()
+                .expect("create_auction returned an error");
+            let start_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.start_auction(auction_id));
+            client
+                .call(&ink_e2e::alice(), start_auction, 0, None)
+                .await
+                .expect("start_auction failed");
+            let bob_bid = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.place_bid(auction_id));
+            client
+                .call(&ink_e2e::bob(), bob_bid, 1_000_000_000, None)
+                .await
+                .expect("bob's bid failed");
+            let charlie_bid = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.place_bid(auction_id));
+            client
+                .call(&ink_e2e::charlie(), charlie_bid, 2_000_000_000, None)
+                .await
+                .expect("charlie's bid failed");
+            let begin_ending_period = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.begin_ending_period(auction_id));
+            client
+                .call(&ink_e2e::alice(), begin_ending_period, 0, None)
+                .await
+                .expect("begin_ending_period failed");
+            let end_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.end_auction(auction_id));
+            client
+                .call(&ink_e2e::alice(), end_auction, 0, None)
+                .await
+                .expect("end_auction failed");
+
+            // When
+            let alice_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let balance_before = client.balance(alice_account.clone()).await.expect("balance failed");
+            let finalize_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.finalize_auction(auction_id));
+            client
+                .call(&ink_e2e::alice(), finalize_auction, 0, None)
+                .await
+                .expect("finalize_auction failed");
+
+            // Then
+            let balance_after = client.balance(alice_account).await.expect("balance failed");
+            // Alice pays the call fees out of the payout, so allow for them.
+            assert!(balance_after > balance_before);
+            assert!(balance_after - balance_before <= 2_000_000_000);
+
+            let get_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get_auction(auction_id));
+            let get_auction_result = client
+                .call_dry_run(&ink_e2e::alice(), &get_auction, 0, None)
+                .await;
+            let auction = get_auction_result.return_value().expect("auction exists");
+            assert!(auction.finalized);
+
+            let finalize_again = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.finalize_auction(auction_id));
+            let finalize_again_result = client
+                .call_dry_run(&ink_e2e::alice(), &finalize_again, 0, None)
+                .await;
+            assert_eq!(finalize_again_result.return_value(), Err(Error::AlreadyFinalized));
+
+            Ok(())
+        }
+
+        /// We test that bids are rejected after the deadline and anyone can finalize.
+        #[ink_e2e::test]
+        async fn deadline_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            // A zero duration closes the auction from the next block on.
+            let create_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 0, None));
+            let auction_id = client
+                .call(&ink_e2e::alice(), create_auction, 0, None)
+                .await
+                .expect("create_auction failed")
+                .return_value()
+                .expect("create_auction returned an error");
+            let start_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.start_auction(auction_id));
+            client
+                .call(&ink_e2e::alice(), start_auction, 0, None)
+                .await
+                .expect("start_auction failed");
+
+            // When
+            let place_bid = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.place_bid(auction_id));
+            let place_bid_result = client
+                .call_dry_run(&ink_e2e::bob(), &place_bid, 1_000_000, None)
+                .await;
+
+            // Then
+            assert_eq!(place_bid_result.return_value(), Err(Error::AuctionClosed));
+
+            let finalize_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.finalize_auction(auction_id));
+            client
+                .call(&ink_e2e::bob(), finalize_auction, 0, None)
+                .await
+                .expect("finalize_auction failed");
+            let get_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get_auction(auction_id));
+            let get_auction_result = client
+                .call_dry_run(&ink_e2e::alice(), &get_auction, 0, None)
+                .await;
+            let auction = get_auction_result.return_value().expect("auction exists");
+            assert_eq!(auction.status, Status::Ended);
+            assert!(auction.finalized);
+
+            Ok(())
+        }
+
+        /// We test that `status` returns the decoded lifecycle state.
+        #[ink_e2e::test]
+        async fn status_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let create_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 60_000, None));
+            let auction_id = client
+                .call(&ink_e2e::alice(), create_auction, 0, None)
+                .await
+                .expect("create_auction failed")
+                .return_value()
+                .expect("create_auction returned an error");
+
+            // When
+            let start_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.start_auction(auction_id));
+            client
+                .call(&ink_e2e::alice(), start_auction, 0, None)
+                .await
+                .expect("start_auction failed");
+
+            // Then
+            let status = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.status(auction_id));
+            let status_result = client
+                .call_dry_run(&ink_e2e::bob(), &status, 0, None)
+                .await;
+            assert_eq!(status_result.return_value(), Some(Status::OpeningPeriod));
+
+            let unknown = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.status(auction_id + 1));
+            let unknown_result = client
+                .call_dry_run(&ink_e2e::bob(), &unknown, 0, None)
+                .await;
+            assert_eq!(unknown_result.return_value(), None);
+
+            Ok(())
+        }
+
+        /// We test that refunds from two auctions are withdrawn in one call.
+        #[ink_e2e::test]
+        async fn withdraw_all_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let mut auction_ids = Vec::new();
+            for subject in [[0x07; 32], [0x08; 32]] {
+                let create_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                    .call(|foobar| foobar.create_auction(String::from("lot"), Hash::from(subject), 1, 0, 60_000, None));
+                let auction_id = client
+                    .call(&ink_e2e::alice(), create_auction, 0, None)
+                    .await
+                    .expect("create_auction failed")
+                    .return_value()
+                    .expect("create_auction returned an error");
+                let start_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                    .call(|foobar| foobar.start_auction(auction_id));
+                client
+                    .call(&ink_e2e::alice(), start_auction, 0, None)
+                    .await
+                    .expect("start_auction failed");
+                auction_ids.push(auction_id);
+            }
+            for auction_id in auction_ids {
+                let bob_bid = build_message::<FoobarRef>(contract_account_id.clone())
+                    .call(|foobar| foobar.place_bid(auction_id));
+                client
+                    .call(&ink_e2e::bob(), bob_bid, 1_000_000_000_000, None)
+                    .await
+                    .expect("bob's bid failed");
+                let charlie_bid = build_message::<FoobarRef>(contract_account_id.clone())
+                    .call(|foobar| foobar.place_bid(auction_id));
+                client
+                    .call(&ink_e2e::charlie(), charlie_bid, 2_000_000_000_000, None)
+                    .await
+                    .expect("charlie's bid failed");
+            }
+            let bob_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let refundable_of = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.refundable_of(bob_account.clone()));
+            let refundable = client
+                .call_dry_run(&ink_e2e::bob(), &refundable_of, 0, None)
+                .await
+                .return_value();
+            assert_eq!(refundable, 2_000_000_000_000);
+
+            // When
+            let balance_before = client.balance(bob_account.clone()).await.expect("balance failed");
+            let withdraw_all = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.withdraw_all());
+            client
+                .call(&ink_e2e::bob(), withdraw_all, 0, None)
+                .await
+                .expect("withdraw_all failed");
+
+            // Then
+            let balance_after = client.balance(bob_account).await.expect("balance failed");
+            // Bob pays the call fees out of the withdrawal, which are far
+            // below a tenth of it.
+            let received = balance_after - balance_before;
+            assert!(received <= refundable);
+            assert!(received > refundable - refundable / 10);
+
+            Ok(())
+        }
+
+        /// We test a complete two-bidder auction running into its deadline.
+        #[ink_e2e::test]
+        async fn two_bidder_auction_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let create_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.create_auction(String::from("lot"), Hash::from([0x07; 32]), 1, 0, 10_000, None));
+            let auction_id = client
+                .call(&ink_e2e::alice(), create_auction, 0, None)
+                .await
+                .expect("create_auction failed")
+                .return_value()
+                .expect("create_auction returned an error");
+            let start_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.start_auction(auction_id));
+            client
+                .call(&ink_e2e::alice(), start_auction, 0, None)
+                .await
+                .expect("start_auction failed");
+
+            // When
+            let bob_bid = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.place_bid(auction_id));
+            client
+                .call(&ink_e2e::bob(), bob_bid, 1_000_000_000, None)
+                .await
+                .expect("bob's bid failed");
+            let charlie_bid = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.place_bid(auction_id));
+            client
+                .call(&ink_e2e::charlie(), charlie_bid, 2_000_000_000, None)
+                .await
+                .expect("charlie's bid failed");
+
+            // Blocks are only produced on demand, so keep flipping until one
+            // lands past the deadline.
+            loop {
+                let time_remaining = build_message::<FoobarRef>(contract_account_id.clone())
+                    .call(|foobar| foobar.time_remaining(auction_id));
+                let remaining = client
+                    .call_dry_run(&ink_e2e::alice(), &time_remaining, 0, None)
+                    .await
+                    .return_value();
+                if remaining == 0 {
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(remaining));
+                let flip = build_message::<FoobarRef>(contract_account_id.clone())
+                    .call(|foobar| foobar.flip());
+                client
+                    .call(&ink_e2e::alice(), flip, 0, None)
+                    .await
+                    .expect("flip failed");
+            }
+
+            let alice_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let balance_before = client.balance(alice_account.clone()).await.expect("balance failed");
+            // Bob finalizes, so the seller's balance only changes by the payout.
+            let finalize_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.finalize_auction(auction_id));
+            let finalize_result = client
+                .call(&ink_e2e::bob(), finalize_auction, 0, None)
+                .await
+                .expect("finalize_auction failed");
+
+            // Then
+            let balance_after = client.balance(alice_account).await.expect("balance failed");
+            assert_eq!(balance_after - balance_before, 2_000_000_000);
+
+            let get_auction = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get_auction(auction_id));
+            let auction = client
+                .call_dry_run(&ink_e2e::alice(), &get_auction, 0, None)
+                .await
+                .return_value()
+                .expect("auction exists");
+            assert_eq!(auction.highest_bidder, Some(ink_e2e::account_id(ink_e2e::AccountKeyring::Charlie)));
+            assert_eq!(auction.highest_bid, 2_000_000_000);
+            assert!(auction.finalized);
+
+            let bob_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let refundable_of = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.refundable_of(bob_account.clone()));
+            let refundable = client
+                .call_dry_run(&ink_e2e::bob(), &refundable_of, 0, None)
+                .await
+                .return_value();
+            assert_eq!(refundable, 1_000_000_000);
+
+            // `ContractEmitted` carries the contract account followed by the
+            // encoded contract event.
+            let finalized = finalize_result
+                .events
+                .iter()
+                .map(|event| event.expect("invalid event"))
+                .filter(|event| event.pallet_name() == "Contracts" && event.variant_name() == "ContractEmitted")
+                .map(|event| {
+                    let (_, data) = <(AccountId, Vec<u8>) as scale::Decode>::decode(&mut event.field_bytes())
+                        .expect("invalid ContractEmitted event");
+                    <Event as scale::Decode>::decode(&mut &data[..])
+                        .expect("invalid contract event")
+                })
+                .any(|event| matches!(event, Event::AuctionFinalized(AuctionFinalized { id, .. }) if id == auction_id));
+            assert!(finalized);
+
+            Ok(())
+        }
+
+        /// We test that a message set by the owner can be read back.
+        #[ink_e2e::test]
+        async fn set_message_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // When
+            let set_message = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.set_message(String::from("grüß dich")));
+            client
+                .call(&ink_e2e::alice(), set_message, 0, None)
+                .await
+                .expect("set_message failed");
+
+            // Then
+            let get_message = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get_message());
+            let get_message_result = client
+                .call_dry_run(&ink_e2e::bob(), &get_message, 0, None)
+                .await;
+            assert_eq!(get_message_result.return_value(), "grüß dich");
+
+            Ok(())
+        }
+
+        /// We test that deposited value shows up in both balance views.
+        #[ink_e2e::test]
+        async fn deposit_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let free_balance = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.contract_free_balance());
+            let balance_before = client
+                .call_dry_run(&ink_e2e::bob(), &free_balance, 0, None)
+                .await
+                .return_value();
+
+            // When
+            let deposit = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.deposit());
+            client
+                .call(&ink_e2e::bob(), deposit, 1_000_000_000, None)
+                .await
+                .expect("deposit failed");
+
+            // Then
+            let total_received = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.total_received());
+            let total_received_result = client
+                .call_dry_run(&ink_e2e::bob(), &total_received, 0, None)
+                .await;
+            assert_eq!(total_received_result.return_value(), 1_000_000_000);
+            let free_balance = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.contract_free_balance());
+            let balance_after = client
+                .call_dry_run(&ink_e2e::bob(), &free_balance, 0, None)
+                .await
+                .return_value();
+            assert_eq!(balance_after - balance_before, 1_000_000_000);
+
+            Ok(())
+        }
+
+        /// We test that `contract_balance` reflects value sent to the contract.
+        #[ink_e2e::test]
+        async fn contract_balance_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let contract_balance = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.contract_balance());
+            let balance_before = client
+                .call_dry_run(&ink_e2e::bob(), &contract_balance, 0, None)
+                .await
+                .return_value();
+
+            // When
+            let deposit = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.deposit());
+            client
+                .call(&ink_e2e::bob(), deposit, 1_000_000, None)
+                .await
+                .expect("deposit failed");
+
+            // Then
+            let balance_after = client
+                .call_dry_run(&ink_e2e::bob(), &contract_balance, 0, None)
+                .await
+                .return_value();
+            assert_eq!(balance_after - balance_before, 1_000_000);
+            let minimum_balance = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.minimum_balance());
+            let minimum = client
+                .call_dry_run(&ink_e2e::bob(), &minimum_balance, 0, None)
+                .await
+                .return_value();
+            let spendable_balance = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.spendable_balance());
+            let spendable = client
+                .call_dry_run(&ink_e2e::bob(), &spendable_balance, 0, None)
+                .await
+                .return_value();
+            assert_eq!(spendable, balance_after - minimum);
+
+            Ok(())
+        }
+
+        /// We test that value sent to an unknown selector is only kept as a
+        /// donation once donations are accepted.
+        #[ink_e2e::test]
+        async fn receive_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let unknown = build_message::<FoobarRef>(contract_account_id.clone()).call(|_| {
+                build_call::<Environment>()
+                    .call(contract_account_id.clone())
+                    .exec_input(ExecutionInput::new(Selector::new([0xde, 0xad, 0xbe, 0xef])))
+                    .returns::<Result<(), Error>>()
+            });
+            let rejected_result = client
+                .call_dry_run(&ink_e2e::bob(), &unknown, 1_000, None)
+                .await;
+            assert_eq!(rejected_result.return_value(), Err(Error::DirectTransfersNotAccepted));
+            let set_accept_donations = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.set_accept_donations(true));
+            client
+                .call(&ink_e2e::alice(), set_accept_donations, 0, None)
+                .await
+                .expect("set_accept_donations failed");
+
+            // When
+            client
+                .call(&ink_e2e::bob(), unknown, 1_000, None)
+                .await
+                .expect("donation failed");
+
+            // Then
+            let donations = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.donations());
+            let donations_result = client.call_dry_run(&ink_e2e::bob(), &donations, 0, None).await;
+            assert_eq!(donations_result.return_value(), 1_000);
+
+            Ok(())
+        }
+
+        /// We test that value sent to `receive` under strict value checks is
+        /// refunded by the revert.
+        #[ink_e2e::test]
+        async fn strict_value_checks_work(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new_with_strict_value_checks(false, true);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let set_accept_donations = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.set_accept_donations(true));
+            client
+                .call(&ink_e2e::alice(), set_accept_donations, 0, None)
+                .await
+                .expect("set_accept_donations failed");
+            let contract_balance_before = client
+                .balance(contract_account_id.clone())
+                .await
+                .expect("balance failed");
+
+            // When
+            let unknown = build_message::<FoobarRef>(contract_account_id.clone()).call(|_| {
+                build_call::<Environment>()
+                    .call(contract_account_id.clone())
+                    .exec_input(ExecutionInput::new(Selector::new([0xde, 0xad, 0xbe, 0xef])))
+                    .returns::<Result<(), Error>>()
+            });
+            let dry_run_result = client
+                .call_dry_run(&ink_e2e::bob(), &unknown, 1_000, None)
+                .await;
+            let call_result = client.call(&ink_e2e::bob(), unknown, 1_000, None).await;
+
+            // Then
+            assert_eq!(dry_run_result.return_value(), Err(Error::UnexpectedPayment));
+            assert!(call_result.is_err());
+            let contract_balance_after = client
+                .balance(contract_account_id.clone())
+                .await
+                .expect("balance failed");
+            assert_eq!(contract_balance_after, contract_balance_before);
+            let donations = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.donations());
+            let donations_result = client.call_dry_run(&ink_e2e::bob(), &donations, 0, None).await;
+            assert_eq!(donations_result.return_value(), 0);
+
+            Ok(())
+        }
+
+        /// We test that the owner can sweep out a plain balance transfer.
+        #[ink_e2e::test]
+        async fn transfer_out_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            use ink_e2e::subxt::dynamic::Value;
+
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let deposit = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.deposit());
+            client
+                .call(&ink_e2e::charlie(), deposit, 1_000, None)
+                .await
+                .expect("deposit failed");
+            let transfer = vec![
+                Value::unnamed_variant("Id", [Value::from_bytes(&contract_account_id)]),
+                Value::u128(5_000),
+            ];
+            client
+                .runtime_call(&ink_e2e::charlie(), "Balances", "transfer_allow_death", transfer)
+                .await
+                .expect("transfer failed");
+            let sweepable_balance = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.sweepable_balance());
+            let sweepable = client
+                .call_dry_run(&ink_e2e::alice(), &sweepable_balance, 0, None)
+                .await
+                .return_value();
+            assert_eq!(sweepable, 5_000);
+            let bob_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let balance_before = client.balance(bob_account.clone()).await.expect("balance failed");
+
+            // When
+            let transfer_out = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.transfer_out(bob_account.clone(), 5_000));
+            client
+                .call(&ink_e2e::alice(), transfer_out, 0, None)
+                .await
+                .expect("transfer_out failed");
+
+            // Then
+            let balance_after = client.balance(bob_account).await.expect("balance failed");
+            assert_eq!(balance_after - balance_before, 5_000);
+            let sweepable = client
+                .call_dry_run(&ink_e2e::alice(), &sweepable_balance, 0, None)
+                .await
+                .return_value();
+            assert_eq!(sweepable, 0);
+            let deposit_of = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.deposit_of(ink_e2e::account_id(ink_e2e::AccountKeyring::Charlie)));
+            let deposit_of_result = client.call_dry_run(&ink_e2e::alice(), &deposit_of, 0, None).await;
+            assert_eq!(deposit_of_result.return_value(), 1_000);
+
+            Ok(())
+        }
+
+        /// We test that `runtime_transfer` moves a plain balance transfer on
+        /// through pallet-balances.
+        ///
+        /// `ink_e2e` builds the contract with its default features, so this
+        /// test needs `call-runtime` among them and a node that allows
+        /// `call_runtime`, like substrate-contracts-node.
+        #[cfg(feature = "call-runtime")]
+        #[ink_e2e::test]
+        async fn runtime_transfer_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            use ink_e2e::subxt::dynamic::Value;
+
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let transfer = vec![
+                Value::unnamed_variant("Id", [Value::from_bytes(&contract_account_id)]),
+                Value::u128(5_000),
+            ];
+            client
+                .runtime_call(&ink_e2e::charlie(), "Balances", "transfer_allow_death", transfer)
+                .await
+                .expect("transfer failed");
+            let bob_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let balance_before = client.balance(bob_account.clone()).await.expect("balance failed");
+
+            // When
+            let runtime_transfer = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.runtime_transfer(bob_account.clone(), 5_000));
+            let runtime_transfer_result = client
+                .call(&ink_e2e::alice(), runtime_transfer, 0, None)
+                .await
+                .expect("runtime_transfer failed");
+
+            // Then
+            assert_eq!(runtime_transfer_result.return_value(), Ok(()));
+            let balance_after = client.balance(bob_account).await.expect("balance failed");
+            assert_eq!(balance_after - balance_before, 5_000);
+            assert!(runtime_transfer_result
+                .events
+                .iter()
+                .map(|event| event.expect("invalid event"))
+                .any(|event| event.pallet_name() == "Balances" && event.variant_name() == "Transfer"));
+
+            Ok(())
+        }
+
+        /// We test that terminating pays out the contract's balance and removes
+        /// the contract.
+        #[ink_e2e::test]
+        async fn terminate_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let paid_flip = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.paid_flip());
+            client
+                .call(&ink_e2e::charlie(), paid_flip, 1_000_000, None)
+                .await
+                .expect("paid_flip failed");
+            let contract_balance = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.contract_balance());
+            let contract_balance = client
+                .call_dry_run(&ink_e2e::alice(), &contract_balance, 0, None)
+                .await
+                .return_value();
+            let bob_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let balance_before = client.balance(bob_account.clone()).await.expect("balance failed");
+
+            // When
+            let terminate = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.terminate(bob_account.clone()));
+            client
+                .call(&ink_e2e::alice(), terminate, 0, None)
+                .await
+                .expect("terminate failed");
 
-        my_string: String,
+            // Then
+            let balance_after = client.balance(bob_account).await.expect("balance failed");
+            assert!(balance_after - balance_before >= contract_balance);
+            let get = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get());
+            assert!(client.call(&ink_e2e::alice(), get, 0, None).await.is_err());
 
-        my_vector: Vec<u32>,
+            Ok(())
+        }
 
-        my_account: AccountId,
+        /// We test that deposits from several accounts are tracked separately.
+        #[ink_e2e::test]
+        async fn deposit_of_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let bob_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let charlie_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Charlie);
 
-        my_balance: Balance,
-        
-        my_hash: Hash,
-    }
+            // When
+            let deposit = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.deposit());
+            client
+                .call(&ink_e2e::bob(), deposit, 1_000, None)
+                .await
+                .expect("deposit failed");
+            let deposit = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.deposit());
+            client
+                .call(&ink_e2e::charlie(), deposit, 2_500, None)
+                .await
+                .expect("deposit failed");
 
-    pub enum Status {
-        NotSTarted,
-        OpeningPeriod,
-    }
+            // Then
+            let deposit_of = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.deposit_of(bob_account));
+            let deposit_of_result = client.call_dry_run(&ink_e2e::alice(), &deposit_of, 0, None).await;
+            assert_eq!(deposit_of_result.return_value(), 1_000);
+            let deposit_of = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.deposit_of(charlie_account));
+            let deposit_of_result = client.call_dry_run(&ink_e2e::alice(), &deposit_of, 0, None).await;
+            assert_eq!(deposit_of_result.return_value(), 2_500);
+            let total_deposits = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.total_deposits());
+            let total_deposits_result = client.call_dry_run(&ink_e2e::alice(), &total_deposits, 0, None).await;
+            assert_eq!(total_deposits_result.return_value(), 3_500);
 
-    pub struct Auction {
-        name: String,
-        subject: Hash,
-        status: Status,
-        finalized: bool,
-        vector: Vec<u8>,
-    }
+            Ok(())
+        }
 
-    #[ink(event)]
-    pub struct Created {
-        #[ink(topic)]   // Indexed element
-        message: String,
-    }
+        /// We test that a populated `MyStruct` round-trips through the ABI.
+        #[ink_e2e::test]
+        async fn my_struct_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let my_struct = MyStruct {
+                value: true,
+                my_string: String::from("hello"),
+                my_vector: vec![1, 2, 3],
+                my_account: ink_e2e::account_id(ink_e2e::AccountKeyring::Bob),
+                my_balance: 1_000,
+                my_hash: Hash::from([0x07; 32]),
+            };
 
-    #[ink(event)]
-    pub struct Flipped {
-        #[ink(topic)]   // Indexed element
-        flip: bool,
+            // When
+            let set_my_struct = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.set_my_struct(my_struct.clone()));
+            client
+                .call(&ink_e2e::alice(), set_my_struct, 0, None)
+                .await
+                .expect("set_my_struct failed");
 
-    }
+            // Then
+            let get_my_struct = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get_my_struct());
+            let get_my_struct_result = client
+                .call_dry_run(&ink_e2e::bob(), &get_my_struct, 0, None)
+                .await;
+            assert_eq!(get_my_struct_result.return_value(), my_struct);
 
-    #[ink(storage)]
-    pub struct Foobar {
-        value: bool,
-    }
-    
-    impl Foobar {
-        /// Constructor that initializes the `bool` value to the given `init_value`.
-        #[ink(constructor)]
-        pub fn new(init_value: bool) -> Self {
-            Self::env().emit_event(Created {
-                message: String::from("Foobar created")
-            });
-            Self {
-                value: init_value,
-            }
+            Ok(())
         }
 
-        /// Constructor that initializes the `bool` value to `false`.
-        ///
-        /// Constructors can delegate to other constructors.
-        #[ink(constructor)]
-        pub fn default() -> Self {
-            Self::new(Default::default())
+        /// We test that only the deployer can call privileged messages.
+        #[ink_e2e::test]
+        async fn owner_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let owner = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.owner());
+            let owner_result = client.call_dry_run(&ink_e2e::bob(), &owner, 0, None).await;
+            assert_eq!(
+                owner_result.return_value(),
+                ink_e2e::account_id(ink_e2e::AccountKeyring::Alice)
+            );
+
+            // When
+            let pause = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.pause());
+            let bob_result = client.call_dry_run(&ink_e2e::bob(), &pause, 0, None).await;
+
+            // Then
+            assert_eq!(bob_result.return_value(), Err(Error::NotOwner));
+            let alice_result = client.call_dry_run(&ink_e2e::alice(), &pause, 0, None).await;
+            assert_eq!(alice_result.return_value(), Ok(()));
+
+            Ok(())
         }
 
-        /// A message that can be called on instantiated contracts.
-        /// This one flips the value of the stored `bool` from `true`
-        /// to `false` and vice versa.
-        /// 
-        /// Selector calculation
-        /// 
-        /// 1. Grab the name of the message or constructor
-        ///  
-        /// 2. Compute the BLACKE2 hash of the name
-        /// 
-        /// 3. Take the first 4 bytes of the hash as the selector
-        /// 
-        /// BLACKE2(flip) = 0x633aa551........................
-        /// 
-        #[ink(message)]
-        pub fn flip(&mut self) {
-            self.value = !self.value;
+        /// We test that the owner can switch to lightweight flip events.
+        #[ink_e2e::test]
+        async fn set_verbose_events_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let set_verbose_events = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.set_verbose_events(false));
+            let bob_result = client
+                .call_dry_run(&ink_e2e::bob(), &set_verbose_events, 0, None)
+                .await;
+            assert_eq!(bob_result.return_value(), Err(Error::NotOwner));
 
-            self.env().emit_event(Flipped {
-                flip: self.value
-            });
+            // When
+            client
+                .call(&ink_e2e::alice(), set_verbose_events, 0, None)
+                .await
+                .expect("set_verbose_events failed");
+
+            // Then
+            let verbose_events = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.verbose_events());
+            let verbose_events_result = client
+                .call_dry_run(&ink_e2e::bob(), &verbose_events, 0, None)
+                .await;
+            assert!(!verbose_events_result.return_value());
+
+            Ok(())
         }
 
-        /// Simply returns the current value of our `bool`.
-        #[ink(message)]
-        pub fn get(&self) -> bool {
-            self.value
+        /// We test that instantiating through `new` and `default` emits one
+        /// `Created` event each, tagged with the constructor that ran.
+        #[ink_e2e::test]
+        async fn created_event_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let created_events = |events: &ink_e2e::subxt::blocks::ExtrinsicEvents<C>| {
+                events
+                    .iter()
+                    .map(|event| event.expect("invalid event"))
+                    .filter(|event| event.pallet_name() == "Contracts" && event.variant_name() == "ContractEmitted")
+                    .filter_map(|event| {
+                        let (_, data) = <(AccountId, Vec<u8>) as scale::Decode>::decode(&mut event.field_bytes())
+                            .expect("invalid ContractEmitted event");
+                        match <Event as scale::Decode>::decode(&mut &data[..]).expect("invalid contract event") {
+                            Event::Created(created) => Some(created),
+                            _ => None,
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            };
+            let alice_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+
+            // When
+            let new_result = client
+                .instantiate("foobar", &ink_e2e::alice(), FoobarRef::new(true), 0, None)
+                .await
+                .expect("instantiate failed");
+            let default_result = client
+                .instantiate("foobar", &ink_e2e::alice(), FoobarRef::default(), 0, None)
+                .await
+                .expect("instantiate failed");
+
+            // Then
+            let created = created_events(&new_result.events);
+            assert_eq!(created.len(), 1);
+            assert_eq!(created[0].creator, alice_account);
+            assert_eq!(created[0].constructor, CONSTRUCTOR_NEW);
+            assert!(created[0].init_value);
+
+            let created = created_events(&default_result.events);
+            assert_eq!(created.len(), 1);
+            assert_eq!(created[0].creator, alice_account);
+            assert_eq!(created[0].constructor, CONSTRUCTOR_DEFAULT);
+            assert!(!created[0].init_value);
+
+            Ok(())
         }
-    }
 
-    /// Unit tests in Rust are normally defined within such a `#[cfg(test)]`
-    /// module and test functions are marked with a `#[test]` attribute.
-    /// The below code is technically just normal Rust code.
-    #[cfg(test)]
-    mod tests {
-        /// Imports all the definitions from the outer scope so we can use them here.
-        use super::*;
+        /// We test that `try_new` refuses to instantiate with an invalid
+        /// configuration and deploys a valid one.
+        #[ink_e2e::test]
+        async fn try_new_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // When
+            let invalid_result = client
+                .instantiate("foobar", &ink_e2e::alice(), FoobarRef::try_new(true, 0, 5_000), 0, None)
+                .await;
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), FoobarRef::try_new(true, 10, 250), 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
 
-        /// We test if the default constructor does its job.
-        #[ink::test]
-        fn default_works() {
-            let foobar = Foobar::default();
-            assert_eq!(foobar.get(), false);
+            // Then
+            assert!(invalid_result.is_err());
+            let fee_bps = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.fee_bps());
+            let fee_bps_result = client.call_dry_run(&ink_e2e::alice(), &fee_bps, 0, None).await;
+            assert_eq!(fee_bps_result.return_value(), 250);
+            let get = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get());
+            let get_result = client.call_dry_run(&ink_e2e::alice(), &get, 0, None).await;
+            assert!(get_result.return_value());
+
+            Ok(())
         }
 
-        /// We test a simple use case of our contract.
-        #[ink::test]
-        fn it_works() {
-            let mut foobar = Foobar::new(false);
-            assert_eq!(foobar.get(), false);
-            foobar.flip();
-            assert_eq!(foobar.get(), true);
+        /// We test that `new_funded` stores the endowment it was sent and
+        /// rejects one below the minimum.
+        #[ink_e2e::test]
+        async fn new_funded_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // When
+            let constructor = FoobarRef::new_funded(false, 1_000_000);
+            let too_low_result = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 999_999, None)
+                .await;
+            let constructor = FoobarRef::new_funded(false, 1_000_000);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 1_500_000, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // Then
+            assert!(too_low_result.is_err());
+            let initial_endowment = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.initial_endowment());
+            let initial_endowment_result = client
+                .call_dry_run(&ink_e2e::alice(), &initial_endowment, 0, None)
+                .await;
+            assert_eq!(initial_endowment_result.return_value(), 1_500_000);
+
+            Ok(())
         }
-    }
 
+        /// We test that `paid_flip` keeps the transferred value as fees.
+        #[ink_e2e::test]
+        async fn paid_flip_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new_with_flip_fee(false, 1_000, false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let balance_before = client.balance(contract_account_id.clone()).await.expect("balance failed");
+            let paid_flip = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.paid_flip());
+            let underpaid_result = client
+                .call_dry_run(&ink_e2e::bob(), &paid_flip, 999, None)
+                .await;
+            assert_eq!(underpaid_result.return_value(), Err(Error::FeeTooLow));
 
-    /// This is how you'd write end-to-end (E2E) or integration tests for ink! contracts.
-    ///
-    /// When running these you need to make sure that you:
-    /// - Compile the tests with the `e2e-tests` feature flag enabled (`--features e2e-tests`)
-    /// - Are running a Substrate node which contains `pallet-contracts` in the background
-    #[cfg(all(test, feature = "e2e-tests"))]
-    mod e2e_tests {
-        /// Imports all the definitions from the outer scope so we can use them here.
-        use super::*;
+            // When
+            client
+                .call(&ink_e2e::bob(), paid_flip, 1_500, None)
+                .await
+                .expect("paid_flip failed");
 
-        /// A helper function used for calling contract messages.
-        use ink_e2e::build_message;
+            // Then
+            let balance_after = client.balance(contract_account_id.clone()).await.expect("balance failed");
+            assert_eq!(balance_after, balance_before + 1_500);
+            let collected_fees = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.collected_fees());
+            let collected_fees_result = client
+                .call_dry_run(&ink_e2e::bob(), &collected_fees, 0, None)
+                .await;
+            assert_eq!(collected_fees_result.return_value(), 1_500);
+            let get = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.get());
+            let get_result = client.call_dry_run(&ink_e2e::bob(), &get, 0, None).await;
+            assert!(get_result.return_value());
 
-        /// The End-to-End test `Result` type.
-        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+            Ok(())
+        }
 
-        /// We test that we can upload and instantiate the contract using its default constructor.
+        /// We test that the owner and the treasury each receive their share
+        /// of the `paid_flip` fees.
         #[ink_e2e::test]
-        async fn default_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+        async fn treasury_split_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
             // Given
-            let constructor = FoobarRef::default();
+            let bob_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let alice_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let constructor = FoobarRef::new_with_treasury(false, bob_account.clone(), 2_000);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            for value in [1_000, 2_000, 3_001] {
+                let paid_flip = build_message::<FoobarRef>(contract_account_id.clone())
+                    .call(|foobar| foobar.paid_flip());
+                client
+                    .call(&ink_e2e::charlie(), paid_flip, value, None)
+                    .await
+                    .expect("paid_flip failed");
+            }
 
             // When
+            let withdraw_treasury_fees = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.withdraw_treasury_fees());
+            let treasury_result = client
+                .call(&ink_e2e::bob(), withdraw_treasury_fees, 0, None)
+                .await
+                .expect("withdraw_treasury_fees failed");
+            let withdraw_owner_fees = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.withdraw_owner_fees());
+            let owner_result = client
+                .call(&ink_e2e::alice(), withdraw_owner_fees, 0, None)
+                .await
+                .expect("withdraw_owner_fees failed");
+
+            // Then
+            // 20% of 6001 is 1200.2, the remainder is rounded up for the treasury.
+            assert_eq!(treasury_result.return_value(), Ok(1_201));
+            assert_eq!(owner_result.return_value(), Ok(4_800));
+            // Callers pay transaction fees, so check the payouts themselves.
+            let payouts = |events: &ink_e2e::subxt::blocks::ExtrinsicEvents<C>| {
+                events
+                    .iter()
+                    .map(|event| event.expect("invalid event"))
+                    .filter(|event| event.pallet_name() == "Balances" && event.variant_name() == "Transfer")
+                    .map(|event| {
+                        <(AccountId, AccountId, Balance) as scale::Decode>::decode(&mut event.field_bytes())
+                            .expect("invalid Transfer event")
+                    })
+                    .filter(|(from, _, _)| *from == contract_account_id)
+                    .map(|(_, to, amount)| (to, amount))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(payouts(&treasury_result.events), vec![(bob_account, 1_201)]);
+            assert_eq!(payouts(&owner_result.events), vec![(alice_account, 4_800)]);
+            let collected_fees = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.collected_fees());
+            let collected_fees_result = client
+                .call_dry_run(&ink_e2e::alice(), &collected_fees, 0, None)
+                .await;
+            assert_eq!(collected_fees_result.return_value(), 0);
+
+            Ok(())
+        }
+
+        /// We test that the owner can withdraw `paid_flip` fees to another account.
+        #[ink_e2e::test]
+        async fn withdraw_fees_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new_with_flip_fee(false, 1_000, false);
             let contract_account_id = client
                 .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
                 .await
                 .expect("instantiate failed")
                 .account_id;
+            let paid_flip = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.paid_flip());
+            client
+                .call(&ink_e2e::charlie(), paid_flip, 5_000, None)
+                .await
+                .expect("paid_flip failed");
+            let bob_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let balance_before = client.balance(bob_account.clone()).await.expect("balance failed");
+
+            // When
+            let withdraw_fees = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.withdraw_fees(Some(bob_account.clone()), 3_000));
+            client
+                .call(&ink_e2e::alice(), withdraw_fees, 0, None)
+                .await
+                .expect("withdraw_fees failed");
 
             // Then
-            let get = build_message::<FoobarRef>(contract_account_id.clone())
-                .call(|foobar| foobar.get());
-            let get_result = client.call_dry_run(&ink_e2e::alice(), &get, 0, None).await;
-            assert!(matches!(get_result.return_value(), false));
+            let balance_after = client.balance(bob_account).await.expect("balance failed");
+            assert_eq!(balance_after - balance_before, 3_000);
+            let collected_fees = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.collected_fees());
+            let collected_fees_result = client
+                .call_dry_run(&ink_e2e::alice(), &collected_fees, 0, None)
+                .await;
+            assert_eq!(collected_fees_result.return_value(), 2_000);
 
             Ok(())
         }
 
-        /// We test that we can read and write a value from the on-chain contract contract.
+        /// We test that `//Alice` can have a relayer flip with an sr25519 signature.
+        ///
+        /// `ink_e2e` builds the contract with its default features, so this
+        /// test needs `sr25519` among them and a node exposing the unstable
+        /// `sr25519_verify` host function, like substrate-contracts-node.
+        #[cfg(feature = "sr25519")]
         #[ink_e2e::test]
-        async fn it_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+        async fn sr25519_flip_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
             // Given
             let constructor = FoobarRef::new(false);
             let contract_account_id = client
-                .instantiate("foobar", &ink_e2e::bob(), constructor, 0, None)
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
                 .await
                 .expect("instantiate failed")
                 .account_id;
+            let alice_pubkey = ink_e2e::alice().public_key().0;
+            let payload = (contract_account_id.clone(), 0u64, FLIP_ACTION).encode();
+            let signature = ink_e2e::alice().sign(&payload).0;
+
+            // When
+            let sr25519_flip = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.sr25519_flip(alice_pubkey, 0, signature));
+            client
+                .call(&ink_e2e::bob(), sr25519_flip, 0, None)
+                .await
+                .expect("sr25519_flip failed");
+            let replay = build_message::<FoobarRef>(contract_account_id.clone())
+                .call(|foobar| foobar.sr25519_flip(alice_pubkey, 0, signature));
+            let replay_result = client.call_dry_run(&ink_e2e::bob(), &replay, 0, None).await;
 
+            // Then
             let get = build_message::<FoobarRef>(contract_account_id.clone())
                 .call(|foobar| foobar.get());
             let get_result = client.call_dry_run(&ink_e2e::bob(), &get, 0, None).await;
-            assert!(matches!(get_result.return_value(), false));
+            assert!(get_result.return_value());
+            assert_eq!(replay_result.return_value(), Err(Error::NonceTooLow));
+
+            Ok(())
+        }
+    }
+
+    /// End-to-End tests of the `chain-ext` build. They need a node whose
+    /// runtime provides the randomness chain extension described at
+    /// `FETCH_RANDOM_FUNC_ID`; the default substrate-contracts-node doesn't.
+    #[cfg(all(test, feature = "e2e-tests", feature = "chain-ext"))]
+    mod chain_ext_e2e_tests {
+        /// Imports all the definitions from the outer scope so we can use them here.
+        use super::*;
+
+        /// Builds messages for the contract in `FoobarEnvironment`.
+        type MessageBuilder = ink_e2e::MessageBuilder<crate::FoobarEnvironment, FoobarRef>;
+
+        /// The End-to-End test `Result` type.
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        /// We test that `random_flip` gets its bit from the runtime.
+        #[ink_e2e::test(environment = crate::FoobarEnvironment)]
+        async fn random_flip_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = FoobarRef::new(false);
+            let contract_account_id = client
+                .instantiate("foobar", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
 
             // When
-            let flip = build_message::<FoobarRef>(contract_account_id.clone())
-                .call(|foobar| foobar.flip());
-            let _flip_result = client
-                .call(&ink_e2e::bob(), flip, 0, None)
+            let random_flip = MessageBuilder::from_account_id(contract_account_id.clone())
+                .call(|foobar| foobar.random_flip(b"seed".to_vec()));
+            let random_flip_result = client
+                .call(&ink_e2e::bob(), random_flip, 0, None)
                 .await
-                .expect("flip failed");
+                .expect("random_flip failed");
 
             // Then
-            let get = build_message::<FoobarRef>(contract_account_id.clone())
+            let bit = random_flip_result.return_value().expect("no randomness");
+            let get = MessageBuilder::from_account_id(contract_account_id.clone())
                 .call(|foobar| foobar.get());
             let get_result = client.call_dry_run(&ink_e2e::bob(), &get, 0, None).await;
-            assert!(matches!(get_result.return_value(), true));
+            assert_eq!(get_result.return_value(), bit);
 
             Ok(())
         }
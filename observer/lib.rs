@@ -0,0 +1,65 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// A minimal contract that gets notified by `foobar` whenever its value flips.
+///
+/// It implements the `foobar::OnFlip` interface by using that trait message's
+/// selector, so it does not need to depend on the `foobar` crate.
+#[ink::contract]
+mod observer {
+    #[ink(storage)]
+    pub struct Observer {
+        /// The value reported by the latest notification, if any.
+        last_value: Option<bool>,
+        /// How many notifications have been received.
+        notifications: u32,
+    }
+
+    impl Observer {
+        /// Constructor that starts without any notification.
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                last_value: None,
+                notifications: 0,
+            }
+        }
+
+        /// Records a flip notification.
+        ///
+        /// The selector equals BLAKE2("OnFlip::on_flip")[0..4], which is what
+        /// `foobar` calls for `OnFlip::on_flip`.
+        #[ink(message, selector = 0xd9856ac9)]
+        pub fn on_flip(&mut self, new_value: bool) {
+            self.last_value = Some(new_value);
+            self.notifications = self.notifications.saturating_add(1);
+        }
+
+        /// Returns the value reported by the latest notification, if any.
+        #[ink(message)]
+        pub fn last_value(&self) -> Option<bool> {
+            self.last_value
+        }
+
+        /// Returns how many notifications have been received.
+        #[ink(message)]
+        pub fn notifications(&self) -> u32 {
+            self.notifications
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// We test that notifications are recorded.
+        #[ink::test]
+        fn on_flip_works() {
+            let mut observer = Observer::new();
+            assert_eq!(observer.last_value(), None);
+            observer.on_flip(true);
+            observer.on_flip(false);
+            assert_eq!(observer.last_value(), Some(false));
+            assert_eq!(observer.notifications(), 2);
+        }
+    }
+}